@@ -11,7 +11,7 @@
 //! Here is a short example:
 //!
 //! ```rust
-//! # use trait_gen::trait_gen;
+//! # use conditional_trait_gen::trait_gen;
 //! # trait MyLog { fn my_log2(self) -> u32; }
 //! #[trait_gen(T -> u8, u16, u32, u64, u128)]
 //! impl MyLog for T {
@@ -45,7 +45,7 @@
 //! is given first, followed by a right arrow (`->`) and a list of type arguments.
 //!
 //! ```rust
-//! # use trait_gen::trait_gen;
+//! # use conditional_trait_gen::trait_gen;
 //! # struct Type1; struct Type2; struct Type3;
 //! # trait Trait {}
 //! #[trait_gen(T -> Type1, Type2, Type3)]
@@ -66,13 +66,23 @@
 //! is not a valid floating-point literal.
 //!
 //! Finally, the actual type replaces any `${T}` occurrence in doc comments, macros, and string literals.
+//! The arguments of macros are otherwise left untouched, except for the `assert!` family of
+//! macros (`assert!`, `assert_eq!`, `assert_ne!` and their `debug_` versions), whose expressions
+//! are substituted like the rest of the code, `addr_of!` and `addr_of_mut!`, whose place
+//! expression is substituted too, `format_args!`, `write!` and `writeln!`, whose
+//! arguments are substituted except the format string, `vec!`, whose elements and length are
+//! substituted, and `matches!`, whose patterns and guard are substituted too, including all the
+//! alternatives of or-patterns like `T::A | T::B`.
 //!
 //! _Notes:_
 //! - _Using the letter "T" is not mandatory; any type path will do. For example, `gen::Type` is fine
-//! too. But to make it easy to read and similar to a generic implementation, short upper-case identifiers
-//! are preferred._
+//!   too. But to make it easy to read and similar to a generic implementation, short upper-case identifiers
+//!   are preferred._
 //! - _Two or more attributes can be chained to generate all the combinations._
 //! - _`trait_gen` can be used on type implementations too._
+//! - _The array lengths in `where` clauses are substituted too, like `[(); T::BITS as usize]: Sized`.
+//!   Since they don't depend on generic parameters once substituted, they don't require the
+//!   unstable `generic_const_exprs` feature, which is only needed for their generic version._
 //!
 //! For more examples, look at the [README.md](https://github.com/blueglyph/trait_gen/blob/v0.2.0/README.md)
 //! or the crate [integration tests](https://github.com/blueglyph/trait_gen/blob/v0.2.0/tests/integration.rs).
@@ -83,7 +93,7 @@
 //! may be more confusing to read:
 //!
 //! ```rust
-//! # use trait_gen::trait_gen;
+//! # use conditional_trait_gen::trait_gen;
 //! # struct Type1; struct Type2; struct Type3;
 //! # trait Trait {}
 //! #[trait_gen(Type1, Type2, Type3)]
@@ -95,7 +105,7 @@
 //! is a shortcut for the equivalent attribute with the other format:
 //!
 //! ```rust
-//! # use trait_gen::trait_gen;
+//! # use conditional_trait_gen::trait_gen;
 //! # struct Type1; struct Type2; struct Type3;
 //! # trait Trait {}
 //! #[trait_gen(Type1 -> Type1, Type2, Type3)]
@@ -117,7 +127,7 @@
 //! Here, `in` is used instead of an arrow `->`, and the argument types must be between square brackets:
 //!
 //! ```rust
-//! # use trait_gen::trait_gen;
+//! # use conditional_trait_gen::trait_gen;
 //! # trait MyLog { fn my_log2(self) -> u32; }
 //! # #[cfg(feature = "in_format")]
 //! #[trait_gen(T in [u8, u16, u32, u64, u128])]
@@ -133,15 +143,1808 @@
 //! Using this format issues 'deprecated' warnings that you can turn off by adding the `#![allow(deprecated)]`
 //! directive at the top of the file or by adding `#[allow(deprecated)]` where the generated code is used.
 //!
+//! ## Type Attributes
+//!
+//! A type can be followed by attributes, which are only attached to the top-level items of the
+//! code generated for that type. For example, a default implementation can be provided for `u16`,
+//! unless a feature provides a better one:
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Backend { fn backend() -> &'static str; }
+//! #[trait_gen(T -> u8, u16 #[cfg(not(feature = "fast_u16"))], u32)]
+//! impl Backend for T {
+//!     fn backend() -> &'static str {
+//!         "generic ${T}"
+//!     }
+//! }
+//!
+//! #[cfg(feature = "fast_u16")]
+//! impl Backend for u16 {
+//!     fn backend() -> &'static str {
+//!         "specific u16"
+//!     }
+//! }
+//! ```
+//!
+//! Any attribute can be given, for example `#[deprecated]` on a type whose support is phased
+//! out, and the types can have different attributes: `T -> u8 #[cfg(feature = "a")], u16
+//! #[deprecated]`. The items derived from all the types by some options, like the methods of a
+//! visitor trait, only get the `cfg` attributes.
+//!
+//! ## Options
+//!
+//! Options can be given after the types, separated from them by a semicolon:
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait MyLog { fn my_log2(self) -> u32; }
+//! #[trait_gen(T -> u8, u16, u32; dispatch_macro = int_type)]
+//! impl MyLog for T {
+//!     fn my_log2(self) -> u32 {
+//!         T::BITS - 1 - self.leading_zeros()
+//!     }
+//! }
+//! ```
+//!
+//! An option only applies to the attribute it's given in. When attributes are chained, it's
+//! generally best to put the options in the first attribute, where the types are final.
+//!
+//! ### `types_from = Trait`
+//!
+//! Adds to the list the types given by the associated types of the implementation of `Trait`
+//! found in the code, so that the list can be written once in a trait implementation and used
+//! elsewhere in the same code. Since the types can't be resolved by a procedural macro, the
+//! extraction is purely syntactic:
+//!
+//! - there must be exactly one `impl Trait for Type` in the code, either at the top level or in
+//!   a module with an inline content (`Trait` is compared as written, so it must be the same path
+//!   as in the option);
+//! - each of its associated types must be defined by a type path, like `type Length = Meter;`,
+//!   and those paths are added after the explicit types of the list, in the order they're given;
+//! - the implementation is only kept in the code generated for the first type.
+//!
+//! The explicit list can be empty (`T -> ; types_from = Trait`). Since the implementation must be
+//! in the code, the attribute is usually placed before a module, in which case `anon_const` is
+//! required to avoid duplicate modules.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Liter(f64);
+//! trait Units { type Length; type Volume; }
+//! trait Name { fn name(&self) -> &'static str; }
+//! struct Registry;
+//!
+//! #[trait_gen(T -> ; types_from = Units, anon_const)]
+//! mod units {
+//!     use super::*;
+//!
+//!     impl Units for Registry {
+//!         type Length = Meter;
+//!         type Volume = Liter;
+//!     }
+//!
+//!     impl Name for T {
+//!         fn name(&self) -> &'static str {
+//!             "${T}"
+//!         }
+//!     }
+//! }
+//!
+//! # fn main() {
+//! assert_eq!(Liter(1.0).name(), "Liter");
+//! # }
+//! ```
+//!
+//! ### `dispatch_macro = name`
+//!
+//! Generates a `macro_rules! name` which maps the name of each type, as a string literal, to
+//! that type. The name is the type as it appears in `${T}` substitutions. The macro can be used
+//! wherever a type is expected:
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait MyLog { fn my_log2(self) -> u32; }
+//! #[trait_gen(T -> u8, u16, u32; dispatch_macro = int_type)]
+//! impl MyLog for T {
+//!     fn my_log2(self) -> u32 {
+//!         T::BITS - 1 - self.leading_zeros()
+//!     }
+//! }
+//!
+//! let x: int_type!("u16") = 1000;
+//! assert_eq!(<int_type!("u32")>::my_log2(16), 4);
+//! ```
+//!
+//! ### `layout_table = name`, `layout_table = vis name`
+//!
+//! Generates a constant table `name` of type `&[(&str, usize, usize)]`, which gives the name,
+//! size and alignment of each type, in the order of the list. The name is the type as it appears
+//! in `${T}` substitutions. The table has the given visibility, private by default, and can be
+//! used in constant expressions.
+//!
+//! The row of each type has the `cfg` attributes of the type, so the types removed in the current
+//! configuration are missing from the table.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Zero { fn zero() -> Self; }
+//! #[trait_gen(T -> u8, u32, [u16; 3]; layout_table = pub(crate) LAYOUTS)]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//!
+//! assert_eq!(LAYOUTS, [("u8", 1, 1), ("u32", 4, 4), ("[u16;3]", 6, 2)]);
+//! ```
+//!
+//! ### `sorted_names = NAME`, `sorted_names = vis NAME`
+//!
+//! Generates a constant table `NAME` of type `&[(&str, usize)]`, which gives the name of each type
+//! and its position in the list, sorted by name when the attribute is expanded. The name is the
+//! type as it appears in `${T}` substitutions. The table has the given visibility, private by
+//! default.
+//!
+//! Since the names are sorted in the order of `str::cmp`, the position of a type can be found
+//! from its name with a binary search:
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Zero { fn zero() -> Self; }
+//! #[trait_gen(T -> u8, i64, f32, bool; sorted_names = SORTED_NAMES)]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//!
+//! fn index_of(name: &str) -> Option<usize> {
+//!     SORTED_NAMES
+//!         .binary_search_by(|(n, _)| n.cmp(&name))
+//!         .ok()
+//!         .map(|i| SORTED_NAMES[i].1)
+//! }
+//!
+//! assert_eq!(SORTED_NAMES, [("bool", 3), ("f32", 2), ("i64", 1), ("u8", 0)]);
+//! assert_eq!(index_of("f32"), Some(2));
+//! assert_eq!(index_of("u16"), None);
+//! ```
+//!
+//! The types can't have `cfg` attributes, and their names must be distinct.
+//!
+//! ### `name_fn = name`, `name_fn = vis name`
+//!
+//! Generates a `const fn name(index: usize) -> &'static str` giving the name of the type at
+//! position `index` in the list, as it appears in `${T}` substitutions. The function has the
+//! given visibility, private by default, and can be evaluated in constant expressions.
+//!
+//! It returns an empty string when `index` is out of range, or when the type at that position is
+//! removed by a `cfg` attribute.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Zero { fn zero() -> Self; }
+//! #[trait_gen(T -> u8, i64, [f32; 2]; name_fn = pub(crate) name_of)]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//!
+//! const SECOND: &str = name_of(1);
+//!
+//! assert_eq!(SECOND, "i64");
+//! assert_eq!(name_of(2), "[f32;2]");
+//! assert_eq!(name_of(3), "");
+//! ```
+//!
+//! ### `visitor = name`, `visitor = vis name`, `visit_method = "template"`
+//!
+//! Generates a visitor trait `name` with one method per type, which takes a reference to a value
+//! of that type and does nothing by default, so the implementations only need to define the
+//! methods they're interested in. The trait has the given visibility, private by default.
+//!
+//! The method names are given by the `visit_method` template, in which `${T}` is replaced by
+//! the type identifier in snake case, without its path and generic arguments. The default
+//! template is `"visit_${T}"`, which gives `visit_meter` for `Meter`, and `visit_big_int` for
+//! `num::BigInt<u8>`. The types must be type paths, and the methods have the `cfg` attributes
+//! of their type.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! // generates
+//! // trait LengthVisitor {
+//! //     fn visit_meter(&mut self, _value: &Meter) {}
+//! //     fn visit_foot(&mut self, _value: &Foot) {}
+//! // }
+//! #[trait_gen(T -> Meter, Foot; visitor = LengthVisitor)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! struct FootCounter(usize);
+//!
+//! impl LengthVisitor for FootCounter {
+//!     fn visit_foot(&mut self, _value: &Foot) {
+//!         self.0 += 1;
+//!     }
+//! }
+//!
+//! let mut counter = FootCounter(0);
+//! counter.visit_meter(&Meter(1.0));
+//! counter.visit_foot(&Foot(1.0));
+//! assert_eq!(counter.0, 1);
+//! ```
+//!
+//! ### `tag_const`, `tag_const = vis "template"`, `tag_base = value`
+//!
+//! Generates a `u32` constant for each type, whose value is the position of the type in the
+//! list, starting at the `tag_base` value (0 by default), for example to give a stable
+//! discriminant to the types in FFI code. The constant names are given by the template, in
+//! which `${T}` is replaced by the type identifier in upper snake case, without its path and
+//! generic arguments. The default template is `"${T}_TAG"`, which gives `BIG_INT_TAG` for
+//! `num::BigInt<u8>`. Either the visibility or the template can be omitted; the constants are
+//! private by default. The types must be type paths, and the constants have the `cfg`
+//! attributes of their type.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! // generates `pub const METER_KIND: u32 = 1;` and `pub const FOOT_KIND: u32 = 2;`
+//! #[trait_gen(T -> Meter, Foot; tag_const = pub "${T}_KIND", tag_base = 1)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! assert_eq!((METER_KIND, FOOT_KIND), (1, 2));
+//! ```
+//!
+//! ### `tag_trait = Name`, `tag_trait = vis Name`
+//!
+//! Generates a trait `Name` with an associated constant `const TAG: u32`, and implements it for
+//! each type, with the position of the type in the list as value, starting at the `tag_base`
+//! value (0 by default), like `tag_const`. The tags are thus available at compile time in
+//! generic code, with a `T: Name` bound. The trait has the given visibility, private by default,
+//! and its implementations have the `cfg` attributes of their type.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! // generates `trait HasTag { const TAG: u32; }`, with `const TAG: u32 = 10;` for `Meter`
+//! // and `const TAG: u32 = 11;` for `Foot`
+//! #[trait_gen(T -> Meter, Foot; tag_trait = HasTag, tag_base = 10)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! fn tag_of<U: HasTag>(_value: &U) -> u32 {
+//!     U::TAG
+//! }
+//!
+//! assert_eq!(tag_of(&Foot(1.0)), 11);
+//! ```
+//!
+//! ### `bit_const`, `bit_const = vis "template"`, `bit_type = type`
+//!
+//! Generates a constant for each type, whose value has a single bit set at the position of the
+//! type in the list, so sets of types can be represented by a mask. The constants are of type
+//! `u32` by default, or of the unsigned integer type given by `bit_type`; there can't be more
+//! types than bits. The names are given by the template like for `tag_const`, with `"${T}_BIT"`
+//! by default. Either the visibility or the template can be omitted; the constants are private
+//! by default. The types must be type paths, and the constants have the `cfg` attributes of
+//! their type.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64); struct Mile(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! // generates `pub const METER_BIT: u8 = 1 << 0;`, `pub const FOOT_BIT: u8 = 1 << 1;`
+//! // and `pub const MILE_BIT: u8 = 1 << 2;`
+//! #[trait_gen(T -> Meter, Foot, Mile; bit_const = pub, bit_type = u8)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! let imperial = FOOT_BIT | MILE_BIT;
+//! assert_eq!(imperial, 0b110);
+//! ```
+//!
+//! ### `error_enum = vis Name(Data)`, `error_variant = "template"`
+//!
+//! Generates an error enum `Name`, deriving `Debug`, with a variant for each type, for example to
+//! report which type failed to be parsed. The variant names are given by the `error_variant`
+//! template, in which `${T}` is replaced by the type identifier with its first letter in upper
+//! case, without its path and generic arguments. The default template is `"${T}"`, which gives
+//! `Meter` for `Meter` and `U8` for `u8`.
+//!
+//! The variants carry the optional `Data` type, in which the generic argument is replaced by the
+//! type of the variant. If that type depends on the generic argument, like `T` or `Box<T>`, the
+//! conversions `impl From<Data> for Name` are generated too, so the `?` operator or `into()` can
+//! be used. The data types of the variants must then all be different, and associated types like
+//! `<T as FromStr>::Err` can't be used since the compiler sees them as potentially conflicting.
+//! Without `Data`, the variants don't carry any data. The enum has the given visibility, private
+//! by default, and its variants have the `cfg` attributes of their type. The types must be type
+//! paths.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! trait Percent: Sized {
+//!     fn percent(self) -> Result<Self, RangeError>;
+//! }
+//!
+//! // generates `enum RangeError { InvalidU8(u8), InvalidF32(f32) }` and the `From` conversions
+//! #[trait_gen(T -> u8, f32; error_enum = RangeError(T), error_variant = "Invalid${T}")]
+//! impl Percent for T {
+//!     fn percent(self) -> Result<Self, RangeError> {
+//!         if self <= 100 as T { Ok(self) } else { Err(self.into()) }
+//!     }
+//! }
+//!
+//! assert!(matches!(150_u8.percent(), Err(RangeError::InvalidU8(150))));
+//! assert_eq!(12.5_f32.percent().unwrap(), 12.5);
+//! ```
+//!
+//! ### `type_enum = Name`, `type_enum = vis Name`
+//!
+//! Generates an enum `Name` with a unit variant for each type, for example to encode the type
+//! of a value in a compact way. The variant names are the type identifiers with their first
+//! letter in upper case, like for `error_enum`, and their discriminant is the index of the type
+//! in the order given by `tag_order`: its position in the list by default, or in the list sorted
+//! by type name with `tag_order = alphabetical`. The enum derives `Clone`, `Copy`, `Debug`,
+//! `PartialEq`, `Eq` and `Hash`, and has the following methods:
+//! - `fn index_of(&self) -> usize` gives the index of the variant type, which is its discriminant;
+//! - `fn from_index(index: usize) -> Option<Self>` gives the variant at that index;
+//! - `fn name_of_index(index: usize) -> Option<&'static str>` gives the name of the type at that
+//!   index, as it appears in `${T}` substitutions.
+//!
+//! The enum and its methods have the given visibility, private by default, and the variants have
+//! the `cfg` attributes of their type; a disabled type keeps its index, so the indices are
+//! stable. The types must be type paths.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! // generates `pub enum Unit { Meter = 0, Foot = 1 }` and its methods
+//! #[trait_gen(T -> Meter, Foot; type_enum = pub Unit)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! assert_eq!(Unit::Foot.index_of(), 1);
+//! assert_eq!(Unit::from_index(1), Some(Unit::Foot));
+//! assert_eq!(Unit::name_of_index(0), Some("Meter"));
+//! ```
+//!
+//! ### `enum_all`
+//!
+//! Adds a method `fn all() -> impl Iterator<Item = Self>` to the enum generated by `type_enum`,
+//! which iterates over its variants in the order of their index, for example to enumerate the
+//! types in tests or in a user interface. Since the variants don't carry any value, no
+//! constructor is required from the types. The variants of the types disabled by a `cfg`
+//! attribute are skipped.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64); struct Mile(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! #[trait_gen(T -> Meter, Foot, Mile; type_enum = Unit, enum_all)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! let units = Unit::all().collect::<Vec<_>>();
+//! assert_eq!(units, [Unit::Meter, Unit::Foot, Unit::Mile]);
+//! ```
+//!
+//! ### `enum_try_from`
+//!
+//! Implements `TryFrom<usize>` for the enum generated by `type_enum`, giving the variant at the
+//! index like `from_index`, so that `index_of` and `try_into` make a roundtrip. The conversion
+//! fails with the index as error if it's out of range, or if the type at that index is disabled
+//! by a `cfg` attribute. Since the variants don't carry any value, no constructor is required
+//! from the types.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! #[trait_gen(T -> Meter, Foot; type_enum = Unit, enum_try_from)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! assert_eq!(Unit::try_from(Unit::Foot.index_of()), Ok(Unit::Foot));
+//! let unit: Result<Unit, usize> = 5.try_into();
+//! assert_eq!(unit, Err(5));
+//! ```
+//!
+//! ### `tag_order = order`
+//!
+//! Gives the order of the types which determines their values in `tag_const`, `tag_trait` and
+//! `type_enum`:
+//! - `list`, the default, is the order in which the types are given;
+//! - `alphabetical` is the order of the type names, as they appear in `${T}` substitutions.
+//!
+//! With `alphabetical`, reordering the types in the attribute doesn't change the values, which
+//! keeps them stable in serialized data; only adding or removing types can change them.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64); struct Mile(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! #[trait_gen(T -> Meter, Foot, Mile; type_enum = Unit, tag_const, tag_order = alphabetical)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! assert_eq!((FOOT_TAG, METER_TAG, MILE_TAG), (0, 1, 2));
+//! assert_eq!(Unit::Meter as u8, 1);
+//! assert_eq!(Unit::from_index(2), Some(Unit::Mile));
+//! ```
+//!
+//! ### `marker_trait = name`, `marker_trait = vis name`
+//!
+//! Generates a marker trait `name`, without any item, and implements it for each type. It can
+//! be declared as a supertrait of the implemented trait, so that only the generated types can
+//! implement it, and used as a bound on any of the generated types. The trait has the given
+//! visibility, private by default, and its implementations have the `cfg` attributes of their
+//! type.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! // `Unit` is generated, with `impl Unit for Meter {}` and `impl Unit for Foot {}`
+//! trait Length: Unit {
+//!     fn meters(&self) -> f64;
+//! }
+//!
+//! #[trait_gen(T -> Meter, Foot; marker_trait = Unit)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         if "${T}" == "Foot" { self.0 * 0.3048 } else { self.0 }
+//!     }
+//! }
+//!
+//! fn is_unit<U: Unit>(_value: &U) -> bool {
+//!     true
+//! }
+//!
+//! assert!(is_unit(&Foot(1.0)));
+//! ```
+//!
+//! ### `as_any = Name`, `as_any = vis Name`
+//!
+//! Generates a trait `Name` with an `as_any(&self) -> &dyn Any` method, and implements it for
+//! each type. The trait objects `dyn Name` get downcasting helpers based on the type names, as
+//! they appear in `${T}` substitutions:
+//! - `is_named(&self, name: &str) -> bool` checks if the value has the type of the given name;
+//! - `type_name(&self) -> Option<&'static str>` gives the name of the type of the value;
+//! - `downcast_ref::<V>(&self) -> Option<&V>` gives a reference to the value if it has type `V`.
+//!
+//! The trait and the helpers have the given visibility, private by default, and the
+//! implementations have the `cfg` attributes of their type. Since `Any` is a supertrait, the
+//! types must be `'static`.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Zero { fn zero() -> Self; }
+//! #[trait_gen(T -> u8, String, Vec<f32>; as_any = pub(crate) Erased)]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//!
+//! let values: Vec<Box<dyn Erased>> = vec![Box::new(5_u8), Box::new(String::from("a"))];
+//! assert!(values[0].is_named("u8"));
+//! assert_eq!(values[1].type_name(), Some("String"));
+//! assert_eq!(values[1].downcast_ref::<String>().unwrap(), "a");
+//! assert_eq!(values[0].downcast_ref::<Vec<f32>>(), None);
+//! ```
+//!
+//! ### `doc_table = name`, `doc_table = vis name`
+//!
+//! Generates an empty module `name`, whose documentation is a markdown table of the types,
+//! giving their index in the list, their name as it appears in `${T}` substitutions, and their
+//! `cfg` attributes if any of the types has some. It summarizes the generated surface in the
+//! documentation of the crate, for example with a link from the trait documentation. The module
+//! has the given visibility, private by default.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Zero { fn zero() -> Self; }
+//! // generates:
+//! // /// Types generated for `T`:
+//! // ///
+//! // /// | # | Type |
+//! // /// |---|------|
+//! // /// | 0 | `u8` |
+//! // /// | 1 | `[u16;3]` |
+//! // pub mod zero_types {}
+//! #[trait_gen(T -> u8, [u16; 3]; doc_table = pub zero_types)]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//! ```
+//!
+//! ### `factory = name`, `factory = vis name`
+//!
+//! Generates a function `name(type_name: &str) -> Option<Box<dyn Trait>>`, which creates the
+//! default value of the type named `type_name` as a boxed object of the trait implemented by the
+//! code, for example to instantiate the types from a configuration file. The name must be
+//! exactly the type as it appears in `${T}` substitutions, and the function returns `None` for
+//! any other name.
+//!
+//! The types must implement `Default`, which is the constructor used by the function, and the
+//! trait must be object-safe. The implementations of the code must all be of the same trait,
+//! which can't depend on the generic argument. The function has the given visibility, private
+//! by default, and the types have their `cfg` attributes. The function can't be `const`, since
+//! it allocates the object.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # #[derive(Default)] struct Zip; #[derive(Default)] struct Tar;
+//! trait Archive {
+//!     fn extension(&self) -> &'static str;
+//! }
+//!
+//! #[trait_gen(T -> Zip, Tar; factory = pub make_archive)]
+//! impl Archive for T {
+//!     fn extension(&self) -> &'static str {
+//!         if "${T}" == "Zip" { "zip" } else { "tar" }
+//!     }
+//! }
+//!
+//! assert_eq!(make_archive("Tar").unwrap().extension(), "tar");
+//! assert!(make_archive("Rar").is_none());
+//! ```
+//!
+//! ### `ctor_array = NAME`, `ctor_array = vis NAME`
+//!
+//! Generates a constant array `NAME: [fn() -> Box<dyn Trait>; N]` of constructors, one for each
+//! type in the order of the list, which create the default value of the type as a boxed object
+//! of the trait implemented by the code. It allows the construction of the types by index, for
+//! example from the tag given by `tag_const` or the index of the `type_enum` variants.
+//!
+//! Like for `factory`, the types must implement `Default`, the trait must be object-safe, and the
+//! implementations of the code must all be of the same trait, which can't depend on the generic
+//! argument. The types can't have `cfg` attributes, since the length of the array must be the
+//! same in all configurations. The constant has the given visibility, private by default.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # #[derive(Default)] struct Zip; #[derive(Default)] struct Tar;
+//! trait Archive {
+//!     fn extension(&self) -> &'static str;
+//! }
+//!
+//! #[trait_gen(T -> Zip, Tar; ctor_array = pub ARCHIVES)]
+//! impl Archive for T {
+//!     fn extension(&self) -> &'static str {
+//!         if "${T}" == "Zip" { "zip" } else { "tar" }
+//!     }
+//! }
+//!
+//! assert_eq!(ARCHIVES.len(), 2);
+//! assert_eq!(ARCHIVES[1]().extension(), "tar");
+//! ```
+//!
+//! ### `blanket_impl = Trait { items }`
+//!
+//! Generates, once for all the types, a blanket implementation of `Trait` with the given items
+//! for all the types implementing the trait of the code, so that a second trait can be built on
+//! the generated implementations. The implementations of the code must all be of the same trait,
+//! which can't depend on the generic argument, and the items refer to the implementing type as
+//! `Self`.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! trait Length {
+//!     fn meters(&self) -> f64;
+//! }
+//!
+//! trait Describe {
+//!     fn describe(&self) -> String;
+//! }
+//!
+//! // generates `impl<B: Length> Describe for B { ... }`, where `B` is a hidden parameter
+//! #[trait_gen(T -> Meter, Foot; blanket_impl = Describe {
+//!     fn describe(&self) -> String {
+//!         format!("{} m", self.meters())
+//!     }
+//! })]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         if "${T}" == "Foot" { self.0 * 0.3048 } else { self.0 }
+//!     }
+//! }
+//!
+//! assert_eq!(Foot(10.0).describe(), "3.048 m");
+//! ```
+//!
+//! ### `roundtrip_test = name(check)`
+//!
+//! Generates a single test `name`, only compiled with `cfg(test)`, which calls the generic
+//! function `check` with each type as its type argument: `check::<Type1>()`, `check::<Type2>()`,
+//! and so on. The function is written by the user, typically to assert that a value survives a
+//! roundtrip through the implemented trait, and the calls have the `cfg` attributes of their
+//! type.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! trait Bytes: Sized {
+//!     fn to_bytes(&self) -> Vec<u8>;
+//!     fn from_bytes(bytes: &[u8]) -> Self;
+//! }
+//!
+//! // generates `#[cfg(test)] #[test] fn roundtrip() { check::<u16>(); check::<u32>(); }`
+//! #[trait_gen(T -> u16, u32; roundtrip_test = roundtrip(check))]
+//! impl Bytes for T {
+//!     fn to_bytes(&self) -> Vec<u8> {
+//!         self.to_le_bytes().to_vec()
+//!     }
+//!     fn from_bytes(bytes: &[u8]) -> Self {
+//!         T::from_le_bytes(bytes.try_into().unwrap())
+//!     }
+//! }
+//!
+//! # #[allow(dead_code)]
+//! fn check<B: Bytes + From<u8> + PartialEq + std::fmt::Debug>() {
+//!     let value = B::from(42);
+//!     assert_eq!(B::from_bytes(&value.to_bytes()), value);
+//! }
+//! ```
+//!
+//! ### `strategies = name(Type => expr)`, `strategy_feature = "feature"`
+//!
+//! Generates a module `name`, only compiled with `cfg(test)`, with a function for each type
+//! returning `expr` as a `Type`, in which the generic argument is substituted by the type. The
+//! functions are named after the type identifiers in snake case, and have the `cfg` attributes of
+//! their type. They're meant to scaffold property-based tests, by creating the value strategies or
+//! generators of a testing crate like `proptest`, without depending on any of them.
+//!
+//! With `strategy_feature = "feature"`, the module also requires the given feature of the crate,
+//! so that the testing crate can be an optional dependency.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Zero { fn zero() -> Self; }
+//! // with proptest, for example: `strategies = arb(impl Strategy<Value = T> => any::<T>())`
+//! #[trait_gen(T -> u16, i32; strategies = samples(impl Iterator<Item = T> => (1..4_u8).map(T::from)))]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//!
+//! // in the tests of the crate:
+//! // assert!(samples::i32().all(|value| value > i32::zero()));
+//! ```
+//!
+//! ### `doctests = name("code")`
+//!
+//! Generates a module `name`, only compiled with `cfg(doctest)`, with a unit structure for each
+//! type documented by a doctest of the code, in which `${T}` is replaced by the type like in the
+//! doc comments. It checks that the API works with each type, for example to keep the examples
+//! of the documentation honest, without adding them to the documentation of the implementations
+//! like `doc_example`. The structures are named after the type identifiers with their first
+//! letter in upper case, so that a failure shows the type, like `name::U8`, and they have the
+//! `cfg` attributes of their type. The types must be type paths.
+//!
+//! The doctests are run by `cargo test` like any other doctest, so the code must refer to the
+//! items through the path of the crate.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! pub trait Zero {
+//!     fn zero() -> Self;
+//! }
+//!
+//! // generates the doctests `zero_doctests::U8` and `zero_doctests::I64`
+//! #[trait_gen(T -> u8, i64;
+//!     doctests = zero_doctests("assert_eq!(<${T} as my_crate::Zero>::zero(), 0);"))]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//! ```
+//!
+//! ### `coherence_test = name`
+//!
+//! Generates a single test `name`, only compiled with `cfg(test)`, which checks the generated
+//! implementations:
+//! - each type implements the trait of the code, with its substituted generic arguments;
+//! - the copies of each implementation are for different self types or trait type arguments,
+//!   as given by [`type_name`](std::any::type_name). This catches two types of the list that are
+//!   aliases of the same type, which the compiler only rejects if their implementations
+//!   conflict.
+//!
+//! The implementations can't have generic parameters, and the checks have the `cfg` attributes
+//! of their type.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! // generates `#[cfg(test)] #[test] fn length_coherence() { ... }`
+//! #[trait_gen(T -> Meter, Foot; coherence_test = length_coherence)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//! ```
+//!
+//! ### `type_id_map = name(method)`, `type_id_map = vis name(method)`
+//!
+//! Generates a function `name` giving a map from the [`TypeId`](std::any::TypeId) of each type
+//! to a function pointer on its `method`, for a dynamic dispatch on the type IDs. The map is a
+//! `HashMap<TypeId, fn(...) -> ...>` built on the first call and kept for the following ones, so
+//! the function returns a `&'static` reference to it. The function has the given visibility,
+//! private by default, and the entries have the `cfg` attributes of their type.
+//!
+//! The pointers must have the same type, so the signature of the method is taken from the code
+//! and can't differ between the types: the method can't take `self`, have type or const
+//! parameters, or refer to `Self` or to the generic argument. The types must be `'static`, as
+//! required by `TypeId`.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # use std::any::TypeId;
+//! trait Describe {
+//!     fn describe(bits: u32) -> String;
+//! }
+//!
+//! // generates `fn describers() -> &'static HashMap<TypeId, fn(u32) -> String> { ... }`
+//! #[trait_gen(T -> u8, i64; type_id_map = describers(describe))]
+//! impl Describe for T {
+//!     fn describe(bits: u32) -> String {
+//!         format!("${T} ({} bits)", bits)
+//!     }
+//! }
+//!
+//! let describe = describers()[&TypeId::of::<i64>()];
+//! assert_eq!(describe(64), "i64 (64 bits)");
+//! ```
+//!
+//! ### `forward_arc_mutex`
+//!
+//! Generates, along with each trait implementation, an implementation of the same trait for
+//! `Arc<Mutex<T>>`, which locks the mutex and forwards the calls to `T`. Associated types and
+//! constants are forwarded too.
+//!
+//! Since the lock is released when a method returns, the methods must take `&self` and return
+//! owned values: the returned type can't have any lifetime other than `'static`, like
+//! `Option<&T>` or `Cow<'_, str>`. They can't be `async`, and their signature can't include a
+//! bare `Self`, which would designate `Arc<Mutex<T>>` in the forwarding implementation, nor can
+//! the type of a constant; `Self::Output` is fine.
+//! The forwarding implementation panics if the mutex is poisoned.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # use std::sync::{Arc, Mutex};
+//! # struct Meter(f64); struct Foot(f64);
+//! trait Length {
+//!     fn meters(&self) -> f64;
+//! }
+//!
+//! #[trait_gen(T -> Meter, Foot; forward_arc_mutex)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0 * if "${T}" == "Foot" { 0.3048 } else { 1.0 }
+//!     }
+//! }
+//!
+//! let shared = Arc::new(Mutex::new(Foot(10.0)));
+//! assert_eq!(shared.meters(), 3.048);
+//! ```
+//!
+//! ```rust, compile_fail
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! trait Length {
+//!     fn value(&self) -> Option<&f64>;
+//! }
+//!
+//! // error: method 'value' must return an owned value to be forwarded by forward_arc_mutex
+//! #[trait_gen(T -> Meter, Foot; forward_arc_mutex)]
+//! impl Length for T {
+//!     fn value(&self) -> Option<&f64> {
+//!         Some(&self.0)
+//!     }
+//! }
+//! ```
+//!
+//! ### `forward`
+//!
+//! Replaces the body of the trait methods made of a single `forward!(Target)` or
+//! `forward!(Target, receiver)` marker by a call to the same method of the `Target`
+//! implementation, typically a generic implementation for a wrapper type:
+//!
+//! ```text
+//! fn method(&self, arg1: A, arg2: B) -> R {
+//!     <Target as Trait>::method(receiver, arg1, arg2)
+//! }
+//! ```
+//!
+//! The types are substituted in the arguments of the marker. The receiver expression is required
+//! when the method has a receiver, since `self` has another type in the target implementation,
+//! and the other arguments are forwarded by name, so they must be identifiers. The signature of
+//! the method must otherwise be compatible with the target's, which the compiler checks.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! struct Wrapper<U>(U);
+//!
+//! trait Describe {
+//!     fn describe(&self, prefix: &str) -> String;
+//! }
+//!
+//! impl<U: Into<f64> + Copy> Describe for Wrapper<U> {
+//!     fn describe(&self, prefix: &str) -> String {
+//!         format!("{prefix}{}", self.0.into())
+//!     }
+//! }
+//!
+//! #[trait_gen(T -> u8, f32; forward)]
+//! impl Describe for T {
+//!     fn describe(&self, prefix: &str) -> String {
+//!         forward!(Wrapper<T>, &Wrapper(*self))
+//!     }
+//! }
+//!
+//! assert_eq!(1.5_f32.describe("> "), "> 1.5");
+//! ```
+//!
+//! ### `self_static`
+//!
+//! Adds a `Self: 'static` predicate to the `where` clause of each generated implementation,
+//! which is required by some methods, like those converting `&self` into `&dyn Any`. The
+//! predicate is added to the existing ones, so the template can declare its own bounds and
+//! lifetimes; the implementations are then restricted to the `'static` lifetimes.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # use std::any::Any;
+//! trait AsAny {
+//!     fn as_any(&self) -> &dyn Any;
+//! }
+//!
+//! #[trait_gen(T -> u8, String; self_static)]
+//! impl AsAny for T {
+//!     fn as_any(&self) -> &dyn Any {
+//!         self
+//!     }
+//! }
+//!
+//! assert_eq!(5_u8.as_any().downcast_ref::<u8>(), Some(&5));
+//! ```
+//!
+//! ### `replace_self`
+//!
+//! Replaces `Self` by the implementing type in the bodies of the methods, so that the generated
+//! code shows the concrete types, for example in the output of `cargo expand`. The signatures
+//! are unchanged, and `Self` is intentionally preserved where the replacement could change the
+//! meaning of the code or isn't valid:
+//! - in the projections on the associated types of the implementation, like `Self::Output`,
+//!   which would be ambiguous with the concrete type;
+//! - in the nested items, like functions or implementations declared in a method, where `Self`
+//!   may designate another type;
+//! - in the arguments of macros;
+//! - in paths like `Self::new()` or `Self { .. }` when the implementing type isn't a path, like
+//!   `&u8` or `(u8, u16)`, although a bare `Self` type is replaced.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # #[derive(Debug, PartialEq)] struct Meter(f64); #[derive(Debug, PartialEq)] struct Foot(f64);
+//! trait Double {
+//!     type Output;
+//!     fn double(&self) -> Self::Output;
+//! }
+//!
+//! // generates `let value: Meter = Meter(self.0 * 2.0); Self::Output::from(value)`, ...
+//! #[trait_gen(T -> Meter, Foot; replace_self)]
+//! impl Double for T {
+//!     type Output = T;
+//!     fn double(&self) -> Self::Output {
+//!         let value: Self = Self(self.0 * 2.0);
+//!         Self::Output::from(value)
+//!     }
+//! }
+//!
+//! assert_eq!(Foot(1.5).double(), Foot(3.0));
+//! ```
+//!
+//! ### `builder`
+//!
+//! Replaces each structure of the code by a builder of the types. The structure is a template
+//! which describes the fields of the types; its name is appended to the type name, with its first
+//! letter in upper case, to make the builder's name, so `struct Builder` generates `MeterBuilder`
+//! for `Meter`. The types must be type paths, and their fields must all be in the template.
+//!
+//! Each builder implements `Default`, and has the following methods, with the same visibility
+//! as the template:
+//! * `new()` creates the builder with no field set,
+//! * a setter for each field, which takes the field value by the same name,
+//! * `build()` creates the value, or returns an error message if a field is missing.
+//!
+//! A `builder()` method is added to the types, which creates their builder. The fields of type
+//! `Option<F>` are optional: their setter takes an `F` value, and they're `None` if not set.
+//! The attributes of the template, like doc comments, are given to the builders.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! struct Meter { value: f64, precision: Option<u8> }
+//! struct Foot { value: f64, precision: Option<u8> }
+//!
+//! #[trait_gen(T -> Meter, Foot; builder)]
+//! /// Builder of ${T} values
+//! struct Builder {
+//!     value: f64,
+//!     precision: Option<u8>,
+//! }
+//!
+//! let meter = MeterBuilder::new().value(2.5).precision(1).build().unwrap();
+//! assert_eq!(meter.precision, Some(1));
+//! assert!(Foot::builder().precision(2).build().is_err());
+//! ```
+//!
+//! ### `newtype`
+//!
+//! Transforms each structure of the code into a `#[repr(transparent)]` newtype of the types,
+//! for example to make FFI-safe wrappers. The structure is a template whose name is appended to
+//! the type name, with its first letter in upper case, to make the newtype's name: `struct
+//! Wrapper(T)` generates `MeterWrapper(Meter)` for `Meter` and `U8Wrapper(u8)` for `u8`.
+//! The types must be type paths.
+//!
+//! The template must have a single field, apart from `PhantomData` fields, and no `repr`
+//! attribute. The option can't be used with `builder`.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! #[trait_gen(T -> u8, f32; newtype)]
+//! /// Wrapper of ${T}
+//! #[derive(Clone, Copy)]
+//! pub struct Wrapper(pub T);
+//!
+//! let w = F32Wrapper(1.5);
+//! assert_eq!(std::mem::size_of_val(&w), 4);
+//! assert_eq!(U8Wrapper(2).0, 2);
+//! ```
+//!
+//! ### `bench_fn`
+//!
+//! Generates a function for each type from each function of the code, named after the template
+//! function followed by the type identifier in snake case: `fn bench_clone()` generates
+//! `bench_clone_meter()` for `Meter` and `bench_clone_big_int()` for `BigInt<u8>`. The
+//! attributes of the template are kept, so it's meant to make a benchmark stub of each type,
+//! with `#[bench]` or the attributes of a benchmark crate, and distinct names in the reports.
+//! The measured operation is the body of the template, in which the generic argument is
+//! substituted like in any other code. The types must be type paths.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # use std::time::{Duration, Instant};
+//! // generates `bench_clone_string()` and `bench_clone_vec()`
+//! #[trait_gen(T -> String, Vec<u64>; bench_fn)]
+//! #[inline(never)]
+//! fn bench_clone(iterations: usize) -> Duration {
+//!     let value: T = Default::default();
+//!     let start = Instant::now();
+//!     let mut total = 0;
+//!     for _ in 0..iterations {
+//!         total += value.clone().len();
+//!     }
+//!     assert_eq!(total, 0);
+//!     start.elapsed()
+//! }
+//!
+//! let elapsed = bench_clone_string(10) + bench_clone_vec(10);
+//! ```
+//!
+//! ### `ord`, `partial_ord`
+//!
+//! Generates the ordering implementations of each structure of the code, which must have a
+//! single field apart from `PhantomData` fields, by delegating to that field. With `ord`,
+//! `PartialOrd` and `Ord` are both implemented, so the field type must implement `Ord` and the
+//! structure must implement `Eq`. With `partial_ord`, only `PartialOrd` is implemented, which is
+//! enough for types like `f32`. The options can't be used together, and they're typically used
+//! with `newtype`.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! #[trait_gen(T -> u8, u32; newtype, ord)]
+//! #[derive(PartialEq, Eq)]
+//! pub struct Id(pub T);
+//!
+//! #[trait_gen(T -> f32, f64; newtype, partial_ord)]
+//! #[derive(PartialEq)]
+//! pub struct Length(pub T);
+//!
+//! assert_eq!(U8Id(3).max(U8Id(5)).0, 5);
+//! assert!(F64Length(1.5) < F64Length(2.0));
+//! ```
+//!
+//! ### `bidirectional`
+//!
+//! Generates, along with each `impl From<A> for B`, the reverse conversion `impl From<B> for A`.
+//! The body of the reverse conversion is given by a `reverse` method in the template, which is
+//! removed from the original implementation and renamed `from` in the reverse one. Since `Self`
+//! wouldn't designate the same type in both implementations, it can't be used in `reverse`.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # #[derive(Debug, PartialEq)]
+//! struct Celsius(f64);
+//! # #[derive(Debug, PartialEq)]
+//! struct Kelvin(f64);
+//! # #[derive(Debug, PartialEq)]
+//! struct Fahrenheit(f64);
+//!
+//! #[trait_gen(T -> Kelvin, Fahrenheit; bidirectional)]
+//! impl From<T> for Celsius {
+//!     fn from(value: T) -> Self {
+//!         Celsius(if "${T}" == "Kelvin" { value.0 - 273.0 } else { (value.0 - 32.0) / 1.8 })
+//!     }
+//!
+//!     fn reverse(value: Celsius) -> T {
+//!         T(if "${T}" == "Kelvin" { value.0 + 273.0 } else { value.0 * 1.8 + 32.0 })
+//!     }
+//! }
+//!
+//! assert_eq!(Celsius::from(Kelvin(300.0)), Celsius(27.0));
+//! assert_eq!(Fahrenheit::from(Celsius(100.0)), Fahrenheit(212.0));
+//! ```
+//!
+//! ### `try_from_name`
+//!
+//! Generates `impl TryFrom<&str>` for each type, which only succeeds if the string is exactly the
+//! name of the type as it appears in `${T}` substitutions, and gives its default value. The
+//! error is the string itself, so it can be given to the conversion of the next type when
+//! parsing a name reflectively. The types must implement `Default`, and be local to the crate,
+//! since the trait and `&str` are foreign. The primitive types, the types of the standard
+//! library, the tuples, arrays and slices are rejected; the other foreign types, like those of
+//! dependencies, can't be detected and fail with the compiler's error on the orphan rule.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # use std::convert::TryFrom;
+//! # #[derive(Default, Debug, PartialEq)] struct Meter(f64);
+//! # #[derive(Default, Debug, PartialEq)] struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! #[trait_gen(T -> Meter, Foot; try_from_name)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! assert_eq!(Meter::try_from("Meter"), Ok(Meter(0.0)));
+//! assert_eq!(Foot::try_from("Meter"), Err("Meter"));
+//! ```
+//!
+//! ```rust, compile_fail
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Length { fn meters(&self) -> f64; }
+//! // error: try_from_name can't implement TryFrom<&str> for the foreign type 'f32'
+//! #[trait_gen(T -> f32, f64; try_from_name)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         *self as f64
+//!     }
+//! }
+//! ```
+//!
+//! ### `from_roundtrip_tests`
+//!
+//! Generates, along with each `impl From<A> for B`, a test only compiled with `cfg(test)`, which
+//! checks that the default value of `A` survives the roundtrip `A -> B -> A`. It's typically used
+//! with the `bidirectional` option, which generates the reverse conversion, but the latter can
+//! be implemented separately too. The source type must implement `Default`, `PartialEq` and
+//! `Debug`, and the tests are named after the identifiers of the types in snake case:
+//! `from_roundtrip_kelvin_celsius` for `impl From<Kelvin> for Celsius`, so the types must be type
+//! paths.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # #[derive(Debug, PartialEq, Default)]
+//! struct Celsius(f64);
+//! # #[derive(Debug, PartialEq, Default)]
+//! struct Kelvin(f64);
+//!
+//! // generates the test `from_roundtrip_kelvin_celsius`
+//! #[trait_gen(T -> Kelvin; bidirectional, from_roundtrip_tests)]
+//! impl From<T> for Celsius {
+//!     fn from(value: T) -> Self {
+//!         Celsius(value.0 - 273.0)
+//!     }
+//!
+//!     fn reverse(value: Celsius) -> T {
+//!         T(value.0 + 273.0)
+//!     }
+//! }
+//! ```
+//!
+//! ### `slice_impl`
+//!
+//! Generates, along with each trait implementation, the implementation of the trait for a slice
+//! of the type, `[T]`, which is also used by `&[T]` and `&mut [T]` values. A method can be given
+//! for slices by a `slice_<method>` method in the template, in which `self` is the slice; it's
+//! removed from the original implementation and renamed in the slice one. The other methods must
+//! take `&self` or `&mut self`, return `()`, and have identifiers as arguments, so that they can
+//! be called on each element. The associated types and constants are copied as they are, and
+//! `Self` can't be used in the delegated methods.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! trait Total {
+//!     fn total(&self) -> f64;
+//!     fn double(&mut self);
+//! }
+//!
+//! #[trait_gen(T -> u32, f32; slice_impl)]
+//! impl Total for T {
+//!     fn total(&self) -> f64 {
+//!         *self as f64
+//!     }
+//!
+//!     fn slice_total(&self) -> f64 {
+//!         self.iter().map(|x| x.total()).sum()
+//!     }
+//!
+//!     fn double(&mut self) {
+//!         *self *= 2 as T;
+//!     }
+//! }
+//!
+//! let mut values = [1.5_f32, 2.0];
+//! values.double();
+//! assert_eq!(values[..].total(), 7.0);
+//! ```
+//!
+//! ### `gate_by_feature`, `gate_by_feature = "template"`, `gate_case = case`
+//!
+//! Gates the code generated for each type by a feature named after the type, by adding
+//! `#[cfg(feature = "name")]` to it. Without template, the name is the type identifier in snake
+//! case, without its path and generic arguments: `Meter` is gated by `"meter"`, and
+//! `units::BigInt<u8>` by `"big_int"`. With a template, `${T}` is replaced by that name, so
+//! `"with-${T}"` gives `"with-meter"`. The types must be type paths.
+//!
+//! The `gate_case` option changes the case of the type identifier in the feature name:
+//! `snake` (the default), `kebab`, `lower`, or `exact`. For `BigInt`, they give `"big_int"`,
+//! `"big-int"`, `"bigint"` and `"BigInt"`, respectively. It's typically used when each type
+//! has an optional feature named identically, like `#[trait_gen(T -> BigInt, BigUint;
+//! gate_by_feature, gate_case = lower)]`.
+//!
+//! The gate is added to the `cfg` attributes of the types, if any.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! // generates `#[cfg(feature = "unit-meter")] impl Length for Meter { ... }` and
+//! //           `#[cfg(feature = "unit-foot")] impl Length for Foot { ... }`
+//! #[trait_gen(T -> Meter, Foot; gate_by_feature = "unit-${T}")]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//! ```
+//!
+//! ### `deprecated_note = "template"`
+//!
+//! Gives a note to the `#[deprecated]` attributes without arguments of the types, so
+//! `Type #[deprecated]` becomes `Type #[deprecated(note = "...")]`, where `${T}` is replaced by
+//! the type in the template. It's typically used to phase out the support of some of the types,
+//! with a consistent message. The other types aren't affected, and at least one type must have
+//! a `#[deprecated]` attribute. Like any type attribute, the deprecation applies to the
+//! top-level items generated for the type, which must be items that can be deprecated, like
+//! type implementations or functions; the compiler rejects it on trait implementations.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64); struct Yard(f64);
+//! // generates `#[deprecated(note = "use Meter instead of Foot")] impl Foot { ... }`, and the
+//! // same for `Yard`
+//! #[trait_gen(T -> Meter, Foot #[deprecated], Yard #[deprecated];
+//!     deprecated_note = "use Meter instead of ${T}")]
+//! impl T {
+//!     fn value(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//! ```
+//!
+//! ### `skip_diagonal`
+//!
+//! When attributes are chained to generate the cross product of type lists, removes the current
+//! type from the types of the next attributes, so the combinations where the types are equal
+//! aren't generated. The types are compared once normalized, as they appear in `${T}`
+//! substitutions. If no type remains in an attribute, the copy isn't generated at all.
+//!
+//! This is typically used for conversion matrices, since `impl From<T> for T` already exists:
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64); struct Mile(f64);
+//! # trait Factor { const FACTOR: f64; }
+//! # impl Factor for Meter { const FACTOR: f64 = 1.0; }
+//! # impl Factor for Foot { const FACTOR: f64 = 0.3048; }
+//! # impl Factor for Mile { const FACTOR: f64 = 1609.344; }
+//! // generates the 6 conversions between different units
+//! #[trait_gen(T -> Meter, Foot, Mile; skip_diagonal)]
+//! #[trait_gen(U -> Meter, Foot, Mile)]
+//! impl From<T> for U {
+//!     fn from(value: T) -> Self {
+//!         U(value.0 * T::FACTOR / U::FACTOR)
+//!     }
+//! }
+//! ```
+//!
+//! The option can't be used in the legacy format, and requires the next attributes in the
+//! `T -> Type1, Type2` format.
+//!
+//! ### `zip`
+//!
+//! When attributes are chained, only keeps the type at the same position as the current type in
+//! the next attributes, instead of generating the cross product of the type lists. The next
+//! attributes must have as many types as the current one. This pairs related types, like the
+//! integer types with their atomic counterpart:
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # use std::sync::atomic::{AtomicU16, AtomicU32, Ordering};
+//! trait Counter {
+//!     type Value;
+//!     fn increment(&self) -> Self::Value;
+//! }
+//!
+//! // generates the implementations for (u16, AtomicU16) and (u32, AtomicU32)
+//! #[trait_gen(T -> u16, u32; zip)]
+//! #[trait_gen(A -> AtomicU16, AtomicU32)]
+//! impl Counter for A {
+//!     type Value = T;
+//!
+//!     fn increment(&self) -> T {
+//!         self.fetch_add(1, Ordering::Relaxed)
+//!     }
+//! }
+//! ```
+//!
+//! The option can't be used in the legacy format nor with `skip_diagonal`, and requires the next
+//! attributes in the `T -> Type1, Type2` format.
+//!
+//! ### `distinct_copies`
+//!
+//! Checks that the code generated for each type is different, and fails otherwise, naming the
+//! first two types whose code is identical. It catches the cases where the generic argument is
+//! only used in positions where it's not substituted, so that all the copies are the same and
+//! conflict, or hide a mistake. The attributes of the types, like `cfg`, aren't included in the
+//! comparison.
+//!
+//! ```rust, compile_fail
+//! # use conditional_trait_gen::trait_gen;
+//! trait Zero { fn zero() -> Self; }
+//!
+//! // error: the code generated for 'u8' and 'u16' is identical
+//! #[trait_gen(T -> u8, u16; distinct_copies)]
+//! impl Zero for u32 {
+//!     fn zero() -> Self { 0 }
+//! }
+//! ```
+//!
+//! In the legacy format, the original code is compared too:
+//!
+//! ```rust, compile_fail
+//! # use conditional_trait_gen::trait_gen;
+//! trait Zero { fn zero() -> Self; }
+//!
+//! // error: the code generated for 'u8' and 'u16' is identical
+//! #[trait_gen(u8, u16; distinct_copies)]
+//! impl Zero for u32 {
+//!     fn zero() -> Self { 0 }
+//! }
+//! ```
+//!
+//! ### `require_methods(method1, method2, ...)`
+//!
+//! Checks that each generated implementation defines all the given methods, and fails
+//! otherwise, naming the type and the missing methods:
+//! `the implementation for 'u16' is missing the required method(s) 'zero'`. It guards against
+//! incomplete templates, for example when a method is only kept for some of the types with the
+//! `#[when]` attribute, or when a trait with default methods is refactored. The check applies
+//! to the implementations of the code, not to those generated by the other options.
+//!
+//! ```rust, compile_fail
+//! # use conditional_trait_gen::{trait_gen, when};
+//! trait Zero { fn zero() -> Self where Self: Sized { unimplemented!() } }
+//!
+//! // error: the implementation for 'u16' is missing the required method(s) 'zero'
+//! #[trait_gen(T -> u8, u16; require_methods(zero))]
+//! impl Zero for T {
+//!     #[when(u8 -> zero)]
+//!     fn zero_u8() -> Self { 0 }
+//! }
+//! ```
+//!
+//! ### `object_safe`
+//!
+//! Checks that the traits implemented by the code can be used as trait objects, by generating
+//! with each implementation a function taking a `&dyn Trait` argument, in which the associated
+//! types are those of the implementation. It guarantees that a trait meant to be used as an
+//! object stays object-safe as its methods and types evolve; otherwise the compiler reports
+//! the error on the trait of the implementation (E0038). The type implementations aren't checked.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Square(f64); struct Circle(f64);
+//! trait Shape {
+//!     type Unit;
+//!     fn area(&self) -> f64;
+//! }
+//!
+//! #[trait_gen(T -> Square, Circle; object_safe)]
+//! impl Shape for T {
+//!     type Unit = f64;
+//!     fn area(&self) -> f64 {
+//!         if "${T}" == "Square" { self.0 * self.0 } else { 3.14 * self.0 * self.0 }
+//!     }
+//! }
+//! ```
+//!
+//! ```rust, compile_fail
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Square(f64); struct Circle(f64);
+//! trait Shape {
+//!     fn unit() -> Self;
+//! }
+//!
+//! // error[E0038]: the trait `Shape` is not object-safe
+//! #[trait_gen(T -> Square, Circle; object_safe)]
+//! impl Shape for T {
+//!     fn unit() -> Self { T(1.0) }
+//! }
+//! ```
+//!
+//! ### `max_size = limit`
+//!
+//! Checks at compile time that the size of each type doesn't exceed the limit, which can be any
+//! constant expression of type `usize`, for example to keep the types small on embedded targets.
+//! The check is generated with each copy, so it has the attributes of its type; when it fails,
+//! the compiler reports an error on the offending type in the attribute, with the message
+//! `the size of 'Type' exceeds the max_size limit 'limit'`.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Zero { fn zero() -> Self; }
+//! const MAX_SIZE: usize = 8;
+//!
+//! #[trait_gen(T -> u8, f64, [u16; 4]; max_size = MAX_SIZE)]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//! ```
+//!
+//! ```rust, compile_fail
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Zero { fn zero() -> Self; }
+//! // error: the size of 'u128' exceeds the max_size limit '2 * 4'
+//! #[trait_gen(T -> u8, u128; max_size = 2 * 4)]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         0
+//!     }
+//! }
+//! ```
+//!
+//! ### `assert_bounds`, `assert_bounds(Bound1 + Bound2 + ...)`
+//!
+//! Checks at compile time that each type satisfies the bounds, `Send + Sync` by default, for
+//! example to guarantee that the types can be shared between threads. Any bound can be given,
+//! like `Unpin` or `'static`. The check is generated with each copy, so it has the attributes of
+//! its type; when it fails, the compiler reports an error on the offending type in the attribute.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Zero { fn zero() -> Self; }
+//! #[trait_gen(T -> u8, String, Vec<f64>; assert_bounds)]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//! ```
+//!
+//! ```rust, compile_fail
+//! # use conditional_trait_gen::trait_gen;
+//! # use std::rc::Rc;
+//! # trait Zero { fn zero() -> Self; }
+//! // error[E0277]: `Rc<u8>` cannot be sent between threads safely
+//! #[trait_gen(T -> u8, Rc<u8>; assert_bounds(Send + Unpin + 'static))]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//! ```
+//!
+//! ### `schema_version = value`
+//!
+//! Adds `const SCHEMA_VERSION: u32 = value;` to each implementation, so the version of a
+//! serialization schema shared by all the types is set once in the attribute. The value can be
+//! any constant expression of type `u32`; it's evaluated at compile time like any other constant.
+//! In a trait implementation, the trait must declare the constant. The implementation must not
+//! define it already.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! const BASE_VERSION: u32 = 2;
+//!
+//! trait Schema {
+//!     const SCHEMA_VERSION: u32;
+//!     fn fields() -> &'static [&'static str];
+//! }
+//!
+//! // generates `const SCHEMA_VERSION: u32 = BASE_VERSION + 1;` in both implementations
+//! #[trait_gen(T -> Meter, Foot; schema_version = BASE_VERSION + 1)]
+//! impl Schema for T {
+//!     fn fields() -> &'static [&'static str] {
+//!         &["value"]
+//!     }
+//! }
+//!
+//! assert_eq!((Meter::SCHEMA_VERSION, Foot::SCHEMA_VERSION), (3, 3));
+//! ```
+//!
+//! ### `type_tag`, `type_tag = method`, `type_tag_path = form`
+//!
+//! Adds `fn method(&self) -> &'static str` to each implementation, returning the name of the
+//! type, as a lightweight reflection without external dependencies. The method is `type_tag`
+//! by default. The `type_tag_path` option gives the form of the name: `last` (the default) for
+//! the identifier of the last segment of the type path, without its generic arguments, or `full`
+//! for the whole type as it appears in `${T}` substitutions. The types must be type paths with
+//! the `last` form. In a trait implementation, the trait must declare the method. The
+//! implementation must not define it already.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! mod units {
+//!     pub struct Meter(pub f64);
+//!     pub struct Foot(pub f64);
+//! }
+//!
+//! // generates `fn name(&self) -> &'static str { "units::Meter" }` in the first implementation
+//! #[trait_gen(T -> units::Meter, units::Foot; type_tag = name, type_tag_path = full)]
+//! impl T {
+//!     fn value(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! assert_eq!(units::Foot(1.0).name(), "units::Foot");
+//! ```
+//!
+//! ### `subst_attrs(path1, path2, ...)`
+//!
+//! Substitutes the generic argument in the attributes with the given paths, anywhere in the code.
+//! The arguments of attributes are otherwise left untouched, except for doc comments and the
+//! inner `trait_gen` attributes.
+//!
+//! Since the arguments of those attributes can't be parsed in general, the substitution is only
+//! a best effort: any occurrence of the generic argument is replaced, unless it's preceded by `::`
+//! or `.`, and `${T}` is replaced in the strings. The attribute paths must be written as they
+//! appear in the code; `helper` and `my::helper` are different paths.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait TagSize { fn tag_size() -> usize; }
+//! #[trait_gen(T -> u8, u16, u32; subst_attrs(repr))]
+//! impl TagSize for T {
+//!     fn tag_size() -> usize {
+//!         #[repr(T)]
+//!         enum Tag { A, B }
+//!         std::mem::size_of::<Tag>()
+//!     }
+//! }
+//!
+//! assert_eq!(u16::tag_size(), 2);
+//! ```
+//!
+//! ### `doc_alias`, `doc_alias(method1, method2, ...)`
+//!
+//! Adds a `#[doc(alias = "Type")]` attribute to the methods of the implementations, naming the
+//! type of each copy, so that searching for the type in the documentation finds them. When
+//! method names are given, only those methods receive the alias.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! pub struct Meter(f64);
+//! pub struct Foot(f64);
+//!
+//! #[trait_gen(T -> Meter, Foot; doc_alias(value))]
+//! impl T {
+//!     /// Length in ${T}
+//!     pub fn value(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//! ```
+//!
+//! ### `doc_example = "code"`
+//!
+//! Adds an `# Example` section to the documentation of the implementations, with the given code
+//! in a doctest. Like in the doc comments, `${T}` is replaced by the type of each copy, so each
+//! implementation is documented by its own example, which is compiled and run by `cargo test`
+//! like any other doctest. The code must refer to the items through the path of the crate, as in
+//! any doctest.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! pub trait Zero {
+//!     fn zero() -> Self;
+//! }
+//!
+//! // the documentation of the implementation for `u8` includes
+//! // `assert_eq!(<u8 as my_crate::Zero>::zero(), 0 as u8);`
+//! #[trait_gen(T -> u8, i64, f32;
+//!     doc_example = "assert_eq!(<${T} as my_crate::Zero>::zero(), 0 as ${T});")]
+//! impl Zero for T {
+//!     fn zero() -> Self {
+//!         Default::default()
+//!     }
+//! }
+//! ```
+//!
+//! ### `inline_never`
+//!
+//! Adds `#[inline(never)]` to the methods of the implementations, replacing their `inline`
+//! attributes if any, so that the code of each type appears as a distinct symbol in the binary.
+//! It's a debugging aid to measure the code size of each type; the implementations themselves
+//! and their other items, like constants, are left untouched.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # trait MyLog { fn my_log2(self) -> u32; }
+//! #[trait_gen(T -> u8, u16, u32; inline_never)]
+//! impl MyLog for T {
+//!     #[inline]
+//!     fn my_log2(self) -> u32 {
+//!         T::BITS - 1 - self.leading_zeros()
+//!     }
+//! }
+//! ```
+//!
+//! ### `trace_calls`, `trace_calls = macro_path`
+//!
+//! Inserts a call to a logging macro at the start of each method of the implementations, with
+//! a string literal naming the type and the method as argument: `"<Meter as Length>::meters"`,
+//! or `"Meter::new"` in a type implementation. It helps to find which of the generated methods
+//! are called. The macro is `log::trace` by default, which requires the `log` crate; any other
+//! macro taking a string literal can be given instead, for example a local macro that expands
+//! to nothing unless a feature of the crate is enabled.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! macro_rules! trace {
+//!     ($name:literal) => { println!("called {}", $name) };
+//! }
+//!
+//! #[trait_gen(T -> Meter, Foot; trace_calls = trace)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! // prints "called <Foot as Length>::meters"
+//! assert_eq!(Foot(1.0).meters(), 1.0);
+//! ```
+//!
+//! ### `register = macro_path`
+//!
+//! Invokes the macro with each type, `macro_path!(Type);`, next to the code generated for it,
+//! so that all the types can be registered and discovered at runtime. The shape of the registry
+//! is left to the macro; typically, it submits an entry to a static collection built at
+//! startup, like the ones of the [inventory](https://crates.io/crates/inventory) or
+//! [linkme](https://crates.io/crates/linkme) crates. The invocation gets the attributes of the
+//! type, so a type removed by a `#[cfg(...)]` attribute isn't registered either.
+//!
+//! ```rust,ignore
+//! # use conditional_trait_gen::trait_gen;
+//! # trait Plugin { fn run(&self); }
+//! pub struct Entry {
+//!     pub name: &'static str,
+//!     pub create: fn() -> Box<dyn Plugin>,
+//! }
+//!
+//! inventory::collect!(Entry);
+//!
+//! macro_rules! register_plugin {
+//!     ($t:ty) => {
+//!         inventory::submit! {
+//!             Entry { name: stringify!($t), create: || Box::new(<$t>::default()) }
+//!         }
+//!     };
+//! }
+//!
+//! #[trait_gen(T -> Zip, Tar; register = register_plugin)]
+//! impl Plugin for T {
+//!     fn run(&self) { /* ... */ }
+//! }
+//!
+//! // lists "Zip" and "Tar"
+//! for entry in inventory::iter::<Entry> {
+//!     println!("{}", entry.name);
+//! }
+//! ```
+//!
+//! ### `anon_const`
+//!
+//! Wraps the code generated for each type in an anonymous scope, `const _: () = { ... };`, so
+//! that the helper items of the copies don't collide with each other, nor pollute the module
+//! namespace. The trait implementations are still visible everywhere, but the other items
+//! can't be reached from outside the scope, so the option can't be used to generate public
+//! items like structures or functions.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! pub trait Describe {
+//!     fn describe(&self) -> String;
+//! }
+//!
+//! #[trait_gen(T -> u8, f64; anon_const)]
+//! mod imp {
+//!     use super::Describe;
+//!
+//!     fn helper(value: T) -> String {
+//!         format!("${T} {value}")
+//!     }
+//!
+//!     impl Describe for T {
+//!         fn describe(&self) -> String {
+//!             helper(*self)
+//!         }
+//!     }
+//! }
+//!
+//! # fn main() {
+//! assert_eq!(3_u8.describe(), "u8 3");
+//! # }
+//! ```
+//!
+//! ### `mod_groups(name1 = [Type1, ...], vis name2 = [Type2, ...], ...)`
+//!
+//! Places the code generated for the types of each group in a module named after the group, to
+//! organize large generated surfaces, for example by family of types. The modules have the given
+//! visibility, private by default, and begin with `use super::*;` so that the code can refer to
+//! the same items as in the parent module. The code of the types that aren't in any group, and
+//! the items generated once for all the types by the other options, stay in the parent module.
+//!
+//! The trait implementations apply everywhere, wherever they're located, but the other items,
+//! like the structures generated with the `newtype` option, must be reached through their
+//! module.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! #[trait_gen(T -> u8, u16, f32, f64; newtype, mod_groups(pub ints = [u8, u16], pub floats = [f32, f64]))]
+//! #[derive(Debug, PartialEq)]
+//! pub struct Value(pub T);
+//!
+//! assert_eq!(ints::U16Value(3).0, 3);
+//! assert_eq!(floats::F64Value(1.5), floats::F64Value(1.5));
+//! ```
+//!
+//! ### `deferred_macro = name`
+//!
+//! Defines a macro `name` containing the generated code instead of emitting it, so that the
+//! implementations are generated where the macro is invoked, with `name!();`. It decouples the
+//! declaration from the emission, for example to put the implementations in another module,
+//! or to generate them only in some configurations.
+//!
+//! The macro is a `macro_rules!` macro without parameters, so it follows the usual scoping
+//! rules: it must be invoked after its definition, in the same module or in a child module,
+//! unless it's exported with `#[macro_use]` on the parent module. Its hygiene only affects the
+//! local variables; the generated items are visible where the macro is invoked, and the paths
+//! are resolved there too, so the types and traits must be in scope at that place. The code
+//! can't contain any `$` token, since it would be interpreted by the macro. With several
+//! attributes, the option must be given to the first one, since the others are expanded for
+//! each of its copies.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # struct Meter(f64); struct Foot(f64);
+//! # trait Length { fn meters(&self) -> f64; }
+//! #[trait_gen(T -> Meter, Foot; deferred_macro = impl_all_units)]
+//! impl Length for T {
+//!     fn meters(&self) -> f64 {
+//!         self.0
+//!     }
+//! }
+//!
+//! // ...
+//!
+//! impl_all_units!();
+//!
+//! assert_eq!(Foot(2.0).meters(), 2.0);
+//! ```
+//!
+//! ### `doc_hidden`
+//!
+//! Adds `#[doc(hidden)]` to all the generated top-level items, like the implementations, so
+//! they don't appear in the documentation. The nested items, like methods, are left untouched.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # pub trait MyLog { fn my_log2(self) -> u32; }
+//! #[trait_gen(T -> u8, u16, u32; doc_hidden)]
+//! impl MyLog for T {
+//!     fn my_log2(self) -> u32 {
+//!         T::BITS - 1 - self.leading_zeros()
+//!     }
+//! }
+//! ```
+//!
+//! ### `allow(lint1, lint2, ...)`
+//!
+//! Adds `#[allow(lint1, lint2, ...)]` to all the generated top-level items, to silence the lints
+//! triggered by the generated code that can't easily be fixed in the template, for example
+//! because they only concern some of the types.
+//!
+//! ```rust
+//! # use conditional_trait_gen::trait_gen;
+//! # pub trait Zero { fn is_zero(&self) -> bool; }
+//! #[deny(clippy::float_cmp)]
+//! #[trait_gen(T -> u8, f32; allow(clippy::float_cmp))]
+//! impl Zero for T {
+//!     fn is_zero(&self) -> bool {
+//!         *self == 0 as T
+//!     }
+//! }
+//! ```
+//!
 //! ## Limitations
 //!
 //! * The procedural macro of the `trait_gen` attribute can't handle scopes, so it doesn't support any
-//! type declaration with the same literal as the generic argument. For instance, this code fails to compile
-//! because of the generic function:
+//!   type declaration with the same literal as the generic argument. The collision with a type or
+//!   const generic parameter, either on the implementation like `impl<T> Trait for Wrapper<T>` or
+//!   on a method or a function, is reported as an error which suggests another name for the
+//!   placeholder. For instance, this code fails to compile because of the generic function:
 //!
 //!   ```rust, compile_fail
 //!   # use num::Num;
-//!   # use trait_gen::trait_gen;
+//!   # use conditional_trait_gen::trait_gen;
 //!   #
 //!   # trait AddMod {
 //!   #     type Output;
@@ -160,16 +1963,35 @@
 //!   }
 //!   ```
 //!
+//!   or because of the generic parameter of the implementation:
+//!
+//!   ```rust, compile_fail
+//!   # use conditional_trait_gen::trait_gen;
+//!   # trait Name { fn name(&self) -> &'static str; }
+//!   struct Wrapper<X>(X);
+//!
+//!   // error: Placeholder 'T' is reserved for the substitution, but it's also declared as a type parameter.
+//!   //  help: ..., or rename the placeholder in the attribute, for example '#[trait_gen(U -> ...)]'.
+//!   #[trait_gen(T -> u8, u16)]
+//!   impl<T> Name for Wrapper<T> {
+//!       fn name(&self) -> &'static str {
+//!           "${T}"
+//!       }
+//!   }
+//!   ```
+//!
 //! * The generic argument must be a [type path](https://doc.rust-lang.org/reference/paths.html#paths-in-types);
-//! it cannot be a more complex type like a reference or a slice. So you can use `gen::T<U> -> ...`
-//! but not `&T -> ...`.
+//!   it cannot be a more complex type like a reference or a slice. So you can use `gen::T<U> -> ...`
+//!   but not `&T -> ...`.
 
+mod options;
+mod output;
 mod tests;
 
+use options::Options;
 use proc_macro::TokenStream;
-use proc_macro2::Ident;
+use proc_macro2::{Ident, TokenTree};
 use proc_macro_error::{abort, proc_macro_error};
-use quote::__private::ext::RepToTokensExt;
 use quote::{quote, ToTokens};
 use std::fmt::{Display, Formatter};
 use syn::parse::{Parse, ParseStream};
@@ -178,18 +2000,35 @@ use syn::spanned::Spanned;
 use syn::token::Colon2;
 use syn::visit_mut::VisitMut;
 use syn::{
-    bracketed, parenthesized, parse, parse2, parse_macro_input, parse_str, Attribute, Error, Expr,
-    ExprLit, File, GenericArgument, GenericParam, Generics, ImplItem, ImplItemMethod, ItemImpl,
-    Lit, LitStr, Macro, Path, PathArguments, PathSegment, Token, Type, TypePath,
+    bracketed, parenthesized, parse2, parse_macro_input, parse_str, Attribute, Error, Expr,
+    ExprLit, File, GenericArgument, GenericParam, Generics, ImplItem, ItemImpl, Lit, LitStr, Macro,
+    Pat, Path, PathArguments, PathSegment, Token, Type, TypePath,
 };
 
 const VERBOSE: bool = false;
 const VERBOSE_TF: bool = false;
 
+/// Macros whose arguments are comma-separated expressions, in which the types can be substituted
+const EXPR_MACROS: &[&str] = &[
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "addr_of",
+    "addr_of_mut",
+];
+
+/// Macros whose arguments are comma-separated expressions including a format string, with the
+/// position of that string, in which the types are substituted except in the format string
+const FORMAT_MACROS: &[(&str, usize)] = &[("format_args", 0), ("write", 1), ("writeln", 1)];
+
 //==============================================================================
 // Main substitution types and their trait implementations
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
 /// Substitution item, either a Path (`super::Type`) or a Type (`&mut Type`)
 enum SubstType {
     Path(Path),
@@ -221,6 +2060,36 @@ struct Subst {
     is_path: bool,
     /// Context stack, cannot substitue paths when last is false (can substitute if empty)
     can_subst_path: Vec<bool>,
+    /// attributes of each type, including the generic argument in legacy format
+    type_attrs: Vec<Vec<Attribute>>,
+    /// options given after the types
+    options: Options,
+    /// the current copy is dropped if true (set when no type remains in an inner attribute)
+    skip_copy: bool,
+    /// position of the current type in the list of types
+    type_index: usize,
+}
+
+#[derive(Debug)]
+/// Type argument of the attribute, followed by the attributes of the code generated for it
+struct TypeEntry {
+    ty: Type,
+    attrs: Vec<Attribute>,
+}
+
+#[derive(Debug)]
+/// Attribute arguments preceding the options
+struct Parameters {
+    /// generic argument `T` (or `Type1` in legacy format)
+    generic_arg: Path,
+    /// attributes of `Type1` in legacy format (empty otherwise)
+    path_attrs: Vec<Attribute>,
+    /// types that replace the generic argument, with their attributes
+    types: Vec<TypeEntry>,
+    /// legacy format if true
+    legacy: bool,
+    /// format `T in [...]` if true
+    in_format: bool,
 }
 
 #[derive(Debug)]
@@ -228,24 +2097,101 @@ struct Subst {
 struct AttrParams {
     /// generic argument to replace
     generic_arg: Path,
-    /// types that replace the generic argument
-    new_types: Vec<Type>,
+    /// types that replace the generic argument, with their attributes
+    new_types: Vec<TypeEntry>,
     /// legacy format if true
     legacy: bool,
+    /// options following the types, including the semicolon separator (unparsed)
+    options: proc_macro2::TokenStream,
 }
 
 impl Subst {
+    /// Creates the substitution data of `generic_arg` with `types`, in the `T -> Type1, Type2`
+    /// format and without attributes.
+    fn new(generic_arg: Path, mut types: Vec<Type>, options: Options) -> Self {
+        let mut visitor = TurboFish;
+        for ty in types.iter_mut() {
+            visitor.visit_type_mut(ty);
+        }
+        let is_path = types.iter().all(|ty| matches!(ty, Type::Path(_)));
+        let new_types = types
+            .into_iter()
+            .map(|ty| {
+                if is_path {
+                    if let Type::Path(p) = ty {
+                        SubstType::Path(p.path)
+                    } else {
+                        panic!("this should match Type::Path: {:?}", ty)
+                    }
+                } else {
+                    SubstType::Type(ty)
+                }
+            })
+            .collect::<Vec<_>>();
+        let type_attrs = vec![Vec::new(); new_types.len()];
+        Subst {
+            generic_arg,
+            new_types,
+            legacy: false,
+            in_format: false,
+            is_path,
+            can_subst_path: Vec::new(),
+            type_attrs,
+            options,
+            skip_copy: false,
+            type_index: 0,
+        }
+    }
+
+    /// Substitutes the generic argument with the current type, at position `type_index` in the
+    /// list of types, in the copy `ast` of the code. Returns false if the copy must be dropped.
+    fn substitute_copy(&mut self, type_index: usize, ast: &mut File) -> bool {
+        self.skip_copy = false;
+        self.type_index = type_index;
+        self.visit_file_mut(ast);
+        assert!(
+            self.can_subst_path.is_empty(),
+            "self.enabled has {} entries after type {}",
+            self.can_subst_path.len(),
+            pathname(self.new_types.first().unwrap())
+        );
+        !self.skip_copy
+    }
+
     fn can_subst_path(&self) -> bool {
         *self.can_subst_path.last().unwrap_or(&true)
     }
+
+    /// Adds types at the end of the list, without attributes other than the feature gate.
+    fn add_types(&mut self, types: Vec<Type>) -> syn::Result<()> {
+        for mut ty in types {
+            TurboFish.visit_type_mut(&mut ty);
+            let new_type = match ty {
+                Type::Path(p) if self.is_path => SubstType::Path(p.path),
+                ty => SubstType::Type(ty),
+            };
+            let mut attrs = Vec::new();
+            if let Some(template) = &self.options.gate_by_feature {
+                attrs.push(output::feature_gate(
+                    template.as_ref(),
+                    self.options.gate_case.as_ref(),
+                    &self.generic_arg,
+                    &new_type,
+                )?);
+            }
+            self.new_types.push(new_type);
+            self.type_attrs.push(attrs);
+        }
+        Ok(())
+    }
 }
 
 impl Display for Subst {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "PathTypes {{\n  current_types: {}\n  new_types: {}\n  current_defined: {}\n  enabled:  {}\n}}",
                pathname(&self.generic_arg),
-               self.new_types.iter().map(|t| pathname(t)).collect::<Vec<_>>().join(", "),
-               self.legacy.to_string(),
+               self.new_types.iter().map(pathname).collect::<Vec<_>>().join(", "),
+               self.legacy,
                self.can_subst_path.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ")
         )
     }
@@ -337,6 +2283,32 @@ fn path_prefix_len(prefix: &Path, full_path: &Path) -> Option<usize> {
     None
 }
 
+/// Gets the name of the macro, without its path (`assert` for `std::assert!`).
+fn macro_name(node: &Macro) -> String {
+    node.path
+        .segments
+        .last()
+        .map(|seg| seg.ident.to_string())
+        .unwrap_or_default()
+}
+
+/// Checks if the arguments of the macro are comma-separated expressions, which are parsed to
+/// substitute the types in them.
+fn is_expr_macro(node: &Macro) -> bool {
+    EXPR_MACROS.contains(&macro_name(node).as_str())
+}
+
+/// Gets the position of the format string in the arguments of the macro, if they're
+/// comma-separated expressions including a format string, which are parsed to substitute the
+/// types in them.
+fn format_string_position(node: &Macro) -> Option<usize> {
+    let name = macro_name(node);
+    FORMAT_MACROS
+        .iter()
+        .find(|(format_macro, _)| *format_macro == name)
+        .map(|(_, pos)| *pos)
+}
+
 /// Replaces the pattern `pat` with `repl` in `string`. Returns `Some(resulting string)` if
 /// the string changed, None if there was no replacement.
 fn replace_str(string: &str, pat: &str, repl: &str) -> Option<String> {
@@ -347,6 +2319,46 @@ fn replace_str(string: &str, pat: &str, repl: &str) -> Option<String> {
     }
 }
 
+/// Replaces the occurrences of the `pat` token sequence with `repl` in the token stream, including
+/// in the groups. A sequence preceded by a `:` or `.` punctuation isn't replaced, since it's then
+/// the end of another path or a field.
+fn replace_tokens(
+    tokens: proc_macro2::TokenStream,
+    pat: &[String],
+    repl: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let trees = tokens.into_iter().collect::<Vec<_>>();
+    let mut output = proc_macro2::TokenStream::new();
+    let mut i = 0;
+    while i < trees.len() {
+        let is_match = i + pat.len() <= trees.len()
+            && trees[i..i + pat.len()]
+                .iter()
+                .zip(pat)
+                .all(|(tree, p)| tree.to_string() == *p)
+            && !matches!(i.checked_sub(1).map(|j| &trees[j]),
+                Some(TokenTree::Punct(p)) if p.as_char() == ':' || p.as_char() == '.');
+        if is_match {
+            output.extend(repl.clone());
+            i += pat.len();
+        } else {
+            match &trees[i] {
+                TokenTree::Group(group) => {
+                    let mut new_group = proc_macro2::Group::new(
+                        group.delimiter(),
+                        replace_tokens(group.stream(), pat, repl),
+                    );
+                    new_group.set_span(group.span());
+                    output.extend(Some(TokenTree::Group(new_group)));
+                }
+                tree => output.extend(Some(tree.clone())),
+            }
+            i += 1;
+        }
+    }
+    output
+}
+
 #[derive(Debug)]
 struct WhenArgs {
     for_type: Type,
@@ -371,11 +2383,128 @@ impl Parse for WhenArgs {
     }
 }
 
+/// Arguments of the `vec!` macro: `element; length` or `element1, element2, ...`
+#[allow(clippy::large_enum_variant)]
+enum VecArgs {
+    Repeat(Expr, Token![;], Expr),
+    List(Punctuated<Expr, Token![,]>),
+}
+
+impl Parse for VecArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.is_empty() {
+            return Ok(VecArgs::List(Punctuated::new()));
+        }
+        let first = input.parse()?;
+        if input.peek(Token![;]) {
+            Ok(VecArgs::Repeat(first, input.parse()?, input.parse()?))
+        } else {
+            let mut list = Punctuated::new();
+            list.push_value(first);
+            while !input.is_empty() {
+                list.push_punct(input.parse()?);
+                if input.is_empty() {
+                    break;
+                }
+                list.push_value(input.parse()?);
+            }
+            Ok(VecArgs::List(list))
+        }
+    }
+}
+
+impl ToTokens for VecArgs {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            VecArgs::Repeat(elem, semi, len) => {
+                elem.to_tokens(tokens);
+                semi.to_tokens(tokens);
+                len.to_tokens(tokens);
+            }
+            VecArgs::List(list) => list.to_tokens(tokens),
+        }
+    }
+}
+
+/// Arguments of the `matches!` macro: `expression, pattern1 | pattern2 if guard`
+struct MatchesArgs {
+    expr: Expr,
+    comma: Token![,],
+    leading_vert: Option<Token![|]>,
+    cases: Punctuated<Pat, Token![|]>,
+    guard: Option<(Token![if], Expr)>,
+    trailing_comma: Option<Token![,]>,
+}
+
+impl Parse for MatchesArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expr = input.parse()?;
+        let comma = input.parse()?;
+        let leading_vert = input.parse()?;
+        let mut cases = Punctuated::new();
+        cases.push_value(input.parse()?);
+        while input.peek(Token![|]) {
+            cases.push_punct(input.parse()?);
+            cases.push_value(input.parse()?);
+        }
+        let guard = if input.peek(Token![if]) {
+            Some((input.parse()?, input.parse()?))
+        } else {
+            None
+        };
+        let trailing_comma = input.parse()?;
+        Ok(MatchesArgs {
+            expr,
+            comma,
+            leading_vert,
+            cases,
+            guard,
+            trailing_comma,
+        })
+    }
+}
+
+impl ToTokens for MatchesArgs {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        self.expr.to_tokens(tokens);
+        self.comma.to_tokens(tokens);
+        self.leading_vert.to_tokens(tokens);
+        self.cases.to_tokens(tokens);
+        if let Some((if_token, guard)) = &self.guard {
+            if_token.to_tokens(tokens);
+            guard.to_tokens(tokens);
+        }
+        self.trailing_comma.to_tokens(tokens);
+    }
+}
+
 //==============================================================================
 // Main substitution code
 
 impl VisitMut for Subst {
     fn visit_attribute_mut(&mut self, node: &mut Attribute) {
+        if self.options.subst_attrs.contains(&pathname(&node.path)) {
+            // best effort on the opaque tokens of the allowed attributes
+            let pat = self
+                .generic_arg
+                .to_token_stream()
+                .into_iter()
+                .map(|tree| tree.to_string())
+                .collect::<Vec<_>>();
+            let repl = self.new_types.first().unwrap().to_token_stream();
+            let tokens = replace_tokens(node.tokens.clone(), &pat, &repl);
+            node.tokens = match replace_str(
+                &tokens.to_string(),
+                &format!("${{{}}}", pathname(&self.generic_arg)),
+                &pathname(self.new_types.first().unwrap()),
+            ) {
+                Some(ts_str) => ts_str
+                    .parse()
+                    .unwrap_or_else(|_| panic!("parsing attribute failed: {}", ts_str)),
+                None => tokens,
+            };
+            return;
+        }
         if let Some(PathSegment { ident, .. }) = node.path.segments.first() {
             match ident.to_string().as_str() {
                 "doc" => {
@@ -386,7 +2515,7 @@ impl VisitMut for Subst {
                     ) {
                         let new_ts: proc_macro2::TokenStream = ts_str
                             .parse()
-                            .expect(&format!("parsing attribute failed: {}", ts_str));
+                            .unwrap_or_else(|_| panic!("parsing attribute failed: {}", ts_str));
                         node.tokens = new_ts;
                     }
                     return;
@@ -441,8 +2570,8 @@ impl VisitMut for Subst {
                 &format!("${{{}}}", pathname(&self.generic_arg)),
                 &pathname(self.new_types.first().unwrap()),
             ) {
-                let new_lit: LitStr =
-                    parse_str(&ts_str).expect(&format!("parsing LitStr failed: {}", ts_str));
+                let new_lit: LitStr = parse_str(&ts_str)
+                    .unwrap_or_else(|_| panic!("parsing LitStr failed: {}", ts_str));
                 node.lit = Lit::Str(new_lit);
             } else {
                 syn::visit_mut::visit_expr_lit_mut(self, node);
@@ -453,24 +2582,37 @@ impl VisitMut for Subst {
     fn visit_generics_mut(&mut self, i: &mut Generics) {
         if let Some(segment) = self.generic_arg.segments.first() {
             let current_ident = &segment.ident;
-            for t in i.params.iter() {
-                match &t {
-                    GenericParam::Type(t) => {
-                        if &t.ident == current_ident {
-                            abort!(t.span(),
-                                "Type '{}' is reserved for the substitution.", current_ident.to_string();
-                                help = "Use another identifier for this local generic type."
-                            );
+            for param in i.params.iter() {
+                let (ident, kind) = match param {
+                    GenericParam::Type(t) => (&t.ident, "type"),
+                    GenericParam::Const(c) => (&c.ident, "const"),
+                    GenericParam::Lifetime(_) => continue,
+                };
+                if ident == current_ident {
+                    // suggests a placeholder that isn't used by the other generic parameters
+                    let free = ["U", "V", "W", "X", "Y", "Z"]
+                        .into_iter()
+                        .find(|name| {
+                            !i.params.iter().any(|p| match p {
+                                GenericParam::Type(t) => t.ident == name,
+                                GenericParam::Const(c) => c.ident == name,
+                                GenericParam::Lifetime(_) => false,
+                            })
+                        })
+                        .unwrap_or("Gen");
+                    abort!(ident.span(),
+                        "Placeholder '{}' is reserved for the substitution, but it's also declared as a {} parameter.",
+                        current_ident, kind;
+                        help = "Use another identifier for this local generic {}, or rename the placeholder in the attribute, for example '#[trait_gen({} -> ...)]'.",
+                        kind, free
+                    );
 
-                            // replace the 'abort!' above with this once it is stable:
-                            //
-                            // t.span().unwrap()
-                            //     .error(format!("Type '{}' is reserved for the substitution.", self.current_type.to_string()))
-                            //     .help("Use another identifier for this local generic type.")
-                            //     .emit();
-                        }
-                    }
-                    _ => {}
+                    // replace the 'abort!' above with this once it is stable:
+                    //
+                    // ident.span().unwrap()
+                    //     .error(format!("Placeholder '{}' is reserved for the substitution, but it's also declared as a {} parameter.", current_ident, kind))
+                    //     .help(format!("Use another identifier for this local generic {}, or rename the placeholder in the attribute, for example '#[trait_gen({} -> ...)]'.", kind, free))
+                    //     .emit();
                 }
             }
         }
@@ -508,11 +2650,68 @@ impl VisitMut for Subst {
         ) {
             let new_ts: proc_macro2::TokenStream = ts_str
                 .parse()
-                .expect(&format!("parsing Macro failed: {}", ts_str));
+                .unwrap_or_else(|_| panic!("parsing Macro failed: {}", ts_str));
             node.tokens = new_ts;
         } else {
             syn::visit_mut::visit_macro_mut(self, node);
         }
+        // substitutes the types in the arguments of known macros
+        if is_expr_macro(node) {
+            if let Ok(mut args) =
+                node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+            {
+                for arg in args.iter_mut() {
+                    self.visit_expr_mut(arg);
+                }
+                node.tokens = args.into_token_stream();
+            }
+        } else if self.options.forward && node.path.is_ident(output::FORWARD_MACRO) {
+            if let Ok(mut args) = node.parse_body::<output::ForwardArgs>() {
+                self.visit_type_mut(&mut args.target);
+                if let Some((_, receiver)) = &mut args.receiver {
+                    self.visit_expr_mut(receiver);
+                }
+                node.tokens = args.into_token_stream();
+            }
+        } else if let Some(format_pos) = format_string_position(node) {
+            if let Ok(mut args) =
+                node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+            {
+                // the format string is left untouched
+                for (i, arg) in args.iter_mut().enumerate() {
+                    if i != format_pos {
+                        self.visit_expr_mut(arg);
+                    }
+                }
+                node.tokens = args.into_token_stream();
+            }
+        } else if macro_name(node) == "vec" {
+            if let Ok(mut args) = node.parse_body::<VecArgs>() {
+                match &mut args {
+                    VecArgs::Repeat(elem, _, len) => {
+                        self.visit_expr_mut(elem);
+                        self.visit_expr_mut(len);
+                    }
+                    VecArgs::List(list) => {
+                        for elem in list.iter_mut() {
+                            self.visit_expr_mut(elem);
+                        }
+                    }
+                }
+                node.tokens = args.into_token_stream();
+            }
+        } else if macro_name(node) == "matches" {
+            if let Ok(mut args) = node.parse_body::<MatchesArgs>() {
+                self.visit_expr_mut(&mut args.expr);
+                for case in args.cases.iter_mut() {
+                    self.visit_pat_mut(case);
+                }
+                if let Some((_, guard)) = &mut args.guard {
+                    self.visit_expr_mut(guard);
+                }
+                node.tokens = args.into_token_stream();
+            }
+        }
     }
 
     fn visit_path_mut(&mut self, path: &mut Path) {
@@ -538,7 +2737,7 @@ impl VisitMut for Subst {
                         //     path     = gen :: T   <'_>    len = 2, subst enabled
                         //     new_path = mod :: Name        len = 2
                         //  => new_seg  = mod :: Name<'_>
-                        let mut nth_new_seg = new_seg.last_mut().unwrap();
+                        let nth_new_seg = new_seg.last_mut().unwrap();
                         let nth_seg = path.segments.iter().nth(length - 1).unwrap();
                         if nth_new_seg.arguments.is_empty() && !nth_seg.arguments.is_empty() {
                             nth_new_seg.arguments = nth_seg.arguments.clone();
@@ -629,22 +2828,67 @@ fn process_attr_args(
 ) -> proc_macro2::TokenStream {
     match parse2::<AttrParams>(args) {
         Ok(mut types) => {
+            for TypeEntry { ty, .. } in &mut types.new_types {
+                // checks if substitutions must be made in that argument:
+                subst.visit_type_mut(ty);
+            }
+            if subst.options.skip_diagonal {
+                if types.legacy {
+                    return Error::new(
+                        types.generic_arg.span(),
+                        "skip_diagonal requires the 'T -> Type1, Type2' format in the next attributes",
+                    )
+                    .to_compile_error();
+                }
+                // the types are compared once normalized like those of `subst`:
+                let current = pathname(subst.new_types.first().unwrap());
+                types.new_types.retain(|TypeEntry { ty, .. }| {
+                    let mut ty = ty.clone();
+                    TurboFish.visit_type_mut(&mut ty);
+                    pathname(&ty) != current
+                });
+                if types.new_types.is_empty() {
+                    subst.skip_copy = true;
+                }
+            }
+            if subst.options.zip {
+                if types.legacy {
+                    return Error::new(
+                        types.generic_arg.span(),
+                        "zip requires the 'T -> Type1, Type2' format in the next attributes",
+                    )
+                    .to_compile_error();
+                }
+                let nbr_types = subst.type_index + subst.new_types.len();
+                if types.new_types.len() != nbr_types {
+                    return Error::new(
+                        types.generic_arg.span(),
+                        format!(
+                            "zip requires {} types in the next attributes, found {}",
+                            nbr_types,
+                            types.new_types.len()
+                        ),
+                    )
+                    .to_compile_error();
+                }
+                let entry = types.new_types.swap_remove(subst.type_index);
+                types.new_types = vec![entry];
+            }
             let mut output = proc_macro2::TokenStream::new();
             if !types.legacy {
                 let gen = types.generic_arg;
                 output.extend(quote!(#gen -> ));
             }
             let mut first = true;
-            for ty in &mut types.new_types {
+            for TypeEntry { ty, attrs } in &types.new_types {
                 if !first {
                     output.extend(quote!(, ));
                 }
-                // checks if substitutions must be made in that argument:
-                subst.visit_type_mut(ty);
-
-                output.extend(quote!(#ty));
+                output.extend(quote!(#ty #(#attrs)*));
                 first = false;
             }
+            // the options, if any, still include their leading semicolon:
+            output.extend(types.options);
 
             // puts the parentheses back and returns the modified token stream
             proc_macro2::Group::new(proc_macro2::Delimiter::Parenthesis, output).into_token_stream()
@@ -660,41 +2904,64 @@ fn process_attr_args(
 /// - `T in [Type1, Type2, Type3]` (when "in_format" feature is enabled)
 /// - `Type1, Type2, Type3` (legacy format)
 ///
-/// Returns (path, types, legacy), where
-/// - `path` is the generic argument `T` (or `Type1` in legacy format)
-/// - `types` is a vector of parsed `Type` items: `Type1, Type2, Type3` (or `Type2, Type3` in legacy)
-/// - `legacy` is true if the legacy format is used
-/// - `in_format` is true if the `T in [Type1, Type2, Type3]` format is used
+/// Each type can be followed by attributes that only apply to the code generated for that type:
+/// `T -> Type1, Type2 #[cfg(feature = "type2")], Type3`.
+///
+/// Returns the [Parameters], where `types` is a vector of parsed `Type` items with their
+/// attributes: `Type1, Type2, Type3` (or `Type2, Type3` in legacy format)
 ///
 /// Note: we don't include `Type1` in `types` for the legacy format because the original stream will be copied
 /// in the generated code, so only the remaining types are requires for the substitutions.
-fn parse_parameters(input: ParseStream) -> syn::parse::Result<(Path, Vec<Type>, bool, bool)> {
+fn parse_parameters(input: ParseStream) -> syn::parse::Result<Parameters> {
     let current_type = input.parse::<Path>()?;
-    let types: Vec<Type>;
+    let mut path_attrs = Vec::new();
+    let types: Vec<TypeEntry>;
     let arrow_format = input.peek(Token![->]); // "T -> Type1, Type2, Type3"
     let in_format = !arrow_format && input.peek(Token![in]); // "T in [Type1, Type2, Type3]"
     let legacy = !arrow_format && !in_format; // "Type1, Type2, Type3"
     if legacy {
+        path_attrs = input.call(Attribute::parse_outer)?;
         input.parse::<Token![,]>()?;
-        let vars = Punctuated::<Type, Token![,]>::parse_terminated(input)?;
-        types = vars.into_iter().collect();
+        types = parse_type_list(input)?;
     } else {
-        let vars = if cfg!(feature = "in_format") && in_format {
+        types = if cfg!(feature = "in_format") && in_format {
             input.parse::<Token![in]>()?;
             let content;
             bracketed!(content in input);
-            Punctuated::<Type, Token![,]>::parse_terminated(&content.into())?
+            parse_type_list(&content)?
         } else {
             // removes the "->" and parses the arguments
             input.parse::<Token![->]>()?;
-            Punctuated::<Type, Token![,]>::parse_terminated(input)?
+            parse_type_list(input)?
         };
-        types = vars.into_iter().collect();
-        if types.is_empty() {
+        // the types may be given by the 'types_from' option:
+        if types.is_empty() && !input.peek(Token![;]) {
             return Err(Error::new(input.span(), "expected type"));
         }
     }
-    Ok((current_type, types, legacy, in_format))
+    Ok(Parameters {
+        generic_arg: current_type,
+        path_attrs,
+        types,
+        legacy,
+        in_format,
+    })
+}
+
+/// Parses a comma-separated list of types and their attributes, which ends either with the input
+/// or with the semicolon introducing the options.
+fn parse_type_list(input: ParseStream) -> syn::parse::Result<Vec<TypeEntry>> {
+    let mut types = Vec::new();
+    while !input.is_empty() && !input.peek(Token![;]) {
+        let ty = input.parse::<Type>()?;
+        let attrs = input.call(Attribute::parse_outer)?;
+        types.push(TypeEntry { ty, attrs });
+        if input.is_empty() || input.peek(Token![;]) {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(types)
 }
 
 /// Attribute parser used for inner attributes
@@ -702,11 +2969,19 @@ impl Parse for AttrParams {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let content;
         parenthesized!(content in input);
-        let (current_type, types, legacy, _) = parse_parameters(&content.into())?;
+        let Parameters {
+            generic_arg: current_type,
+            types,
+            legacy,
+            ..
+        } = parse_parameters(&content)?;
+        // the options are left untouched, they're only parsed when the inner attribute is processed
+        let options = content.parse::<proc_macro2::TokenStream>()?;
         Ok(AttrParams {
             generic_arg: current_type,
             new_types: types,
             legacy,
+            options,
         })
     }
 }
@@ -714,34 +2989,97 @@ impl Parse for AttrParams {
 /// Attribute argument parser used for the procedural macro being processed
 impl Parse for Subst {
     fn parse(input: ParseStream) -> syn::parse::Result<Self> {
-        let (current_type, mut types, legacy, in_format) = parse_parameters(input)?;
-        let mut visitor = TurboFish;
-        for ty in types.iter_mut() {
-            visitor.visit_type_mut(ty);
-        }
-        let is_path = types.iter().all(|ty| matches!(ty, Type::Path(_)));
-        let new_types = types
-            .into_iter()
-            .map(|ty| {
-                if is_path {
-                    if let Type::Path(p) = ty {
-                        SubstType::Path(p.path)
-                    } else {
-                        panic!("this should match Type::Path: {:?}", ty)
-                    }
-                } else {
-                    SubstType::Type(ty)
-                }
-            })
-            .collect::<Vec<_>>();
-        Ok(Subst {
+        let Parameters {
             generic_arg: current_type,
-            new_types: new_types,
+            path_attrs,
+            types: entries,
             legacy,
             in_format,
-            is_path,
-            can_subst_path: Vec::new(),
-        })
+        } = parse_parameters(input)?;
+        let options = if input.peek(Token![;]) {
+            input.parse::<Token![;]>()?;
+            input.parse::<Options>()?
+        } else {
+            Options::default()
+        };
+        if legacy && options.skip_diagonal {
+            return Err(Error::new(
+                current_type.span(),
+                "skip_diagonal requires the 'T -> Type1, Type2' format",
+            ));
+        }
+        if legacy && options.zip {
+            return Err(Error::new(
+                current_type.span(),
+                "zip requires the 'T -> Type1, Type2' format",
+            ));
+        }
+        if options.zip && options.skip_diagonal {
+            return Err(Error::new(
+                current_type.span(),
+                "zip and skip_diagonal can't be used together",
+            ));
+        }
+        if legacy && options.types_from.is_some() {
+            return Err(Error::new(
+                current_type.span(),
+                "types_from requires the 'T -> Type1, Type2' format",
+            ));
+        }
+        if !legacy && entries.is_empty() && options.types_from.is_none() {
+            return Err(Error::new(current_type.span(), "expected type"));
+        }
+        let mut type_attrs = vec![];
+        if legacy {
+            type_attrs.push(path_attrs);
+        }
+        let mut types = Vec::new();
+        for TypeEntry { ty, attrs } in entries {
+            types.push(ty);
+            type_attrs.push(attrs);
+        }
+        for attr in type_attrs.iter().flatten() {
+            if attr.path.is_ident("cfg") {
+                attr.parse_meta()?;
+            }
+        }
+        let mut subst = Subst::new(current_type, types, options);
+        subst.legacy = legacy;
+        subst.in_format = in_format;
+        let Subst {
+            generic_arg: current_type,
+            new_types,
+            options,
+            ..
+        } = &subst;
+        if let Some(template) = &options.gate_by_feature {
+            let original = legacy.then(|| SubstType::Path(current_type.clone()));
+            let all_types = original.iter().chain(new_types.iter());
+            for (ty, attrs) in all_types.zip(type_attrs.iter_mut()) {
+                attrs.push(output::feature_gate(
+                    template.as_ref(),
+                    options.gate_case.as_ref(),
+                    current_type,
+                    ty,
+                )?);
+            }
+        }
+        if let Some(template) = &options.deprecated_note {
+            let original = legacy.then(|| SubstType::Path(current_type.clone()));
+            let all_types = original.iter().chain(new_types.iter());
+            let mut replaced = false;
+            for (ty, attrs) in all_types.zip(type_attrs.iter_mut()) {
+                replaced |= output::deprecation_note(template, current_type, ty, attrs);
+            }
+            if !replaced {
+                return Err(Error::new(
+                    template.span(),
+                    "deprecated_note requires a type with a #[deprecated] attribute",
+                ));
+            }
+        }
+        subst.type_attrs = type_attrs;
+        Ok(subst)
     }
 }
 
@@ -767,6 +3105,25 @@ impl VisitMut for TurboFish {
     }
 }
 
+//==============================================================================
+// Substitution entry point
+
+/// Substitutes `generic_arg` with `ty` in `tokens`, which must be a list of items, as the attribute
+/// does for each of its types, but without the options.
+fn substitute(
+    tokens: proc_macro2::TokenStream,
+    generic_arg: &Path,
+    ty: &Type,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut ast: File = parse2(tokens)?;
+    let mut subst = Subst::new(generic_arg.clone(), vec![ty.clone()], Options::default());
+    Ok(if subst.substitute_copy(0, &mut ast) {
+        ast.to_token_stream()
+    } else {
+        proc_macro2::TokenStream::new()
+    })
+}
+
 //==============================================================================
 
 /// Generates the attached trait implementation for all the types given in argument.
@@ -775,7 +3132,7 @@ impl VisitMut for TurboFish {
 /// is given first, followed by a right arrow (`->`) and a list of type arguments.
 ///
 /// ```rust
-/// # use trait_gen::trait_gen;
+/// # use conditional_trait_gen::trait_gen;
 /// # struct Type1; struct Type2; struct Type3;
 /// # trait Trait {}
 /// #[trait_gen(T -> Type1, Type2, Type3)]
@@ -796,18 +3153,25 @@ impl VisitMut for TurboFish {
 /// is not a valid floating-point literal.
 ///
 /// Finally, the actual type replaces any `${T}` occurrence in doc comments, macros and string literals.
+/// The arguments of macros are otherwise left untouched, except for the `assert!` family of
+/// macros (`assert!`, `assert_eq!`, `assert_ne!` and their `debug_` versions), whose expressions
+/// are substituted like the rest of the code, `addr_of!` and `addr_of_mut!`, whose place
+/// expression is substituted too, `format_args!`, `write!` and `writeln!`, whose
+/// arguments are substituted except the format string, `vec!`, whose elements and length are
+/// substituted, and `matches!`, whose patterns and guard are substituted too, including all the
+/// alternatives of or-patterns like `T::A | T::B`.
 ///
 /// _Notes:_
 /// - _Using the letter "T" is not mandatory; any type path will do. For example, `gen::Type` is fine
-/// too. But to make it easy to read and similar to a generic implementation, short upper-case identifiers
-/// are preferred._
+///   too. But to make it easy to read and similar to a generic implementation, short upper-case identifiers
+///   are preferred._
 /// - _Two or more attributes can be chained to generate all the combinations._
 /// - _`trait_gen` can be used on type implementations too._
 ///
 /// ## Examples
 ///
 /// ```rust
-/// # use trait_gen::trait_gen;
+/// # use conditional_trait_gen::trait_gen;
 /// # trait MyLog { fn my_log2(self) -> u32; }
 /// #[trait_gen(T -> u8, u16, u32, u64, u128)]
 /// impl MyLog for T {
@@ -837,7 +3201,7 @@ pub fn trait_gen(args: TokenStream, item: TokenStream) -> TokenStream {
             &types
                 .new_types
                 .iter()
-                .map(|t| pathname(t))
+                .map(pathname)
                 .collect::<Vec<_>>()
                 .join(", "),
         );
@@ -858,7 +3222,7 @@ pub fn trait_gen(args: TokenStream, item: TokenStream) -> TokenStream {
             &types
                 .new_types
                 .iter()
-                .map(|t| pathname(t))
+                .map(pathname)
                 .collect::<Vec<_>>()
                 .join(", ")
         )
@@ -866,27 +3230,114 @@ pub fn trait_gen(args: TokenStream, item: TokenStream) -> TokenStream {
     if VERBOSE || VERBOSE_TF {
         println!("\n{}\n{}", item, "-".repeat(80));
     }
-    let ast: File = syn::parse(item).unwrap();
+    let mut ast: File = syn::parse(item).unwrap();
+    if let Some(code) = &types.options.doc_example {
+        output::add_doc_example(code, &mut ast);
+    }
+    if let Some(trait_path) = &types.options.types_from {
+        let extracted = output::types_from(trait_path, &ast);
+        if let Err(e) = types.add_types(extracted) {
+            return e.to_compile_error().into();
+        }
+    }
+    // all the types, including the first one in legacy format, which is the original code:
+    let mut all_types = Vec::new();
+    if types.legacy {
+        all_types.push(SubstType::Path(types.generic_arg.clone()));
+    }
+    all_types.extend(types.new_types.iter().cloned());
     let mut output = TokenStream::new();
     if let Some(message) = warning {
         output.extend(TokenStream::from(quote!(
             #[deprecated = #message]
         )));
     }
+    let all_type_attrs = std::mem::take(&mut types.type_attrs);
+    let mut type_attrs = all_type_attrs.iter();
+    let original_attrs = if types.legacy {
+        type_attrs.next()
+    } else {
+        None
+    };
+    // generated code of each type, to detect identical copies:
+    let mut copies: Vec<(SubstType, String)> = Vec::new();
+    // generated code of the types in each module group:
+    let mut group_contents = vec![proc_macro2::TokenStream::new(); types.options.mod_groups.len()];
+    if let Some(attrs) = original_attrs {
+        // in legacy format, the original code is the copy of the generic argument
+        let mut original_ast = ast.clone();
+        let ty = SubstType::Path(types.generic_arg.clone());
+        output::process_copy(&types.options, &ty, &mut original_ast);
+        if types.options.distinct_copies {
+            copies.push((ty.clone(), original_ast.to_token_stream().to_string()));
+        }
+        output::add_attributes(&mut original_ast, attrs);
+        match output::mod_group(&types.options.mod_groups, &ty) {
+            Some(group) => group_contents[group].extend(quote!(#original_ast)),
+            None => output.extend(TokenStream::from(quote!(#original_ast))),
+        }
+    }
+    let mut first = true;
     while !types.new_types.is_empty() {
         let mut modified_ast = ast.clone();
-        types.visit_file_mut(&mut modified_ast);
-        output.extend(TokenStream::from(quote!(#modified_ast)));
-        assert!(
-            types.can_subst_path.is_empty(),
-            "self.enabled has {} entries after type {}",
-            types.can_subst_path.len(),
-            pathname(types.new_types.first().unwrap())
-        );
+        if let Some(trait_path) = &types.options.types_from {
+            // the implementation giving the types is only kept in the first copy
+            if !first {
+                output::remove_types_impl(trait_path, &mut modified_ast.items);
+            }
+        }
+        first = false;
+        let type_index = all_types.len() - types.new_types.len();
+        let keep = types.substitute_copy(type_index, &mut modified_ast);
+        let ty = types.new_types.first().unwrap().clone();
+        output::process_copy(&types.options, &ty, &mut modified_ast);
+        if types.options.distinct_copies && keep {
+            let code = modified_ast.to_token_stream().to_string();
+            if let Some((other, _)) = copies.iter().find(|(_, other_code)| other_code == &code) {
+                abort!(ty.span(),
+                    "the code generated for '{}' and '{}' is identical", pathname(other), pathname(&ty);
+                    help = "'{}' isn't substituted anywhere in the code", pathname(&types.generic_arg)
+                );
+            }
+            copies.push((ty.clone(), code));
+        }
+        output::add_attributes(&mut modified_ast, type_attrs.next().unwrap());
+        if keep {
+            match output::mod_group(&types.options.mod_groups, &ty) {
+                Some(group) => group_contents[group].extend(quote!(#modified_ast)),
+                None => output.extend(TokenStream::from(quote!(#modified_ast))),
+            }
+        }
         types.new_types.remove(0);
     }
-    if types.legacy {
-        output.extend(TokenStream::from(quote!(#ast)));
+    if !types.options.mod_groups.is_empty() {
+        output.extend(TokenStream::from(output::mod_groups(
+            &types.options.mod_groups,
+            group_contents,
+            &all_types,
+        )));
+    }
+    // the companion items only get the `cfg` attributes of the types:
+    let cfg_attrs = all_type_attrs
+        .iter()
+        .map(|attrs| {
+            attrs
+                .iter()
+                .filter(|a| a.path.is_ident("cfg"))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let companions = output::companions(
+        &types.options,
+        &types.generic_arg,
+        &all_types,
+        &cfg_attrs,
+        &ast,
+    );
+    output.extend(TokenStream::from(companions));
+    if let Some(name) = &types.options.deferred_macro {
+        output = output::deferred_macro(name, output.into()).into();
     }
     if VERBOSE {
         println!(
@@ -902,6 +3353,6 @@ pub fn trait_gen(args: TokenStream, item: TokenStream) -> TokenStream {
 }
 
 #[proc_macro_attribute]
-pub fn when(args: TokenStream, item: TokenStream) -> TokenStream {
+pub fn when(_args: TokenStream, item: TokenStream) -> TokenStream {
     item
 }
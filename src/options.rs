@@ -0,0 +1,736 @@
+// Copyright 2023 Redglyph
+//
+// Attribute options.
+
+use crate::pathname;
+use proc_macro2::Ident;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    braced, bracketed, parenthesized, Error, Expr, ImplItem, LitInt, LitStr, Path, Token, Type,
+    TypeParamBound, Visibility,
+};
+
+#[derive(Debug, Default)]
+/// Options given after the types in the attribute, separated from them by a semicolon:
+///
+/// `#[trait_gen(T -> Type1, Type2, Type3; option1 = value, option2)]`
+pub(crate) struct Options {
+    /// path of the trait whose associated types in the code are added to the types
+    pub(crate) types_from: Option<Path>,
+    /// name of the generated `macro_rules!` that maps the type names to the types
+    pub(crate) dispatch_macro: Option<Ident>,
+    /// visibility and name of the generated constant table of the type layouts
+    pub(crate) layout_table: Option<(Visibility, Ident)>,
+    /// visibility and name of the generated constant table of the type names sorted by name
+    pub(crate) sorted_names: Option<(Visibility, Ident)>,
+    /// visibility and name of the generated `const fn` giving the type names from their index
+    pub(crate) name_fn: Option<(Visibility, Ident)>,
+    /// visibility and name of the generated visitor trait
+    pub(crate) visitor: Option<(Visibility, Ident)>,
+    /// template of the visitor method names
+    pub(crate) visit_method: Option<LitStr>,
+    /// visibility and name template of the generated type tag constants
+    pub(crate) tag_const: Option<(Visibility, Option<LitStr>)>,
+    /// value of the first type tag constant or associated constant
+    pub(crate) tag_base: Option<LitInt>,
+    /// order of the types giving their tags and enum discriminants: `list` (default) or
+    /// `alphabetical`
+    pub(crate) tag_order: Option<Ident>,
+    /// visibility and name of the generated trait giving the tag of the types
+    pub(crate) tag_trait: Option<(Visibility, Ident)>,
+    /// visibility and optional name template of the type bit constants
+    pub(crate) bit_const: Option<(Visibility, Option<LitStr>)>,
+    /// integer type of the type bit constants
+    pub(crate) bit_type: Option<Ident>,
+    /// visibility, name and variant data type of the generated error enum
+    pub(crate) error_enum: Option<(Visibility, Ident, Option<Type>)>,
+    /// template of the error enum variant names
+    pub(crate) error_variant: Option<LitStr>,
+    /// visibility and name of the generated enum with a variant for each type
+    pub(crate) type_enum: Option<(Visibility, Ident)>,
+    /// adds an `all()` method iterating over the variants to the type enum if true
+    pub(crate) enum_all: bool,
+    /// generates the conversion of an index into a variant of the type enum if true
+    pub(crate) enum_try_from: bool,
+    /// visibility and name of the generated marker trait implemented by the types
+    pub(crate) marker_trait: Option<(Visibility, Ident)>,
+    /// visibility and name of the generated trait giving the types as `&dyn Any`
+    pub(crate) as_any: Option<(Visibility, Ident)>,
+    /// visibility and name of the generated module documented with the table of the types
+    pub(crate) doc_table: Option<(Visibility, Ident)>,
+    /// visibility and name of the generated function creating a boxed trait object from a type
+    /// name
+    pub(crate) factory: Option<(Visibility, Ident)>,
+    /// visibility and name of the generated array of constructors creating boxed trait objects
+    pub(crate) ctor_array: Option<(Visibility, Ident)>,
+    /// name of the generated test and path of the generic function it calls for each type
+    pub(crate) roundtrip_test: Option<(Ident, Path)>,
+    /// name of the generated doctest module and code of the doctest generated for each type
+    pub(crate) doctests: Option<(Ident, LitStr)>,
+    /// name of the generated test checking the implementations of the types
+    pub(crate) coherence_test: Option<Ident>,
+    /// name of the generated test module, type and expression of the strategy function created
+    /// for each type
+    pub(crate) strategies: Option<(Ident, Type, Expr)>,
+    /// feature required by the strategy module, in addition to `cfg(test)`
+    pub(crate) strategy_feature: Option<LitStr>,
+    /// visibility and name of the generated function giving the map from the type IDs to the
+    /// given method
+    pub(crate) type_id_map: Option<(Visibility, Ident, Ident)>,
+    /// trait and items of the blanket implementation for the types implementing the trait of
+    /// the code
+    pub(crate) blanket_impl: Option<(Path, Vec<ImplItem>)>,
+    /// generates a forwarding implementation for `Arc<Mutex<T>>` if true
+    pub(crate) forward_arc_mutex: bool,
+    /// adds `where Self: 'static` to the implementations if true
+    pub(crate) self_static: bool,
+    /// replaces `Self` by the implementing type in the method bodies if true
+    pub(crate) replace_self: bool,
+    /// replaces the structures with builders of the types if true
+    pub(crate) builder: bool,
+    /// transforms the structures into transparent newtypes of the types if true
+    pub(crate) newtype: bool,
+    /// renames the functions after the types, to make a benchmark of each type, if true
+    pub(crate) bench_fn: bool,
+    /// generates `PartialOrd` and `Ord` implementations of the single-field structures if true
+    pub(crate) ord: bool,
+    /// generates a `PartialOrd` implementation of the single-field structures if true
+    pub(crate) partial_ord: bool,
+    /// generates the reverse conversion of the `From` implementations if true
+    pub(crate) bidirectional: bool,
+    /// generates `TryFrom<&str>` for each type, parsing its name, if true
+    pub(crate) try_from_name: bool,
+    /// generates a test of the roundtrip conversion of each `From` implementation if true
+    pub(crate) from_roundtrip_tests: bool,
+    /// generates the implementation for a slice of the type if true
+    pub(crate) slice_impl: bool,
+    /// replaces the `forward!(Target)` markers by forwarding bodies if true
+    pub(crate) forward: bool,
+    /// adds `#[inline(never)]` to the methods of the implementations if true
+    pub(crate) inline_never: bool,
+    /// path of the macro called with the type and method names at the start of each method
+    pub(crate) trace_calls: Option<Path>,
+    /// path of the macro invoked with each type, to register it in a user-defined registry
+    pub(crate) register: Option<Path>,
+    /// adds `#[doc(alias = "type")]` to the methods of the implementations, restricted to the
+    /// given names if not empty
+    pub(crate) doc_alias: Option<Vec<Ident>>,
+    /// code of the example added to the documentation of the implementations, in which `${T}`
+    /// is replaced by the type
+    pub(crate) doc_example: Option<LitStr>,
+    /// wraps each generated copy in an anonymous `const _: () = { ... };` scope if true
+    pub(crate) anon_const: bool,
+    /// visibility, name and types of the modules in which the code generated for those types is
+    /// placed
+    pub(crate) mod_groups: Vec<(Visibility, Ident, Vec<Type>)>,
+    /// name of the macro defined with the generated code, instead of emitting it directly
+    pub(crate) deferred_macro: Option<Ident>,
+    /// adds `#[doc(hidden)]` to the generated top-level items if true
+    pub(crate) doc_hidden: bool,
+    /// lints allowed on the generated top-level items
+    pub(crate) allow: Vec<Path>,
+    /// names of the methods each generated implementation must define
+    pub(crate) require_methods: Vec<Ident>,
+    /// checks that the implemented traits can be used as trait objects if true
+    pub(crate) object_safe: bool,
+    /// maximum size of the types, checked at compile time
+    pub(crate) max_size: Option<Expr>,
+    /// bounds required from the types, checked at compile time (`Send + Sync` if empty)
+    pub(crate) assert_bounds: Option<Vec<TypeParamBound>>,
+    /// value of the `SCHEMA_VERSION` constant added to each implementation
+    pub(crate) schema_version: Option<Expr>,
+    /// name of the method giving the type name, added to each implementation
+    pub(crate) type_tag: Option<Ident>,
+    /// form of the type name given by the `type_tag` method: `last` (default) or `full`
+    pub(crate) type_tag_path: Option<Ident>,
+    /// fails if two of the generated copies are identical if true
+    pub(crate) distinct_copies: bool,
+    /// removes the current type from the types of the inner attributes if true
+    pub(crate) skip_diagonal: bool,
+    /// only keeps the type at the same position as the current type in the inner attributes if true
+    pub(crate) zip: bool,
+    /// paths of the attributes in which the types are substituted, in their normalized form
+    pub(crate) subst_attrs: Vec<String>,
+    /// gates the code generated for each type by a feature, whose name is given by an optional
+    /// template (the type name by default)
+    pub(crate) gate_by_feature: Option<Option<LitStr>>,
+    /// note template of the types with a `#[deprecated]` attribute
+    pub(crate) deprecated_note: Option<LitStr>,
+    /// case of the type names in the gating features: `snake` (default), `kebab`, `lower` or
+    /// `exact`
+    pub(crate) gate_case: Option<Ident>,
+}
+
+/// Checks that an option hasn't been given already.
+fn check_unique(is_defined: bool, name: &Ident) -> syn::Result<()> {
+    if is_defined {
+        Err(Error::new(
+            name.span(),
+            format!("option '{}' is already defined", name),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+impl Parse for Options {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Options::default();
+        while !input.is_empty() {
+            let name = input.parse::<Ident>()?;
+            match name.to_string().as_str() {
+                "types_from" => {
+                    check_unique(options.types_from.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.types_from = Some(input.parse()?);
+                }
+                "dispatch_macro" => {
+                    check_unique(options.dispatch_macro.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.dispatch_macro = Some(input.parse()?);
+                }
+                "layout_table" => {
+                    check_unique(options.layout_table.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.layout_table = Some((input.parse()?, input.parse()?));
+                }
+                "sorted_names" => {
+                    check_unique(options.sorted_names.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.sorted_names = Some((input.parse()?, input.parse()?));
+                }
+                "name_fn" => {
+                    check_unique(options.name_fn.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.name_fn = Some((input.parse()?, input.parse()?));
+                }
+                "visitor" => {
+                    check_unique(options.visitor.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.visitor = Some((input.parse()?, input.parse()?));
+                }
+                "visit_method" => {
+                    check_unique(options.visit_method.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.visit_method = Some(input.parse()?);
+                }
+                "tag_const" => {
+                    check_unique(options.tag_const.is_some(), &name)?;
+                    let mut tag_const = (Visibility::Inherited, None);
+                    if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        tag_const.0 = input.parse()?;
+                        if input.peek(LitStr) {
+                            tag_const.1 = Some(input.parse()?);
+                        } else if let Visibility::Inherited = tag_const.0 {
+                            return Err(input.error("expected visibility or name template"));
+                        }
+                    }
+                    options.tag_const = Some(tag_const);
+                }
+                "tag_base" => {
+                    check_unique(options.tag_base.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let base = input.parse::<LitInt>()?;
+                    base.base10_parse::<u32>()?;
+                    options.tag_base = Some(base);
+                }
+                "tag_order" => {
+                    check_unique(options.tag_order.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let order = input.parse::<Ident>()?;
+                    if order != "list" && order != "alphabetical" {
+                        return Err(Error::new(
+                            order.span(),
+                            format!(
+                                "unknown order '{}', expected 'list' or 'alphabetical'",
+                                order
+                            ),
+                        ));
+                    }
+                    options.tag_order = Some(order);
+                }
+                "tag_trait" => {
+                    check_unique(options.tag_trait.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.tag_trait = Some((input.parse()?, input.parse()?));
+                }
+                "bit_const" => {
+                    check_unique(options.bit_const.is_some(), &name)?;
+                    let mut bit_const = (Visibility::Inherited, None);
+                    if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        bit_const.0 = input.parse()?;
+                        if input.peek(LitStr) {
+                            bit_const.1 = Some(input.parse()?);
+                        } else if let Visibility::Inherited = bit_const.0 {
+                            return Err(input.error("expected visibility or name template"));
+                        }
+                    }
+                    options.bit_const = Some(bit_const);
+                }
+                "bit_type" => {
+                    check_unique(options.bit_type.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let ty = input.parse::<Ident>()?;
+                    if !["u8", "u16", "u32", "u64", "u128"].contains(&ty.to_string().as_str()) {
+                        return Err(Error::new(
+                            ty.span(),
+                            format!("expected an unsigned integer type, not '{}'", ty),
+                        ));
+                    }
+                    options.bit_type = Some(ty);
+                }
+                "error_enum" => {
+                    check_unique(options.error_enum.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let vis = input.parse()?;
+                    let enum_name = input.parse()?;
+                    let data = if input.peek(syn::token::Paren) {
+                        let content;
+                        parenthesized!(content in input);
+                        Some(content.parse()?)
+                    } else {
+                        None
+                    };
+                    options.error_enum = Some((vis, enum_name, data));
+                }
+                "error_variant" => {
+                    check_unique(options.error_variant.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.error_variant = Some(input.parse()?);
+                }
+                "type_enum" => {
+                    check_unique(options.type_enum.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.type_enum = Some((input.parse()?, input.parse()?));
+                }
+                "enum_all" => {
+                    check_unique(options.enum_all, &name)?;
+                    options.enum_all = true;
+                }
+                "enum_try_from" => {
+                    check_unique(options.enum_try_from, &name)?;
+                    options.enum_try_from = true;
+                }
+                "marker_trait" => {
+                    check_unique(options.marker_trait.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.marker_trait = Some((input.parse()?, input.parse()?));
+                }
+                "as_any" => {
+                    check_unique(options.as_any.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.as_any = Some((input.parse()?, input.parse()?));
+                }
+                "doc_table" => {
+                    check_unique(options.doc_table.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.doc_table = Some((input.parse()?, input.parse()?));
+                }
+                "factory" => {
+                    check_unique(options.factory.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.factory = Some((input.parse()?, input.parse()?));
+                }
+                "ctor_array" => {
+                    check_unique(options.ctor_array.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.ctor_array = Some((input.parse()?, input.parse()?));
+                }
+                "doctests" => {
+                    check_unique(options.doctests.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let mod_name = input.parse()?;
+                    let content;
+                    parenthesized!(content in input);
+                    options.doctests = Some((mod_name, content.parse()?));
+                }
+                "roundtrip_test" => {
+                    check_unique(options.roundtrip_test.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let test_name = input.parse()?;
+                    let content;
+                    parenthesized!(content in input);
+                    options.roundtrip_test = Some((test_name, content.parse()?));
+                }
+                "strategies" => {
+                    check_unique(options.strategies.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let mod_name = input.parse()?;
+                    let content;
+                    parenthesized!(content in input);
+                    let ty = content.parse()?;
+                    content.parse::<Token![=>]>()?;
+                    options.strategies = Some((mod_name, ty, content.parse()?));
+                }
+                "strategy_feature" => {
+                    check_unique(options.strategy_feature.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.strategy_feature = Some(input.parse()?);
+                }
+                "coherence_test" => {
+                    check_unique(options.coherence_test.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.coherence_test = Some(input.parse()?);
+                }
+                "type_id_map" => {
+                    check_unique(options.type_id_map.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let vis = input.parse()?;
+                    let map_name = input.parse()?;
+                    let content;
+                    parenthesized!(content in input);
+                    options.type_id_map = Some((vis, map_name, content.parse()?));
+                }
+                "blanket_impl" => {
+                    check_unique(options.blanket_impl.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let trait_path = input.parse()?;
+                    let content;
+                    braced!(content in input);
+                    let mut items = Vec::new();
+                    while !content.is_empty() {
+                        items.push(content.parse()?);
+                    }
+                    options.blanket_impl = Some((trait_path, items));
+                }
+                "forward_arc_mutex" => {
+                    check_unique(options.forward_arc_mutex, &name)?;
+                    options.forward_arc_mutex = true;
+                }
+                "self_static" => {
+                    check_unique(options.self_static, &name)?;
+                    options.self_static = true;
+                }
+                "replace_self" => {
+                    check_unique(options.replace_self, &name)?;
+                    options.replace_self = true;
+                }
+                "builder" => {
+                    check_unique(options.builder, &name)?;
+                    options.builder = true;
+                }
+                "newtype" => {
+                    check_unique(options.newtype, &name)?;
+                    options.newtype = true;
+                }
+                "bench_fn" => {
+                    check_unique(options.bench_fn, &name)?;
+                    options.bench_fn = true;
+                }
+                "ord" => {
+                    check_unique(options.ord, &name)?;
+                    options.ord = true;
+                }
+                "partial_ord" => {
+                    check_unique(options.partial_ord, &name)?;
+                    options.partial_ord = true;
+                }
+                "bidirectional" => {
+                    check_unique(options.bidirectional, &name)?;
+                    options.bidirectional = true;
+                }
+                "try_from_name" => {
+                    check_unique(options.try_from_name, &name)?;
+                    options.try_from_name = true;
+                }
+                "from_roundtrip_tests" => {
+                    check_unique(options.from_roundtrip_tests, &name)?;
+                    options.from_roundtrip_tests = true;
+                }
+                "slice_impl" => {
+                    check_unique(options.slice_impl, &name)?;
+                    options.slice_impl = true;
+                }
+                "forward" => {
+                    check_unique(options.forward, &name)?;
+                    options.forward = true;
+                }
+                "anon_const" => {
+                    check_unique(options.anon_const, &name)?;
+                    options.anon_const = true;
+                }
+                "mod_groups" => {
+                    check_unique(!options.mod_groups.is_empty(), &name)?;
+                    let content;
+                    parenthesized!(content in input);
+                    while !content.is_empty() {
+                        let vis = content.parse()?;
+                        let mod_name = content.parse()?;
+                        content.parse::<Token![=]>()?;
+                        let list;
+                        bracketed!(list in content);
+                        let types = Punctuated::<Type, Token![,]>::parse_terminated(&list)?;
+                        options
+                            .mod_groups
+                            .push((vis, mod_name, types.into_iter().collect()));
+                        if !content.is_empty() {
+                            content.parse::<Token![,]>()?;
+                        }
+                    }
+                    if options.mod_groups.is_empty() {
+                        return Err(Error::new(name.span(), "expected module groups"));
+                    }
+                }
+                "deferred_macro" => {
+                    check_unique(options.deferred_macro.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.deferred_macro = Some(input.parse()?);
+                }
+                "doc_hidden" => {
+                    check_unique(options.doc_hidden, &name)?;
+                    options.doc_hidden = true;
+                }
+                "inline_never" => {
+                    check_unique(options.inline_never, &name)?;
+                    options.inline_never = true;
+                }
+                "trace_calls" => {
+                    check_unique(options.trace_calls.is_some(), &name)?;
+                    options.trace_calls = Some(if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        input.parse()?
+                    } else {
+                        syn::parse_quote!(log::trace)
+                    });
+                }
+                "register" => {
+                    check_unique(options.register.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.register = Some(input.parse()?);
+                }
+                "doc_example" => {
+                    check_unique(options.doc_example.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.doc_example = Some(input.parse()?);
+                }
+                "doc_alias" => {
+                    check_unique(options.doc_alias.is_some(), &name)?;
+                    let mut methods = Vec::new();
+                    if input.peek(syn::token::Paren) {
+                        let content;
+                        parenthesized!(content in input);
+                        let names = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+                        if names.is_empty() {
+                            return Err(Error::new(name.span(), "expected method names"));
+                        }
+                        methods.extend(names);
+                    }
+                    options.doc_alias = Some(methods);
+                }
+                "allow" => {
+                    check_unique(!options.allow.is_empty(), &name)?;
+                    let content;
+                    parenthesized!(content in input);
+                    let lints = Punctuated::<Path, Token![,]>::parse_terminated(&content)?;
+                    if lints.is_empty() {
+                        return Err(Error::new(name.span(), "expected lint names"));
+                    }
+                    options.allow = lints.into_iter().collect();
+                }
+                "require_methods" => {
+                    check_unique(!options.require_methods.is_empty(), &name)?;
+                    let content;
+                    parenthesized!(content in input);
+                    let names = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+                    if names.is_empty() {
+                        return Err(Error::new(name.span(), "expected method names"));
+                    }
+                    options.require_methods = names.into_iter().collect();
+                }
+                "object_safe" => {
+                    check_unique(options.object_safe, &name)?;
+                    options.object_safe = true;
+                }
+                "max_size" => {
+                    check_unique(options.max_size.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.max_size = Some(input.parse()?);
+                }
+                "assert_bounds" => {
+                    check_unique(options.assert_bounds.is_some(), &name)?;
+                    let mut bounds = Vec::new();
+                    if input.peek(syn::token::Paren) {
+                        let content;
+                        parenthesized!(content in input);
+                        bounds.extend(
+                            Punctuated::<TypeParamBound, Token![+]>::parse_separated_nonempty(
+                                &content,
+                            )?,
+                        );
+                        if !content.is_empty() {
+                            return Err(content.error("expected '+' between the bounds"));
+                        }
+                    }
+                    options.assert_bounds = Some(bounds);
+                }
+                "schema_version" => {
+                    check_unique(options.schema_version.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.schema_version = Some(input.parse()?);
+                }
+                "type_tag" => {
+                    check_unique(options.type_tag.is_some(), &name)?;
+                    options.type_tag = Some(if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        input.parse()?
+                    } else {
+                        Ident::new("type_tag", name.span())
+                    });
+                }
+                "type_tag_path" => {
+                    check_unique(options.type_tag_path.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let form = input.parse::<Ident>()?;
+                    if form != "last" && form != "full" {
+                        return Err(Error::new(
+                            form.span(),
+                            format!("unknown path form '{}', expected 'last' or 'full'", form),
+                        ));
+                    }
+                    options.type_tag_path = Some(form);
+                }
+                "subst_attrs" => {
+                    check_unique(!options.subst_attrs.is_empty(), &name)?;
+                    let content;
+                    parenthesized!(content in input);
+                    let paths = Punctuated::<Path, Token![,]>::parse_terminated(&content)?;
+                    if paths.is_empty() {
+                        return Err(Error::new(name.span(), "expected attribute paths"));
+                    }
+                    options.subst_attrs = paths.iter().map(pathname).collect();
+                }
+                "distinct_copies" => {
+                    check_unique(options.distinct_copies, &name)?;
+                    options.distinct_copies = true;
+                }
+                "skip_diagonal" => {
+                    check_unique(options.skip_diagonal, &name)?;
+                    options.skip_diagonal = true;
+                }
+                "zip" => {
+                    check_unique(options.zip, &name)?;
+                    options.zip = true;
+                }
+                "gate_by_feature" => {
+                    check_unique(options.gate_by_feature.is_some(), &name)?;
+                    options.gate_by_feature = Some(if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        Some(input.parse()?)
+                    } else {
+                        None
+                    });
+                }
+                "deprecated_note" => {
+                    check_unique(options.deprecated_note.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    options.deprecated_note = Some(input.parse()?);
+                }
+                "gate_case" => {
+                    check_unique(options.gate_case.is_some(), &name)?;
+                    input.parse::<Token![=]>()?;
+                    let case = input.parse::<Ident>()?;
+                    if !["snake", "kebab", "lower", "exact"].contains(&case.to_string().as_str()) {
+                        return Err(Error::new(
+                            case.span(),
+                            format!(
+                                "unknown case '{}', expected 'snake', 'kebab', 'lower' or 'exact'",
+                                case
+                            ),
+                        ));
+                    }
+                    options.gate_case = Some(case);
+                }
+                _ => {
+                    return Err(Error::new(
+                        name.span(),
+                        format!("unknown option '{}'", name),
+                    ))
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        if options.builder && options.newtype {
+            return Err(Error::new(
+                input.span(),
+                "options 'builder' and 'newtype' can't be used together",
+            ));
+        }
+        if options.enum_all && options.type_enum.is_none() {
+            return Err(Error::new(
+                input.span(),
+                "option 'enum_all' requires the 'type_enum' option",
+            ));
+        }
+        if options.enum_try_from && options.type_enum.is_none() {
+            return Err(Error::new(
+                input.span(),
+                "option 'enum_try_from' requires the 'type_enum' option",
+            ));
+        }
+        if options.visit_method.is_some() && options.visitor.is_none() {
+            return Err(Error::new(
+                input.span(),
+                "option 'visit_method' requires the 'visitor' option",
+            ));
+        }
+        if options.tag_base.is_some() && options.tag_const.is_none() && options.tag_trait.is_none()
+        {
+            return Err(Error::new(
+                input.span(),
+                "option 'tag_base' requires the 'tag_const' or 'tag_trait' option",
+            ));
+        }
+        if options.error_variant.is_some() && options.error_enum.is_none() {
+            return Err(Error::new(
+                input.span(),
+                "option 'error_variant' requires the 'error_enum' option",
+            ));
+        }
+        if options.bit_type.is_some() && options.bit_const.is_none() {
+            return Err(Error::new(
+                input.span(),
+                "option 'bit_type' requires the 'bit_const' option",
+            ));
+        }
+        if options.type_tag_path.is_some() && options.type_tag.is_none() {
+            return Err(Error::new(
+                input.span(),
+                "option 'type_tag_path' requires the 'type_tag' option",
+            ));
+        }
+        if options.tag_order.is_some()
+            && options.tag_const.is_none()
+            && options.tag_trait.is_none()
+            && options.type_enum.is_none()
+        {
+            return Err(Error::new(
+                input.span(),
+                "option 'tag_order' requires the 'tag_const', 'tag_trait' or 'type_enum' option",
+            ));
+        }
+        if options.gate_case.is_some() && options.gate_by_feature.is_none() {
+            return Err(Error::new(
+                input.span(),
+                "option 'gate_case' requires the 'gate_by_feature' option",
+            ));
+        }
+        if options.strategy_feature.is_some() && options.strategies.is_none() {
+            return Err(Error::new(
+                input.span(),
+                "option 'strategy_feature' requires the 'strategies' option",
+            ));
+        }
+        if options.ord && options.partial_ord {
+            return Err(Error::new(
+                input.span(),
+                "options 'ord' and 'partial_ord' can't be used together",
+            ));
+        }
+        Ok(options)
+    }
+}
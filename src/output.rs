@@ -0,0 +1,3151 @@
+// Copyright 2023 Redglyph
+//
+// Additional output driven by the attribute options.
+
+use crate::options::Options;
+use crate::{path_prefix_len, pathname, replace_tokens, substitute, SubstType, TurboFish};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use syn::{
+    Attribute, Block, Error, Expr, Fields, File, FnArg, GenericArgument, ImplItem, Item, ItemImpl,
+    ItemMod, ItemStruct, LitInt, LitStr, Macro, Member, Pat, PatIdent, Path, PathArguments,
+    PathSegment, ReturnType, Signature, Stmt, Token, Type, TypeParamBound, TypePath, Visibility,
+};
+
+/// Generates the companion items, which are emitted once after all the implementations.
+///
+/// `types` contains all the types of the attribute, in the order they were given, and
+/// `type_attrs` their `cfg` attributes. `ast` is the original code, before substitution.
+pub(crate) fn companions(
+    options: &Options,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+    ast: &File,
+) -> TokenStream {
+    let mut output = TokenStream::new();
+    if let Some(name) = &options.dispatch_macro {
+        output.extend(dispatch_macro(name, types));
+    }
+    if let Some((vis, name)) = &options.layout_table {
+        output.extend(layout_table(vis, name, types, type_attrs));
+    }
+    if let Some((vis, name)) = &options.sorted_names {
+        output.extend(sorted_names(vis, name, types, type_attrs));
+    }
+    if let Some((vis, name)) = &options.name_fn {
+        output.extend(name_fn(vis, name, types, type_attrs));
+    }
+    if let Some((vis, name)) = &options.visitor {
+        let template = options.visit_method.as_ref();
+        output.extend(visitor(vis, name, template, generic_arg, types, type_attrs));
+    }
+    let alphabetical = options
+        .tag_order
+        .as_ref()
+        .map_or(false, |order| order == "alphabetical");
+    let positions = type_positions(alphabetical, types);
+    if let Some((vis, template)) = &options.tag_const {
+        let base = options.tag_base.as_ref();
+        output.extend(tag_consts(
+            vis,
+            template.as_ref(),
+            base,
+            generic_arg,
+            types,
+            &positions,
+            type_attrs,
+        ));
+    }
+    if let Some((vis, name)) = &options.tag_trait {
+        let base = options.tag_base.as_ref();
+        output.extend(tag_trait(vis, name, base, types, &positions, type_attrs));
+    }
+    if let Some((vis, template)) = &options.bit_const {
+        let bit_type = options.bit_type.as_ref();
+        output.extend(bit_consts(
+            vis,
+            template.as_ref(),
+            bit_type,
+            generic_arg,
+            types,
+            type_attrs,
+        ));
+    }
+    if let Some((vis, name, data)) = &options.error_enum {
+        let template = options.error_variant.as_ref();
+        output.extend(error_enum(
+            vis,
+            name,
+            data.as_ref(),
+            template,
+            generic_arg,
+            types,
+            type_attrs,
+        ));
+    }
+    if let Some((vis, name)) = &options.type_enum {
+        let all = options.enum_all;
+        output.extend(type_enum(vis, name, all, types, &positions, type_attrs));
+        if options.enum_try_from {
+            output.extend(enum_try_from(name));
+        }
+    }
+    if let Some((vis, name)) = &options.marker_trait {
+        output.extend(marker_trait(vis, name, types, type_attrs));
+    }
+    if let Some((vis, name)) = &options.as_any {
+        output.extend(as_any(vis, name, types, type_attrs));
+    }
+    if let Some((vis, name)) = &options.doc_table {
+        output.extend(doc_table(vis, name, generic_arg, types, type_attrs));
+    }
+    if let Some((vis, name)) = &options.factory {
+        output.extend(factory(vis, name, generic_arg, types, type_attrs, ast));
+    }
+    if let Some((vis, name)) = &options.ctor_array {
+        output.extend(ctor_array(vis, name, generic_arg, types, type_attrs, ast));
+    }
+    if let Some((trait_path, items)) = &options.blanket_impl {
+        output.extend(blanket_impl(trait_path, items, generic_arg, ast));
+    }
+    if let Some((name, code)) = &options.doctests {
+        output.extend(doctests(name, code, generic_arg, types, type_attrs));
+    }
+    if let Some((name, check)) = &options.roundtrip_test {
+        output.extend(roundtrip_test(name, check, types, type_attrs));
+    }
+    if let Some((name, ty, expr)) = &options.strategies {
+        let feature = options.strategy_feature.as_ref();
+        output.extend(strategies(
+            name,
+            ty,
+            expr,
+            feature,
+            generic_arg,
+            types,
+            type_attrs,
+        ));
+    }
+    if let Some(name) = &options.coherence_test {
+        output.extend(coherence_test(name, generic_arg, types, type_attrs, ast));
+    }
+    if let Some((vis, name, method)) = &options.type_id_map {
+        output.extend(type_id_map(
+            vis,
+            name,
+            method,
+            generic_arg,
+            types,
+            type_attrs,
+            ast,
+        ));
+    }
+    output
+}
+
+/// Applies the options to a copy of the code, and adds the items derived from its
+/// implementations, so that they're generated along with each copy.
+///
+/// `ty` is the type the copy was generated for.
+pub(crate) fn process_copy(options: &Options, ty: &SubstType, ast: &mut File) {
+    if !options.require_methods.is_empty() {
+        for item in &ast.items {
+            if let Item::Impl(item_impl) = item {
+                check_methods(ty, item_impl, &options.require_methods);
+            }
+        }
+    }
+    if options.builder {
+        let items = std::mem::take(&mut ast.items);
+        for item in items {
+            match item {
+                Item::Struct(template) => ast.items.extend(builder(ty, &template)),
+                _ => ast.items.push(item),
+            }
+        }
+    }
+    if options.newtype {
+        for item in &mut ast.items {
+            if let Item::Struct(template) = item {
+                newtype(ty, template);
+            }
+        }
+    }
+    if options.bench_fn {
+        bench_fn(ty, ast);
+    }
+    if options.self_static {
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                add_self_static(item_impl);
+            }
+        }
+    }
+    if options.replace_self {
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                replace_self(item_impl);
+            }
+        }
+    }
+    if options.forward {
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                expand_forward(item_impl);
+            }
+        }
+    }
+    if options.inline_never {
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                add_inline_never(item_impl);
+            }
+        }
+    }
+    if let Some(version) = &options.schema_version {
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                add_schema_version(ty, item_impl, version);
+            }
+        }
+    }
+    if let Some(method) = &options.type_tag {
+        let full = options
+            .type_tag_path
+            .as_ref()
+            .map_or(false, |form| form == "full");
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                add_type_tag(ty, item_impl, method, full);
+            }
+        }
+    }
+    if let Some(mac) = &options.trace_calls {
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                add_trace_calls(ty, item_impl, mac);
+            }
+        }
+    }
+    if let Some(methods) = &options.doc_alias {
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                add_doc_alias(ty, item_impl, methods);
+            }
+        }
+    }
+    let mut new_items = Vec::new();
+    if options.slice_impl {
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                new_items.push(slice_impl(item_impl));
+            }
+        }
+    }
+    if options.ord || options.partial_ord {
+        for item in &ast.items {
+            if let Item::Struct(template) = item {
+                new_items.extend(ordering(template, options.ord));
+            }
+        }
+    }
+    if options.from_roundtrip_tests {
+        let tests = ast
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Impl(item_impl) => from_roundtrip_test(item_impl),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if tests.is_empty() {
+            abort!(
+                ty.span(),
+                "from_roundtrip_tests requires a 'From' trait implementation for '{}'",
+                pathname(ty)
+            );
+        }
+        new_items.extend(tests);
+    }
+    if options.bidirectional {
+        for item in &mut ast.items {
+            if let Item::Impl(item_impl) = item {
+                new_items.push(reverse_from(item_impl));
+            }
+        }
+    }
+    for item in &ast.items {
+        if let Item::Impl(item_impl) = item {
+            if options.forward_arc_mutex {
+                new_items.push(forward_arc_mutex(item_impl));
+            }
+            if options.object_safe {
+                new_items.extend(object_safety_check(item_impl));
+            }
+        }
+    }
+    ast.items.extend(new_items);
+    if options.doc_hidden {
+        add_attributes(ast, &[syn::parse_quote!(#[doc(hidden)])]);
+    }
+    if !options.allow.is_empty() {
+        let lints = &options.allow;
+        add_attributes(ast, &[syn::parse_quote!(#[allow(#(#lints),*)])]);
+    }
+    if let Some(limit) = &options.max_size {
+        ast.items.push(size_check(ty, limit));
+    }
+    if let Some(bounds) = &options.assert_bounds {
+        ast.items.push(bounds_check(ty, bounds));
+    }
+    if options.try_from_name {
+        ast.items.push(try_from_name(ty));
+    }
+    if let Some(mac) = &options.register {
+        ast.items.push(syn::parse_quote!(#mac!(#ty);));
+    }
+    if options.anon_const {
+        let items = std::mem::take(&mut ast.items);
+        ast.items
+            .push(syn::parse_quote!(const _: () = { #(#items)* };));
+    }
+}
+
+/// Checks that the implementation generated for `ty` defines all the `methods`, or aborts with
+/// the list of the missing ones.
+fn check_methods(ty: &SubstType, item_impl: &ItemImpl, methods: &[Ident]) {
+    let missing = methods
+        .iter()
+        .filter(|name| {
+            !item_impl
+                .items
+                .iter()
+                .any(|item| matches!(item, ImplItem::Method(method) if &method.sig.ident == *name))
+        })
+        .collect::<Vec<_>>();
+    if let Some(first) = missing.first() {
+        abort!(
+            first.span(),
+            "the implementation for '{}' is missing the required method(s) {}",
+            pathname(ty),
+            missing
+                .iter()
+                .map(|name| format!("'{}'", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+}
+
+/// Adds `#[inline(never)]` to the methods of the implementation, replacing their `inline`
+/// attributes if any.
+fn add_inline_never(item_impl: &mut ItemImpl) {
+    for item in &mut item_impl.items {
+        if let ImplItem::Method(method) = item {
+            method.attrs.retain(|a| !a.path.is_ident("inline"));
+            method.attrs.push(syn::parse_quote!(#[inline(never)]));
+        }
+    }
+}
+
+/// Inserts `const SCHEMA_VERSION: u32 = version;` at the start of the implementation. Aborts if
+/// the implementation already defines that constant.
+fn add_schema_version(ty: &SubstType, item_impl: &mut ItemImpl, version: &Expr) {
+    let existing = item_impl.items.iter().find_map(|item| match item {
+        ImplItem::Const(item_const) if item_const.ident == "SCHEMA_VERSION" => Some(item_const),
+        _ => None,
+    });
+    if let Some(item_const) = existing {
+        abort!(item_const.ident.span(),
+            "SCHEMA_VERSION is already defined for '{}'", pathname(ty);
+            help = "remove it to use the value of the schema_version option"
+        );
+    }
+    item_impl.items.insert(
+        0,
+        syn::parse_quote_spanned!(version.span()=> const SCHEMA_VERSION: u32 = #version;),
+    );
+}
+
+/// Inserts `fn method(&self) -> &'static str` at the start of the implementation, returning the
+/// name of `ty`: its full path if `full` is true, or the identifier of its last segment. Aborts if
+/// the implementation already defines that method.
+fn add_type_tag(ty: &SubstType, item_impl: &mut ItemImpl, method: &Ident, full: bool) {
+    let existing = item_impl.items.iter().find_map(|item| match item {
+        ImplItem::Method(item_method) if &item_method.sig.ident == method => Some(item_method),
+        _ => None,
+    });
+    if let Some(item_method) = existing {
+        abort!(item_method.sig.ident.span(),
+            "'{}' is already defined for '{}'", method, pathname(ty);
+            help = "remove it or give another name to the type_tag option"
+        );
+    }
+    let tag = if full {
+        pathname(ty)
+    } else {
+        match type_ident(ty) {
+            Some(ident) => ident.to_string(),
+            None => abort!(ty.span(),
+                "type_tag requires type paths, not '{}'", pathname(ty);
+                help = "use 'type_tag_path = full' to return the full type name"
+            ),
+        }
+    };
+    item_impl.items.insert(
+        0,
+        syn::parse_quote_spanned!(method.span()=>
+            fn #method(&self) -> &'static str {
+                #tag
+            }
+        ),
+    );
+}
+
+/// Inserts a call to the macro `mac` at the start of each method of the implementation, with
+/// the type and method names as argument: `mac!("<Type as Trait>::method")`, or
+/// `mac!("Type::method")` in a type implementation.
+fn add_trace_calls(ty: &SubstType, item_impl: &mut ItemImpl, mac: &Path) {
+    let prefix = match &item_impl.trait_ {
+        Some((_, path, _)) => format!("<{} as {}>", pathname(ty), pathname(path)),
+        None => pathname(ty),
+    };
+    for item in &mut item_impl.items {
+        if let ImplItem::Method(method) = item {
+            let name = format!("{}::{}", prefix, method.sig.ident);
+            method
+                .block
+                .stmts
+                .insert(0, syn::parse_quote!(#mac!(#name);));
+        }
+    }
+}
+
+/// Adds a `#[doc(alias = "type")]` attribute to the methods of the implementation, or only to
+/// those in `methods` if it's not empty.
+fn add_doc_alias(ty: &SubstType, item_impl: &mut ItemImpl, methods: &[Ident]) {
+    let alias = pathname(ty);
+    for item in &mut item_impl.items {
+        if let ImplItem::Method(method) = item {
+            if methods.is_empty() || methods.contains(&method.sig.ident) {
+                method.attrs.push(syn::parse_quote!(#[doc(alias = #alias)]));
+            }
+        }
+    }
+}
+
+/// Adds a `Self: 'static` predicate to the `where` clause of the implementation.
+fn add_self_static(item_impl: &mut ItemImpl) {
+    item_impl
+        .generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote!(Self: 'static));
+}
+
+/// Replaces `Self` by the implementing type in the bodies of the methods.
+fn replace_self(item_impl: &mut ItemImpl) {
+    let assoc_types = item_impl
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Type(t) => Some(t.ident.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut visitor = ReplaceSelf {
+        self_ty: (*item_impl.self_ty).clone(),
+        assoc_types,
+    };
+    for item in &mut item_impl.items {
+        if let ImplItem::Method(method) = item {
+            visitor.visit_block_mut(&mut method.block);
+        }
+    }
+}
+
+/// Replaces `Self` by `self_ty`, except in
+/// - the projections on the associated types of the implementation (`Self::Output`),
+/// - the paths other than `Self` alone when `self_ty` isn't a path (`Self::new()` for `&u8`),
+/// - the nested items, where `Self` may designate another type,
+/// - the macro arguments.
+struct ReplaceSelf {
+    self_ty: Type,
+    assoc_types: Vec<Ident>,
+}
+
+impl VisitMut for ReplaceSelf {
+    fn visit_item_mut(&mut self, _node: &mut Item) {
+        // nested items aren't visited
+    }
+
+    fn visit_type_mut(&mut self, node: &mut Type) {
+        match node {
+            Type::Path(p) if p.qself.is_none() && p.path.is_ident("Self") => {
+                *node = self.self_ty.clone();
+            }
+            _ => syn::visit_mut::visit_type_mut(self, node),
+        }
+    }
+
+    fn visit_path_mut(&mut self, node: &mut Path) {
+        let is_self = node.leading_colon.is_none()
+            && node
+                .segments
+                .first()
+                .map_or(false, |seg| seg.ident == "Self");
+        let is_projection = node
+            .segments
+            .iter()
+            .nth(1)
+            .map_or(false, |seg| self.assoc_types.contains(&seg.ident));
+        if is_self && !is_projection {
+            if let Type::Path(TypePath { qself: None, path }) = &self.self_ty {
+                let mut segments = path.segments.clone();
+                for segment in segments.iter_mut() {
+                    if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+                        args.colon2_token = Some(Default::default());
+                    }
+                }
+                segments.extend(std::mem::take(&mut node.segments).into_iter().skip(1));
+                node.leading_colon = path.leading_colon;
+                node.segments = segments;
+            }
+        }
+        syn::visit_mut::visit_path_mut(self, node);
+    }
+}
+
+/// Generates the turbofish passing the type parameters of a method to another method with
+/// the same type parameters (`::<A, B>`), or nothing if there aren't any.
+fn turbofish(sig: &Signature) -> TokenStream {
+    let type_params = sig
+        .generics
+        .type_params()
+        .map(|p| &p.ident)
+        .collect::<Vec<_>>();
+    if type_params.is_empty() {
+        quote!()
+    } else {
+        quote!(::<#(#type_params),*>)
+    }
+}
+
+/// Gets the identifiers of the arguments of a method, without its receiver, or aborts if an
+/// argument isn't a simple identifier.
+fn arg_idents<'a>(sig: &'a Signature, option: &str) -> Vec<&'a Ident> {
+    sig.inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Receiver(_) => None,
+            FnArg::Typed(pat_type) => match pat_type.pat.as_ref() {
+                Pat::Ident(PatIdent {
+                    ident,
+                    subpat: None,
+                    ..
+                }) => Some(ident),
+                pat => abort!(pat.span(), "{} requires identifiers as arguments", option),
+            },
+        })
+        .collect()
+}
+
+/// Name prefix of the methods giving the body of the slice implementation with the `slice_impl`
+/// option
+const SLICE_PREFIX: &str = "slice_";
+
+/// Generates the implementation of the trait for a slice of the type, where the methods are
+/// either given in the template by a method of the same name prefixed with `slice_`, which is
+/// removed from the original implementation, or, if they return `()`, delegated element-wise:
+///
+/// ```text
+/// impl Trait for [T] {
+///     fn method(&self, arg1: A) {
+///         for elem in self.iter() {
+///             <T as Trait>::method(elem, arg1);
+///         }
+///     }
+///     fn method2(&self) -> R { /* body of slice_method2 */ }
+/// }
+/// ```
+fn slice_impl(item_impl: &mut ItemImpl) -> Item {
+    let self_ty = item_impl.self_ty.clone();
+    let trait_path = match &item_impl.trait_ {
+        Some((None, path, _)) => path.clone(),
+        _ => abort!(self_ty.span(), "slice_impl requires a trait implementation"),
+    };
+    // extracts the slice versions of the methods:
+    let mut slice_methods = Vec::new();
+    item_impl.items.retain(|item| match item {
+        ImplItem::Method(method) if method.sig.ident.to_string().starts_with(SLICE_PREFIX) => {
+            slice_methods.push(method.clone());
+            false
+        }
+        _ => true,
+    });
+    let mut items = Vec::new();
+    for item in &item_impl.items {
+        match item {
+            ImplItem::Method(method) => {
+                let ident = &method.sig.ident;
+                let slice_name = format!("{}{}", SLICE_PREFIX, ident);
+                let position = slice_methods.iter().position(|m| m.sig.ident == slice_name);
+                if let Some(position) = position {
+                    let mut slice_method = slice_methods.remove(position);
+                    slice_method.sig.ident = ident.clone();
+                    items.push(quote!(#slice_method));
+                    continue;
+                }
+                let sig = &method.sig;
+                let iter = match sig.inputs.first() {
+                    Some(FnArg::Receiver(r)) if r.reference.is_some() => {
+                        if r.mutability.is_some() {
+                            quote!(iter_mut)
+                        } else {
+                            quote!(iter)
+                        }
+                    }
+                    _ => abort!(
+                        ident.span(),
+                        "method '{}' must take '&self' or '&mut self' to be delegated element-wise, or be given by a '{}' method",
+                        ident,
+                        slice_name
+                    ),
+                };
+                if !matches!(sig.output, ReturnType::Default) || sig.asyncness.is_some() {
+                    abort!(
+                        ident.span(),
+                        "method '{}' must return '()' to be delegated element-wise, or be given by a '{}' method",
+                        ident,
+                        slice_name
+                    );
+                }
+                let mut find_self = FindSelf::default();
+                find_self.visit_signature_mut(&mut sig.clone());
+                if let Some(span) = find_self.0 {
+                    abort!(
+                        span,
+                        "'Self' cannot be delegated element-wise by slice_impl"
+                    );
+                }
+                let args = arg_idents(sig, "slice_impl");
+                let turbofish = turbofish(sig);
+                items.push(quote!(
+                    #sig {
+                        for elem in self.#iter() {
+                            <#self_ty as #trait_path>::#ident #turbofish(elem, #(#args),*);
+                        }
+                    }
+                ));
+            }
+            _ => items.push(quote!(#item)),
+        }
+    }
+    if let Some(method) = slice_methods.first() {
+        abort!(
+            method.sig.ident.span(),
+            "'{}' doesn't correspond to any method",
+            method.sig.ident
+        );
+    }
+    let ItemImpl {
+        attrs,
+        unsafety,
+        generics,
+        ..
+    } = item_impl;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    syn::parse_quote!(
+        #(#attrs)*
+        #unsafety impl #impl_generics #trait_path for [#self_ty] #where_clause {
+            #(#items)*
+        }
+    )
+}
+
+/// Name of the marker macro replaced by a forwarding body with the `forward` option
+pub(crate) const FORWARD_MACRO: &str = "forward";
+
+/// Arguments of the forwarding marker: `forward!(Target)` or `forward!(Target, receiver)`
+pub(crate) struct ForwardArgs {
+    pub(crate) target: Type,
+    pub(crate) receiver: Option<(Token![,], Expr)>,
+}
+
+impl Parse for ForwardArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let target = input.parse()?;
+        let receiver = if input.is_empty() {
+            None
+        } else {
+            Some((input.parse()?, input.parse()?))
+        };
+        Ok(ForwardArgs { target, receiver })
+    }
+}
+
+impl ToTokens for ForwardArgs {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.target.to_tokens(tokens);
+        if let Some((comma, receiver)) = &self.receiver {
+            comma.to_tokens(tokens);
+            receiver.to_tokens(tokens);
+        }
+    }
+}
+
+/// Gets the marker macro if it's the only statement of the block.
+fn forward_marker(block: &Block) -> Option<&Macro> {
+    match block.stmts.as_slice() {
+        [Stmt::Expr(Expr::Macro(m)) | Stmt::Semi(Expr::Macro(m), _)] => Some(&m.mac),
+        [Stmt::Item(Item::Macro(m))] => Some(&m.mac),
+        _ => None,
+    }
+    .filter(|mac| mac.path.is_ident(FORWARD_MACRO))
+}
+
+/// Replaces the body of the methods made of a `forward!(Target, receiver)` marker by a call to
+/// the same method of the implementation of the trait for `Target`:
+///
+/// ```text
+/// fn method(&self, arg1: A) -> R {
+///     <Target as Trait>::method(receiver, arg1)
+/// }
+/// ```
+///
+/// The receiver expression is required if the method has a receiver, and the other arguments
+/// must be identifiers.
+fn expand_forward(item_impl: &mut ItemImpl) {
+    let trait_path = match &item_impl.trait_ {
+        Some((None, path, _)) => path.clone(),
+        _ => {
+            if let Some(mac) = item_impl.items.iter().find_map(|item| match item {
+                ImplItem::Method(method) => forward_marker(&method.block),
+                _ => None,
+            }) {
+                abort!(mac.span(), "forward! requires a trait implementation");
+            }
+            return;
+        }
+    };
+    for item in &mut item_impl.items {
+        if let ImplItem::Method(method) = item {
+            let args = match forward_marker(&method.block) {
+                Some(mac) => match mac.parse_body::<ForwardArgs>() {
+                    Ok(args) => args,
+                    Err(e) => abort!(e.span(), "{}", e),
+                },
+                None => continue,
+            };
+            let mut call_args = Vec::new();
+            match (method.sig.inputs.first(), &args.receiver) {
+                (Some(FnArg::Receiver(_)), Some((_, receiver))) => {
+                    call_args.push(quote!(#receiver))
+                }
+                (Some(FnArg::Receiver(r)), None) => abort!(
+                    r.span(),
+                    "method '{}' has a receiver, which must be given to forward!: forward!(Target, receiver)",
+                    method.sig.ident
+                ),
+                (_, Some((_, receiver))) => abort!(
+                    receiver.span(),
+                    "method '{}' has no receiver",
+                    method.sig.ident
+                ),
+                (_, None) => {}
+            }
+            let idents = arg_idents(&method.sig, "forward!");
+            call_args.extend(idents.iter().map(|ident| quote!(#ident)));
+            let target = &args.target;
+            let ident = &method.sig.ident;
+            let turbofish = turbofish(&method.sig);
+            let call = quote!(<#target as #trait_path>::#ident #turbofish(#(#call_args),*));
+            method.block = if method.sig.asyncness.is_some() {
+                syn::parse_quote!({ #call.await })
+            } else {
+                syn::parse_quote!({ #call })
+            };
+        }
+    }
+}
+
+/// Generates a test of the roundtrip conversion of `impl From<A> for B` with the default value
+/// of `A`, or `None` if it's another implementation:
+///
+/// ```text
+/// #[cfg(test)]
+/// #[test]
+/// fn from_roundtrip_a_b() {
+///     let converted: B = From::from(<A as Default>::default());
+///     let back: A = Into::into(converted);
+///     assert_eq!(back, <A as Default>::default());
+/// }
+/// ```
+///
+/// The test is named after the identifiers of the types in snake case, so they must be type paths.
+fn from_roundtrip_test(item_impl: &ItemImpl) -> Option<Item> {
+    let source_ty = match &item_impl.trait_ {
+        Some((None, path, _)) => match path.segments.last() {
+            Some(PathSegment {
+                ident,
+                arguments: PathArguments::AngleBracketed(args),
+            }) if ident == "From" && args.args.len() == 1 => match args.args.first() {
+                Some(GenericArgument::Type(ty)) => ty,
+                _ => return None,
+            },
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let target_ty = item_impl.self_ty.as_ref();
+    let mut names = Vec::new();
+    for ty in [source_ty, target_ty] {
+        match ty {
+            Type::Path(TypePath { qself: None, path }) => {
+                let ident = &path.segments.last().unwrap().ident;
+                names.push(to_snake_case(&ident.to_string()));
+            }
+            _ => abort!(
+                ty.span(),
+                "from_roundtrip_tests requires type paths, not '{}'",
+                pathname(ty)
+            ),
+        }
+    }
+    let name = format_ident!("from_roundtrip_{}_{}", names[0], names[1]);
+    Some(syn::parse_quote!(
+        #[cfg(test)]
+        #[test]
+        fn #name() {
+            let converted: #target_ty =
+                ::core::convert::From::from(<#source_ty as ::core::default::Default>::default());
+            let back: #source_ty = ::core::convert::Into::into(converted);
+            assert_eq!(back, <#source_ty as ::core::default::Default>::default());
+        }
+    ))
+}
+
+/// Name of the method giving the body of the reverse conversion with the `bidirectional` option
+const REVERSE_METHOD: &str = "reverse";
+
+/// Extracts the `reverse` method from an `impl From<A> for B`, and uses it to generate the
+/// reverse implementation:
+///
+/// ```text
+/// impl From<B> for A {
+///     fn from(value: B) -> A { /* body of reverse */ }
+/// }
+/// ```
+fn reverse_from(item_impl: &mut ItemImpl) -> Item {
+    let self_ty = item_impl.self_ty.clone();
+    let mut trait_path = match &item_impl.trait_ {
+        Some((None, path, _)) => path.clone(),
+        _ => abort!(
+            self_ty.span(),
+            "bidirectional requires a 'From' trait implementation"
+        ),
+    };
+    let source_ty = match trait_path.segments.last_mut() {
+        Some(PathSegment {
+            ident,
+            arguments: PathArguments::AngleBracketed(args),
+        }) if ident == "From" && args.args.len() == 1 => match args.args.first_mut() {
+            Some(GenericArgument::Type(ty)) => {
+                // the trait of the reverse implementation is From<B>:
+                std::mem::replace(ty, self_ty.as_ref().clone())
+            }
+            _ => abort!(args.span(), "bidirectional requires a 'From<Type>' trait"),
+        },
+        _ => abort!(
+            trait_path.span(),
+            "bidirectional requires a 'From' trait implementation"
+        ),
+    };
+    let position = item_impl.items.iter().position(
+        |item| matches!(item, ImplItem::Method(method) if method.sig.ident == REVERSE_METHOD),
+    );
+    let mut reverse = match position.map(|i| item_impl.items.remove(i)) {
+        Some(ImplItem::Method(method)) => method,
+        _ => abort!(
+            self_ty.span(),
+            "bidirectional requires a '{}' method giving the reverse conversion",
+            REVERSE_METHOD
+        ),
+    };
+    let mut find_self = FindSelf::default();
+    find_self.visit_impl_item_method_mut(&mut reverse);
+    if let Some(span) = find_self.0 {
+        abort!(span, "'Self' is ambiguous in the reverse conversion");
+    }
+    reverse.sig.ident = Ident::new("from", reverse.sig.ident.span());
+    let ItemImpl {
+        attrs,
+        unsafety,
+        generics,
+        ..
+    } = item_impl;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    syn::parse_quote!(
+        #(#attrs)*
+        #unsafety impl #impl_generics #trait_path for #source_ty #where_clause {
+            #reverse
+        }
+    )
+}
+
+/// Looks for a bare `Self` type, which can't be forwarded because it designates another type
+/// in the forwarding implementation (`Self::Output` is fine).
+#[derive(Default)]
+struct FindSelf(Option<Span>);
+
+impl VisitMut for FindSelf {
+    fn visit_type_mut(&mut self, node: &mut Type) {
+        match node {
+            Type::Path(p) if p.qself.is_none() && p.path.is_ident("Self") => {
+                self.0 = Some(p.span())
+            }
+            _ => syn::visit_mut::visit_type_mut(self, node),
+        }
+    }
+}
+
+/// Generates the implementation forwarding the trait methods of `Arc<Mutex<T>>` to `T`:
+///
+/// ```text
+/// impl Trait for ::std::sync::Arc<::std::sync::Mutex<T>> {
+///     type Output = <T as Trait>::Output;
+///     fn method(&self, arg1: A) -> R {
+///         <T as Trait>::method(&*self.lock().unwrap(), arg1)
+///     }
+/// }
+/// ```
+///
+/// The methods must take `&self` and return owned values, whose types can only have `'static`
+/// lifetimes, since the lock is released when they return. The signatures of the methods and the
+/// types of the constants can't include a bare `Self`.
+fn forward_arc_mutex(item_impl: &ItemImpl) -> Item {
+    let ItemImpl {
+        unsafety,
+        generics,
+        trait_,
+        self_ty,
+        items,
+        ..
+    } = item_impl;
+    let trait_path = match trait_ {
+        Some((None, path, _)) => path,
+        _ => abort!(
+            self_ty.span(),
+            "forward_arc_mutex requires a trait implementation"
+        ),
+    };
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let inner = quote!(<#self_ty as #trait_path>);
+    let mut forwarded = Vec::new();
+    for item in items {
+        match item {
+            ImplItem::Method(method) => {
+                let mut sig = method.sig.clone();
+                if let Some(asyncness) = &sig.asyncness {
+                    abort!(
+                        asyncness.span(),
+                        "forward_arc_mutex doesn't support async methods"
+                    );
+                }
+                match sig.inputs.first() {
+                    Some(FnArg::Receiver(r)) if r.reference.is_some() && r.mutability.is_none() => {
+                    }
+                    _ => abort!(
+                        sig.ident.span(),
+                        "method '{}' must take '&self' to be forwarded by forward_arc_mutex",
+                        sig.ident
+                    ),
+                }
+                if let ReturnType::Type(_, ty) = &sig.output {
+                    let mut finder = FindLifetime(None);
+                    finder.visit_type_mut(&mut ty.as_ref().clone());
+                    if let Some(span) = finder.0 {
+                        abort!(span,
+                            "method '{}' must return an owned value to be forwarded by forward_arc_mutex",
+                            sig.ident;
+                            help = "only the 'static lifetime can be used in the returned type"
+                        );
+                    }
+                }
+                let mut find_self = FindSelf::default();
+                find_self.visit_signature_mut(&mut sig);
+                if let Some(span) = find_self.0 {
+                    abort!(span, "'Self' cannot be forwarded by forward_arc_mutex");
+                }
+                // the arguments are renamed, since their patterns can't always be used as expressions:
+                let mut args = Vec::new();
+                for (i, input) in sig.inputs.iter_mut().skip(1).enumerate() {
+                    if let FnArg::Typed(pat_type) = input {
+                        let arg = format_ident!("arg{}", i + 1);
+                        *pat_type.pat = syn::parse_quote!(#arg);
+                        args.push(arg);
+                    }
+                }
+                let ident = &sig.ident;
+                let turbofish = turbofish(&sig);
+                forwarded.push(quote!(
+                    #sig {
+                        #inner::#ident #turbofish(&*self.lock().unwrap(), #(#args),*)
+                    }
+                ));
+            }
+            ImplItem::Type(ty) => {
+                let ident = &ty.ident;
+                let (ty_impl_generics, ty_generics, ty_where_clause) = ty.generics.split_for_impl();
+                forwarded.push(quote!(
+                    type #ident #ty_impl_generics = #inner::#ident #ty_generics #ty_where_clause;
+                ));
+            }
+            ImplItem::Const(c) => {
+                let mut find_self = FindSelf::default();
+                find_self.visit_type_mut(&mut c.ty.clone());
+                if let Some(span) = find_self.0 {
+                    abort!(span, "'Self' cannot be forwarded by forward_arc_mutex");
+                }
+                let ident = &c.ident;
+                let ty = &c.ty;
+                forwarded.push(quote!(const #ident: #ty = #inner::#ident;));
+            }
+            _ => abort!(
+                item.span(),
+                "forward_arc_mutex only supports methods, types and constants"
+            ),
+        }
+    }
+    syn::parse_quote!(
+        #unsafety impl #impl_generics #trait_path for ::std::sync::Arc<::std::sync::Mutex<#self_ty>> #where_clause {
+            #(#forwarded)*
+        }
+    )
+}
+
+/// Generates a compile-time check that the size of `ty` doesn't exceed `limit`, spanned on the
+/// type so that the compiler points at it in the attribute:
+///
+/// ```text
+/// const _: () = assert!(::core::mem::size_of::<Type>() <= (LIMIT), "message");
+/// ```
+fn size_check(ty: &SubstType, limit: &Expr) -> Item {
+    let message = format!(
+        "the size of '{}' exceeds the max_size limit '{}'",
+        pathname(ty),
+        pathname(limit)
+    );
+    let check = quote_spanned!(ty.span()=>
+        assert!(::core::mem::size_of::<#ty>() <= (#limit), #message)
+    );
+    syn::parse_quote!(const _: () = #check;)
+}
+
+/// Generates a compile-time check that `ty` satisfies the `bounds`, or `Send + Sync` if there
+/// are none, spanned on the type so that the compiler points at it in the attribute:
+///
+/// ```text
+/// const _: fn() = || {
+///     fn assert_bounds<TraitGenSelf: Send + Sync>() {}
+///     assert_bounds::<Type>();
+/// };
+/// ```
+fn bounds_check(ty: &SubstType, bounds: &[TypeParamBound]) -> Item {
+    let bounds = if bounds.is_empty() {
+        quote!(::core::marker::Send + ::core::marker::Sync)
+    } else {
+        quote!(#(#bounds)+*)
+    };
+    let check = quote_spanned!(ty.span()=> assert_bounds::<#ty>(););
+    syn::parse_quote!(
+        const _: fn() = || {
+            fn assert_bounds<TraitGenSelf: #bounds>() {}
+            #check
+        };
+    )
+}
+
+/// Generates the conversion from the name of `ty` to its default value, which gives the name
+/// back as error if it doesn't match:
+///
+/// ```text
+/// impl<'name> TryFrom<&'name str> for Type {
+///     type Error = &'name str;
+///     fn try_from(name: &'name str) -> Result<Self, &'name str> {
+///         if name == "Type" { Ok(Default::default()) } else { Err(name) }
+///     }
+/// }
+/// ```
+///
+/// The implementation is only allowed by the orphan rule for local types, so the types that are
+/// foreign for sure (primitive types, types of the standard library, tuples, arrays and slices)
+/// are rejected with a clearer error than the compiler's.
+fn try_from_name(ty: &SubstType) -> Item {
+    let name = pathname(ty);
+    let path = match ty {
+        SubstType::Path(path) | SubstType::Type(Type::Path(TypePath { path, .. })) => Some(path),
+        _ => None,
+    };
+    let foreign = match (path, ty) {
+        (Some(path), _) => {
+            let first = path.segments.first().map(|s| s.ident.to_string());
+            let std = matches!(first.as_deref(), Some("std" | "core" | "alloc"));
+            let builtin = path.get_ident().map_or(false, |ident| {
+                [
+                    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16",
+                    "i32", "i64", "i128", "isize", "f32", "f64", "String",
+                ]
+                .iter()
+                .any(|p| ident == p)
+            });
+            std || builtin
+        }
+        (None, SubstType::Type(t)) => matches!(
+            t,
+            Type::Tuple(_) | Type::Array(_) | Type::Slice(_) | Type::Never(_)
+        ),
+        _ => false,
+    };
+    if foreign {
+        abort!(ty.span(),
+            "try_from_name can't implement TryFrom<&str> for the foreign type '{}'", name;
+            help = "the orphan rule only allows the implementation for the types defined in the crate"
+        );
+    }
+    syn::parse_quote!(
+        impl<'name> ::core::convert::TryFrom<&'name str> for #ty {
+            type Error = &'name str;
+
+            fn try_from(name: &'name str) -> ::core::result::Result<Self, &'name str> {
+                if name == #name {
+                    ::core::result::Result::Ok(::core::default::Default::default())
+                } else {
+                    ::core::result::Result::Err(name)
+                }
+            }
+        }
+    )
+}
+
+/// Generates a compile-time check that the trait of `item_impl` can be used as a trait object,
+/// with the associated types of the implementation:
+///
+/// ```text
+/// const _: () = {
+///     fn _assert_object_safe<'a>(_: &dyn Trait<'a, Assoc = Type>) {}
+/// };
+/// ```
+///
+/// The check is spanned on the trait, so that the compiler points at it when the trait isn't
+/// object-safe. There's no check for type implementations.
+fn object_safety_check(item_impl: &ItemImpl) -> Option<Item> {
+    let trait_path = match &item_impl.trait_ {
+        Some((None, path, _)) => path,
+        _ => return None,
+    };
+    let bindings = item_impl
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Type(t) if t.generics.params.is_empty() => {
+                let (ident, ty) = (&t.ident, &t.ty);
+                Some(syn::parse_quote!(#ident = #ty))
+            }
+            _ => None,
+        })
+        .collect::<Vec<GenericArgument>>();
+    let mut dyn_path = trait_path.clone();
+    if !bindings.is_empty() {
+        let segment = dyn_path.segments.last_mut().unwrap();
+        match &mut segment.arguments {
+            PathArguments::AngleBracketed(args) => args.args.extend(bindings),
+            arguments => {
+                *arguments = PathArguments::AngleBracketed(syn::parse_quote!(<#(#bindings),*>))
+            }
+        }
+    }
+    let (impl_generics, _, where_clause) = item_impl.generics.split_for_impl();
+    let dyn_type = quote_spanned!(trait_path.span()=> &dyn #dyn_path);
+    Some(syn::parse_quote!(
+        const _: () = {
+            fn _assert_object_safe #impl_generics (_: #dyn_type) #where_clause {}
+        };
+    ))
+}
+
+/// Gets the identifier of a type path, without its path and generic arguments (`Meter` for
+/// `units::Meter<f32>`), which can be used to build the names of generated items.
+fn type_ident(ty: &SubstType) -> Option<&Ident> {
+    match ty {
+        SubstType::Path(path) => path.segments.last().map(|seg| &seg.ident),
+        SubstType::Type(Type::Path(TypePath { qself: None, path })) => {
+            path.segments.last().map(|seg| &seg.ident)
+        }
+        _ => None,
+    }
+}
+
+/// Converts a camel case identifier into snake case (`BigInt` -> `big_int`).
+fn to_snake_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_uppercase() && prev_lower {
+            result.push('_');
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+/// Converts the first letter of an identifier to upper case (`u8` -> `U8`), so it can be used
+/// as a prefix in camel case identifiers.
+fn to_camel_case(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Replaces the `#[deprecated]` attributes without arguments in `attrs` by
+/// `#[deprecated(note = "note")]`, where the note is given by the `template`, in which `${T}` is
+/// replaced by the name of `ty`. Returns true if an attribute was replaced.
+pub(crate) fn deprecation_note(
+    template: &LitStr,
+    generic_arg: &Path,
+    ty: &SubstType,
+    attrs: &mut [Attribute],
+) -> bool {
+    let mut replaced = false;
+    for attr in attrs {
+        if attr.path.is_ident("deprecated") && attr.tokens.is_empty() {
+            let note = template
+                .value()
+                .replace(&format!("${{{}}}", pathname(generic_arg)), &pathname(ty));
+            let note = LitStr::new(&note, template.span());
+            *attr = syn::parse_quote_spanned!(attr.span()=> #[deprecated(note = #note)]);
+            replaced = true;
+        }
+    }
+    replaced
+}
+
+/// Generates the `#[cfg(feature = "name")]` attribute gating the code generated for `ty`, where
+/// the name is given by the `template`, in which `${T}` is replaced by the type identifier in
+/// the given `case` (snake case by default). Without template, the name is the type identifier
+/// in that case.
+pub(crate) fn feature_gate(
+    template: Option<&LitStr>,
+    case: Option<&Ident>,
+    generic_arg: &Path,
+    ty: &SubstType,
+) -> syn::Result<Attribute> {
+    let ident = type_ident(ty).ok_or_else(|| {
+        Error::new(
+            ty.span(),
+            format!(
+                "gate_by_feature requires type paths, not '{}'",
+                pathname(ty)
+            ),
+        )
+    })?;
+    let ident = ident.to_string();
+    let name = match case.map(|c| c.to_string()).as_deref() {
+        Some("kebab") => to_snake_case(&ident).replace('_', "-"),
+        Some("lower") => ident.to_lowercase(),
+        Some("exact") => ident,
+        _ => to_snake_case(&ident),
+    };
+    let feature = match template {
+        Some(template) => template
+            .value()
+            .replace(&format!("${{{}}}", pathname(generic_arg)), &name),
+        None => name,
+    };
+    Ok(syn::parse_quote!(#[cfg(feature = #feature)]))
+}
+
+/// Gets the type argument of `Option<T>`, if `ty` is an optional type.
+fn option_arg(ty: &Type) -> Option<&Type> {
+    if let Type::Path(TypePath { qself: None, path }) = ty {
+        let seg = path.segments.last()?;
+        if seg.ident == "Option" {
+            if let PathArguments::AngleBracketed(args) = &seg.arguments {
+                if let Some(GenericArgument::Type(arg)) = args.args.first() {
+                    return Some(arg);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Generates the builder of type `ty` described by the `template` structure, whose fields are
+/// those of `ty`:
+///
+/// ```text
+/// struct TypeBuilder {
+///     field: Option<F>,
+/// }
+/// impl Default for TypeBuilder { ... }
+/// impl TypeBuilder {
+///     fn new() -> Self;
+///     fn field(mut self, field: F) -> Self;
+///     fn build(self) -> Result<Type, &'static str>;
+/// }
+/// impl Type {
+///     fn builder() -> TypeBuilder;
+/// }
+/// ```
+///
+/// Optional fields of type `Option<F>` are set with an `F` value, and are `None` by default.
+/// The other fields must be set before calling `build`, which fails otherwise.
+fn builder(ty: &SubstType, template: &ItemStruct) -> Vec<Item> {
+    let ItemStruct {
+        attrs,
+        vis,
+        ident,
+        generics,
+        fields,
+        ..
+    } = template;
+    if !generics.params.is_empty() {
+        abort!(generics.span(), "builder templates can't be generic");
+    }
+    let fields = match fields {
+        Fields::Named(fields) => &fields.named,
+        _ => abort!(ident.span(), "builder templates must have named fields"),
+    };
+    let target_ident = match type_ident(ty) {
+        Some(target_ident) => target_ident,
+        None => abort!(
+            ident.span(),
+            "builder requires type paths, not '{}'",
+            pathname(ty)
+        ),
+    };
+    let builder_ident = format_ident!("{}{}", to_camel_case(&target_ident.to_string()), ident);
+    // the target must be written with a turbofish to be constructed (`Meter::<f32> { ... }`):
+    let mut target_path: Path = syn::parse_quote!(#ty);
+    for seg in target_path.segments.iter_mut() {
+        if let PathArguments::AngleBracketed(args) = &mut seg.arguments {
+            args.colon2_token = Some(Default::default());
+        }
+    }
+    let mut builder_fields = Vec::new();
+    let mut setters = Vec::new();
+    let mut values = Vec::new();
+    for field in fields {
+        let name = field.ident.as_ref().unwrap();
+        let docs = field.attrs.iter().filter(|a| a.path.is_ident("doc"));
+        let (field_ty, value) = match option_arg(&field.ty) {
+            Some(arg) => (arg, quote!(self.#name)),
+            None => {
+                let message = format!("missing field '{}' in {}", name, builder_ident);
+                (&field.ty, quote!(self.#name.ok_or(#message)?))
+            }
+        };
+        builder_fields.push(quote!(#name: ::core::option::Option<#field_ty>));
+        setters.push(quote!(
+            #(#docs)*
+            #vis fn #name(mut self, #name: #field_ty) -> Self {
+                self.#name = ::core::option::Option::Some(#name);
+                self
+            }
+        ));
+        values.push(quote!(#name: #value));
+    }
+    let names = fields.iter().map(|field| &field.ident);
+    let builder_doc = format!("Builder of [`{}`].", pathname(ty));
+    vec![
+        syn::parse_quote!(
+            #(#attrs)*
+            #vis struct #builder_ident {
+                #(#builder_fields),*
+            }
+        ),
+        syn::parse_quote!(
+            impl ::core::default::Default for #builder_ident {
+                fn default() -> Self {
+                    #builder_ident {
+                        #(#names: ::core::option::Option::None),*
+                    }
+                }
+            }
+        ),
+        syn::parse_quote!(
+            impl #builder_ident {
+                /// Creates a builder with no field set.
+                #vis fn new() -> Self {
+                    ::core::default::Default::default()
+                }
+
+                #(#setters)*
+
+                /// Builds the value, or returns an error message if a required field isn't set.
+                #vis fn build(self) -> ::core::result::Result<#ty, &'static str> {
+                    ::core::result::Result::Ok(#target_path {
+                        #(#values),*
+                    })
+                }
+            }
+        ),
+        syn::parse_quote!(
+            impl #ty {
+                #[doc = #builder_doc]
+                #vis fn builder() -> #builder_ident {
+                    #builder_ident::new()
+                }
+            }
+        ),
+    ]
+}
+
+/// Checks if the type is a `PhantomData`.
+fn is_phantom(ty: &Type) -> bool {
+    matches!(ty, Type::Path(TypePath { qself: None, path })
+        if path.segments.last().map_or(false, |seg| seg.ident == "PhantomData"))
+}
+
+/// Gets the member designating the single field of the structure, apart from `PhantomData`
+/// fields, or aborts if there isn't exactly one such field. `what` describes the structure in
+/// the error message.
+fn inner_field(template: &ItemStruct, what: &str) -> Member {
+    let mut fields = template
+        .fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| !is_phantom(&field.ty));
+    match (fields.next(), fields.next()) {
+        (Some((index, field)), None) => match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(index.into()),
+        },
+        _ => abort!(
+            template.ident.span(),
+            "{} must have a single field, apart from PhantomData fields",
+            what
+        ),
+    }
+}
+
+/// Transforms the `template` structure into a transparent newtype of `ty`, named after the type
+/// followed by the template name: `struct Wrapper(T)` gives `#[repr(transparent)] struct
+/// MeterWrapper(Meter)` for `Meter`. The structure must have a single field, apart from
+/// `PhantomData` fields.
+fn newtype(ty: &SubstType, template: &mut ItemStruct) {
+    if let Some(attr) = template.attrs.iter().find(|a| a.path.is_ident("repr")) {
+        abort!(
+            attr.span(),
+            "newtype templates can't have a 'repr' attribute"
+        );
+    }
+    inner_field(template, "newtype templates");
+    let type_ident = match type_ident(ty) {
+        Some(type_ident) => type_ident,
+        None => abort!(
+            template.ident.span(),
+            "newtype requires type paths, not '{}'",
+            pathname(ty)
+        ),
+    };
+    template.ident = format_ident!(
+        "{}{}",
+        to_camel_case(&type_ident.to_string()),
+        template.ident
+    );
+    template
+        .attrs
+        .insert(0, syn::parse_quote!(#[repr(transparent)]));
+}
+
+/// Renames the functions of the code after `ty`, keeping their attributes: `fn bench_clone()`
+/// gives `fn bench_clone_big_int()` for `BigInt`.
+fn bench_fn(ty: &SubstType, ast: &mut File) {
+    let type_ident = match type_ident(ty) {
+        Some(type_ident) => type_ident,
+        None => abort!(
+            ty.span(),
+            "bench_fn requires type paths, not '{}'",
+            pathname(ty)
+        ),
+    };
+    let suffix = to_snake_case(&type_ident.to_string());
+    let mut found = false;
+    for item in &mut ast.items {
+        if let Item::Fn(item_fn) = item {
+            item_fn.sig.ident = format_ident!("{}_{}", item_fn.sig.ident, suffix);
+            found = true;
+        }
+    }
+    if !found {
+        abort!(ty.span(), "bench_fn requires a function in the code");
+    }
+}
+
+/// Generates the ordering implementations of a single-field structure, which delegate to its
+/// field. With `total`, both `PartialOrd` and `Ord` are generated, so the field type must
+/// implement `Ord` and the structure `Eq`:
+///
+/// ```text
+/// impl PartialOrd for Wrapper {
+///     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+///         Some(Ord::cmp(self, other))
+///     }
+/// }
+/// impl Ord for Wrapper {
+///     fn cmp(&self, other: &Self) -> Ordering {
+///         Ord::cmp(&self.0, &other.0)
+///     }
+/// }
+/// ```
+///
+/// Otherwise, only `PartialOrd` is generated, and calls `PartialOrd::partial_cmp` on the field.
+fn ordering(template: &ItemStruct, total: bool) -> Vec<Item> {
+    let option = if total { "ord" } else { "partial_ord" };
+    let field = inner_field(
+        template,
+        &format!("structures with the '{}' option", option),
+    );
+    let ident = &template.ident;
+    let (impl_generics, ty_generics, where_clause) = template.generics.split_for_impl();
+    if total {
+        vec![
+            syn::parse_quote!(
+                impl #impl_generics ::core::cmp::PartialOrd for #ident #ty_generics #where_clause {
+                    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                        ::core::option::Option::Some(::core::cmp::Ord::cmp(self, other))
+                    }
+                }
+            ),
+            syn::parse_quote!(
+                impl #impl_generics ::core::cmp::Ord for #ident #ty_generics #where_clause {
+                    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                        ::core::cmp::Ord::cmp(&self.#field, &other.#field)
+                    }
+                }
+            ),
+        ]
+    } else {
+        vec![syn::parse_quote!(
+            impl #impl_generics ::core::cmp::PartialOrd for #ident #ty_generics #where_clause {
+                fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                    ::core::cmp::PartialOrd::partial_cmp(&self.#field, &other.#field)
+                }
+            }
+        )]
+    }
+}
+
+/// Checks if the item is an implementation of the trait `trait_path`.
+fn is_types_impl(trait_path: &Path, item: &Item) -> bool {
+    matches!(item, Item::Impl(ItemImpl { trait_: Some((None, path, _)), .. })
+        if pathname(path) == pathname(trait_path))
+}
+
+/// Collects the implementations of the trait `trait_path` in the items, including those of the
+/// modules with an inline content.
+fn find_types_impls<'a>(trait_path: &Path, items: &'a [Item], found: &mut Vec<&'a ItemImpl>) {
+    for item in items {
+        match item {
+            Item::Impl(item_impl) if is_types_impl(trait_path, item) => found.push(item_impl),
+            Item::Mod(ItemMod {
+                content: Some((_, mod_items)),
+                ..
+            }) => find_types_impls(trait_path, mod_items, found),
+            _ => {}
+        }
+    }
+}
+
+/// Adds an `# Example` section with the `code` in a doctest to the documentation of the
+/// implementations in the code, before the types are substituted, so that `${T}` is replaced by
+/// the type in each copy:
+///
+/// ````text
+/// /// # Example
+/// ///
+/// /// ```
+/// /// code
+/// /// ```
+/// impl Trait for T { ... }
+/// ````
+pub(crate) fn add_doc_example(code: &LitStr, ast: &mut File) {
+    let doc = format!("# Example\n\n```\n{}\n```", code.value().trim());
+    let doc = LitStr::new(&doc, code.span());
+    let mut found = false;
+    for item in &mut ast.items {
+        if let Item::Impl(item_impl) = item {
+            if item_impl.attrs.iter().any(|attr| attr.path.is_ident("doc")) {
+                item_impl.attrs.push(syn::parse_quote!(#[doc = ""]));
+            }
+            item_impl.attrs.push(syn::parse_quote!(#[doc = #doc]));
+            found = true;
+        }
+    }
+    if !found {
+        abort!(
+            code.span(),
+            "doc_example requires an implementation in the code"
+        );
+    }
+}
+
+/// Extracts the types from the implementation of the trait `trait_path` in the code: the
+/// definitions of its associated types, which must be type paths, in the order they're given:
+///
+/// ```text
+/// impl Units for Registry {
+///     type Length = Meter;    // => Meter
+///     type Volume = Liter;    // => Liter
+/// }
+/// ```
+///
+/// There must be exactly one such implementation, either at the top level or in a module
+/// with an inline content.
+pub(crate) fn types_from(trait_path: &Path, ast: &File) -> Vec<Type> {
+    let mut found = Vec::new();
+    find_types_impls(trait_path, &ast.items, &mut found);
+    let item_impl = match found.as_slice() {
+        [item_impl] => item_impl,
+        [] => abort!(
+            trait_path.span(),
+            "no implementation of '{}' found in the code",
+            pathname(trait_path)
+        ),
+        [_, second, ..] => abort!(
+            second.span(),
+            "types_from requires a single implementation of '{}'",
+            pathname(trait_path)
+        ),
+    };
+    let types = item_impl
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Type(assoc) => match &assoc.ty {
+                ty @ Type::Path(TypePath { qself: None, .. })
+                    if assoc.generics.params.is_empty() =>
+                {
+                    Some(ty.clone())
+                }
+                ty => abort!(
+                    ty.span(),
+                    "associated type '{}' must be defined by a type path to be used by types_from",
+                    assoc.ident
+                ),
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if types.is_empty() {
+        abort!(
+            item_impl.span(),
+            "the implementation of '{}' has no associated type",
+            pathname(trait_path)
+        );
+    }
+    types
+}
+
+/// Removes the implementations of the trait `trait_path` from the items, including those of the
+/// modules with an inline content.
+pub(crate) fn remove_types_impl(trait_path: &Path, items: &mut Vec<Item>) {
+    items.retain(|item| !is_types_impl(trait_path, item));
+    for item in items {
+        if let Item::Mod(ItemMod {
+            content: Some((_, mod_items)),
+            ..
+        }) = item
+        {
+            remove_types_impl(trait_path, mod_items);
+        }
+    }
+}
+
+/// Generates a macro mapping each type name literal to its type:
+///
+/// ```text
+/// macro_rules! name {
+///     ("Type1") => { Type1 };
+///     ("Type2") => { Type2 };
+/// }
+/// ```
+fn dispatch_macro(name: &Ident, types: &[SubstType]) -> TokenStream {
+    let rules = types.iter().map(|ty| {
+        let ty_name = pathname(ty);
+        quote!((#ty_name) => { #ty };)
+    });
+    quote!(
+        #[allow(unused_macros)]
+        macro_rules! #name {
+            #(#rules)*
+        }
+    )
+}
+
+/// Generates a constant table giving the name, size and alignment of each type, whose row has the
+/// `cfg` attributes of the type:
+///
+/// ```text
+/// const NAME: &[(&str, usize, usize)] = &[
+///     ("Type1", ::core::mem::size_of::<Type1>(), ::core::mem::align_of::<Type1>()),
+/// ];
+/// ```
+fn layout_table(
+    vis: &Visibility,
+    name: &Ident,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let entries = types.iter().zip(type_attrs).map(|(ty, attrs)| {
+        let ty_name = pathname(ty);
+        quote!(#(#attrs)* (#ty_name, ::core::mem::size_of::<#ty>(), ::core::mem::align_of::<#ty>()))
+    });
+    quote!(
+        #[allow(dead_code)]
+        #vis const #name: &[(&str, usize, usize)] = &[
+            #(#entries),*
+        ];
+    )
+}
+
+/// Generates a constant table giving the name and the position in the list of each type, sorted
+/// by name:
+///
+/// ```text
+/// const NAME: &[(&str, usize)] = &[("Type2", 1), ("Type1", 0)];
+/// ```
+///
+/// The types can't have `cfg` attributes, since the table entries can't be disabled: the table
+/// would still give the name and the position of a type whose implementation is compiled out.
+fn sorted_names(
+    vis: &Visibility,
+    name: &Ident,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    if let Some(attr) = type_attrs.iter().flatten().next() {
+        abort!(attr.span(),
+            "sorted_names doesn't support types with cfg attributes";
+            help = "the table would give the position of types whose implementation is compiled out in some configurations"
+        );
+    }
+    let mut names = types.iter().map(pathname).enumerate().collect::<Vec<_>>();
+    // same order as `str::cmp`, so that the table can be searched with `binary_search_by`
+    names.sort_by(|(_, a), (_, b)| a.cmp(b));
+    if let Some(pair) = names.windows(2).find(|pair| pair[0].1 == pair[1].1) {
+        abort!(
+            types[pair[1].0].span(),
+            "sorted_names requires distinct type names, found '{}' twice",
+            pair[1].1
+        );
+    }
+    let entries = names
+        .iter()
+        .map(|(index, ty_name)| quote!((#ty_name, #index)));
+    quote!(
+        #[allow(dead_code)]
+        #vis const #name: &[(&str, usize)] = &[
+            #(#entries),*
+        ];
+    )
+}
+
+/// Generates a `const fn` giving the name of the type at position `index` in the list, with the
+/// `cfg` attributes of the types:
+///
+/// ```text
+/// const fn name(index: usize) -> &'static str {
+///     match index {
+///         0 => "Type1",
+///         1 => "Type2",
+///         _ => "",
+///     }
+/// }
+/// ```
+fn name_fn(
+    vis: &Visibility,
+    name: &Ident,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let arms = types
+        .iter()
+        .zip(type_attrs)
+        .enumerate()
+        .map(|(index, (ty, attrs))| {
+            let type_name = pathname(ty);
+            quote!(#(#attrs)* #index => #type_name,)
+        });
+    quote!(
+        /// Gives the name of the type at position `index` in the list, or an empty string if
+        /// there is none.
+        #[allow(dead_code)]
+        #vis const fn #name(index: usize) -> &'static str {
+            match index {
+                #(#arms)*
+                _ => "",
+            }
+        }
+    )
+}
+
+/// Gives the position of each type, which determines its tag and enum discriminant: its position
+/// in the list, or in the list sorted by type name if `alphabetical` is true, so that reordering
+/// the list doesn't change the values.
+fn type_positions(alphabetical: bool, types: &[SubstType]) -> Vec<usize> {
+    let mut positions = (0..types.len()).collect::<Vec<_>>();
+    if alphabetical {
+        let mut order = positions.clone();
+        let names = types.iter().map(pathname).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| names[a].cmp(&names[b]));
+        for (position, index) in order.into_iter().enumerate() {
+            positions[index] = position;
+        }
+    }
+    positions
+}
+
+/// Generates a `u32` constant for each type, whose value is its position given by `positions`,
+/// starting at `base` (0 by default), with the `cfg` attributes of the type:
+///
+/// ```text
+/// const TYPE1_TAG: u32 = 0;
+/// const TYPE2_TAG: u32 = 1;
+/// ```
+///
+/// The constant names are given by the `template`, in which `${T}` is replaced by the type
+/// identifier in upper snake case (by default, `${T}_TAG`).
+fn tag_consts(
+    vis: &Visibility,
+    template: Option<&LitStr>,
+    base: Option<&LitInt>,
+    generic_arg: &Path,
+    types: &[SubstType],
+    positions: &[usize],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let placeholder = format!("${{{}}}", pathname(generic_arg));
+    let template = match template {
+        Some(template) => template.value(),
+        None => format!("{}_TAG", placeholder),
+    };
+    // the value was checked when the options were parsed
+    let base = base.map_or(0, |base| base.base10_parse::<u32>().unwrap());
+    let consts = types
+        .iter()
+        .zip(type_attrs)
+        .zip(positions)
+        .map(|((ty, attrs), &i)| {
+            let ident = match type_ident(ty) {
+                Some(ident) => ident,
+                None => abort!(
+                    ty.span(),
+                    "tag_const requires type paths, not '{}'",
+                    pathname(ty)
+                ),
+            };
+            let name = template.replace(
+                &placeholder,
+                &to_snake_case(&ident.to_string()).to_uppercase(),
+            );
+            let name = match syn::parse_str::<Ident>(&name) {
+                Ok(name) => name,
+                Err(_) => abort!(ty.span(), "'{}' isn't a valid constant name", name),
+            };
+            let value = match u32::try_from(i).ok().and_then(|i| base.checked_add(i)) {
+                Some(value) => value,
+                None => abort!(ty.span(), "the tag of '{}' overflows u32", pathname(ty)),
+            };
+            quote!(
+                #(#attrs)*
+                #[allow(dead_code)]
+                #vis const #name: u32 = #value;
+            )
+        });
+    quote!(#(#consts)*)
+}
+
+/// Generates a trait with a `u32` associated constant, and implements it for each type with its
+/// position given by `positions`, starting at `base` (0 by default), with the `cfg` attributes of
+/// the type:
+///
+/// ```text
+/// trait Name {
+///     const TAG: u32;
+/// }
+/// impl Name for Type1 {
+///     const TAG: u32 = 0;
+/// }
+/// impl Name for Type2 {
+///     const TAG: u32 = 1;
+/// }
+/// ```
+fn tag_trait(
+    vis: &Visibility,
+    name: &Ident,
+    base: Option<&LitInt>,
+    types: &[SubstType],
+    positions: &[usize],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    // the value was checked when the options were parsed
+    let base = base.map_or(0, |base| base.base10_parse::<u32>().unwrap());
+    let impls = types
+        .iter()
+        .zip(type_attrs)
+        .zip(positions)
+        .map(|((ty, attrs), &i)| {
+            let value = match u32::try_from(i).ok().and_then(|i| base.checked_add(i)) {
+                Some(value) => value,
+                None => abort!(ty.span(), "the tag of '{}' overflows u32", pathname(ty)),
+            };
+            quote!(
+                #(#attrs)*
+                impl #name for #ty {
+                    const TAG: u32 = #value;
+                }
+            )
+        });
+    quote!(
+        #vis trait #name {
+            const TAG: u32;
+        }
+        #(#impls)*
+    )
+}
+
+/// Generates a constant for each type, whose value has a single bit set at the position of the
+/// type in the list, with the `cfg` attributes of the type:
+///
+/// ```text
+/// const TYPE1_BIT: u32 = 1 << 0;
+/// const TYPE2_BIT: u32 = 1 << 1;
+/// ```
+///
+/// The constant type is `bit_type` (`u32` by default), and the constant names are given by the
+/// `template`, in which `${T}` is replaced by the type identifier in upper snake case (by
+/// default, `${T}_BIT`). Aborts if there are more types than bits.
+fn bit_consts(
+    vis: &Visibility,
+    template: Option<&LitStr>,
+    bit_type: Option<&Ident>,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let placeholder = format!("${{{}}}", pathname(generic_arg));
+    let template = match template {
+        Some(template) => template.value(),
+        None => format!("{}_BIT", placeholder),
+    };
+    let bit_type = match bit_type {
+        Some(bit_type) => bit_type.clone(),
+        None => format_ident!("u32"),
+    };
+    // the type was checked when the options were parsed
+    let nbr_bits = bit_type.to_string()[1..].parse::<usize>().unwrap();
+    let consts = types
+        .iter()
+        .zip(type_attrs)
+        .enumerate()
+        .map(|(i, (ty, attrs))| {
+            let ident = match type_ident(ty) {
+                Some(ident) => ident,
+                None => abort!(
+                    ty.span(),
+                    "bit_const requires type paths, not '{}'",
+                    pathname(ty)
+                ),
+            };
+            let name = template.replace(
+                &placeholder,
+                &to_snake_case(&ident.to_string()).to_uppercase(),
+            );
+            let name = match syn::parse_str::<Ident>(&name) {
+                Ok(name) => name,
+                Err(_) => abort!(ty.span(), "'{}' isn't a valid constant name", name),
+            };
+            if i >= nbr_bits {
+                abort!(ty.span(),
+                    "the bit of '{}' overflows {}", pathname(ty), bit_type;
+                    help = "there are {} types for {} bits, use a larger bit_type", types.len(), nbr_bits
+                );
+            }
+            let shift = LitInt::new(&i.to_string(), Span::call_site());
+            quote!(
+                #(#attrs)*
+                #[allow(dead_code)]
+                #vis const #name: #bit_type = 1 << #shift;
+            )
+        });
+    quote!(#(#consts)*)
+}
+
+/// Generates an error enum with a variant for each type, with the `cfg` attributes of the type:
+///
+/// ```text
+/// #[derive(Debug)]
+/// enum Name {
+///     Type1(Data1),
+///     Type2(Data2),
+/// }
+/// impl From<Data1> for Name { ... }
+/// impl From<Data2> for Name { ... }
+/// ```
+///
+/// The variant names are given by the `template`, in which `${T}` is replaced by the type
+/// identifier (by default, `${T}`). The variants carry the `data` type, in which the generic
+/// argument is replaced by the type, or nothing if there's no data type. The `From`
+/// conversions are only generated if the data type depends on the generic argument, since
+/// they'd conflict otherwise (they still conflict if two data types are the same, or if they're
+/// associated types, which aren't normalized by the coherence check).
+fn error_enum(
+    vis: &Visibility,
+    name: &Ident,
+    data: Option<&Type>,
+    template: Option<&LitStr>,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let placeholder = format!("${{{}}}", pathname(generic_arg));
+    let template = match template {
+        Some(template) => template.value(),
+        None => placeholder.clone(),
+    };
+    let pat = generic_arg
+        .to_token_stream()
+        .into_iter()
+        .map(|tree| tree.to_string())
+        .collect::<Vec<_>>();
+    let has_conversions = data.map_or(false, |data| {
+        let mut find_arg = FindGenericArg(generic_arg, None);
+        find_arg.visit_type_mut(&mut data.clone());
+        find_arg.1.is_some()
+    });
+    let mut variants = Vec::new();
+    let mut conversions = Vec::new();
+    for (ty, attrs) in types.iter().zip(type_attrs) {
+        let ident = match type_ident(ty) {
+            Some(ident) => ident,
+            None => abort!(
+                ty.span(),
+                "error_enum requires type paths, not '{}'",
+                pathname(ty)
+            ),
+        };
+        let variant = template.replace(&placeholder, &to_camel_case(&ident.to_string()));
+        let variant = match syn::parse_str::<Ident>(&variant) {
+            Ok(variant) => variant,
+            Err(_) => abort!(ty.span(), "'{}' isn't a valid variant name", variant),
+        };
+        match data {
+            Some(data) => {
+                let data = replace_tokens(data.to_token_stream(), &pat, &ty.to_token_stream());
+                variants.push(quote!(#(#attrs)* #variant(#data)));
+                if has_conversions {
+                    conversions.push(quote!(
+                        #(#attrs)*
+                        impl ::core::convert::From<#data> for #name {
+                            fn from(error: #data) -> Self {
+                                #name::#variant(error)
+                            }
+                        }
+                    ));
+                }
+            }
+            None => variants.push(quote!(#(#attrs)* #variant)),
+        }
+    }
+    quote!(
+        #[derive(Debug)]
+        #vis enum #name {
+            #(#variants),*
+        }
+        #(#conversions)*
+    )
+}
+
+/// Generates an enum with a variant for each type, whose discriminant is the index of the type in
+/// the order of `tag_order`, given by `positions`, and the conversions between the variants, their
+/// index and the type names, with the `cfg` attributes of the type:
+///
+/// ```text
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// enum Name {
+///     Type1 = 0,
+///     Type2 = 1,
+/// }
+/// impl Name {
+///     fn index_of(&self) -> usize { ... }
+///     fn from_index(index: usize) -> Option<Self> { ... }
+///     fn name_of_index(index: usize) -> Option<&'static str> { ... }
+///     fn all() -> impl Iterator<Item = Self> { ... }  // if `all` is true
+/// }
+/// ```
+///
+/// The variant names are the type identifiers with their first letter in upper case.
+fn type_enum(
+    vis: &Visibility,
+    name: &Ident,
+    all: bool,
+    types: &[SubstType],
+    positions: &[usize],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let mut variants = Vec::new();
+    let mut from_index_arms = Vec::new();
+    let mut name_arms = Vec::new();
+    for ((ty, attrs), &index) in types.iter().zip(type_attrs).zip(positions) {
+        let ident = match type_ident(ty) {
+            Some(ident) => ident,
+            None => abort!(
+                ty.span(),
+                "type_enum requires type paths, not '{}'",
+                pathname(ty)
+            ),
+        };
+        let variant = format_ident!("{}", to_camel_case(&ident.to_string()));
+        let type_name = pathname(ty);
+        let discriminant = LitInt::new(&index.to_string(), Span::call_site());
+        variants.push(quote!(#(#attrs)* #variant = #discriminant));
+        from_index_arms.push(quote!(#(#attrs)* #index => Some(#name::#variant),));
+        name_arms.push(quote!(#(#attrs)* #index => Some(#type_name),));
+    }
+    let all_method = if all {
+        let len = types.len();
+        quote!(
+            /// Iterates over the variants, in the order of their index.
+            #vis fn all() -> impl Iterator<Item = Self> {
+                (0..#len).filter_map(Self::from_index)
+            }
+        )
+    } else {
+        quote!()
+    };
+    quote!(
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #vis enum #name {
+            #(#variants),*
+        }
+
+        #[allow(dead_code)]
+        impl #name {
+            /// Gives the index of the type, which is the discriminant of the variant.
+            #vis fn index_of(&self) -> usize {
+                *self as usize
+            }
+
+            /// Gives the variant of the type at `index`, if any.
+            #vis fn from_index(index: usize) -> Option<Self> {
+                match index {
+                    #(#from_index_arms)*
+                    _ => None,
+                }
+            }
+
+            /// Gives the name of the type at `index`, if any.
+            #vis fn name_of_index(index: usize) -> Option<&'static str> {
+                match index {
+                    #(#name_arms)*
+                    _ => None,
+                }
+            }
+
+            #all_method
+        }
+    )
+}
+
+/// Generates the conversion of an index into a variant of the type enum `name`, which gives the
+/// index back as error if there's no variant at that index:
+///
+/// ```text
+/// impl TryFrom<usize> for Name {
+///     type Error = usize;
+///     fn try_from(index: usize) -> Result<Self, usize> { ... }
+/// }
+/// ```
+fn enum_try_from(name: &Ident) -> TokenStream {
+    quote!(
+        impl ::core::convert::TryFrom<usize> for #name {
+            type Error = usize;
+
+            fn try_from(index: usize) -> ::core::result::Result<Self, usize> {
+                Self::from_index(index).ok_or(index)
+            }
+        }
+    )
+}
+
+/// Generates a marker trait implemented by each type, with the `cfg` attributes of the type:
+///
+/// ```text
+/// trait Name {}
+/// impl Name for Type1 {}
+/// impl Name for Type2 {}
+/// ```
+fn marker_trait(
+    vis: &Visibility,
+    name: &Ident,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let impls = types.iter().zip(type_attrs).map(|(ty, attrs)| {
+        quote!(
+            #(#attrs)*
+            impl #name for #ty {}
+        )
+    });
+    quote!(
+        #vis trait #name {}
+        #(#impls)*
+    )
+}
+
+/// Generates a trait giving the value as a `&dyn Any`, implemented by each type, and the
+/// downcasting helpers of its trait objects, with the `cfg` attributes of the types:
+///
+/// ```text
+/// trait Name: Any {
+///     fn as_any(&self) -> &dyn Any;
+/// }
+/// impl Name for Type1 {
+///     fn as_any(&self) -> &dyn Any { self }
+/// }
+/// impl dyn Name {
+///     fn is_named(&self, name: &str) -> bool { ... }
+///     fn type_name(&self) -> Option<&'static str> { ... }
+///     fn downcast_ref<V: Name>(&self) -> Option<&V> { ... }
+/// }
+/// ```
+///
+/// The types must be `'static`.
+fn as_any(
+    vis: &Visibility,
+    name: &Ident,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let mut impls = Vec::new();
+    let mut arms = Vec::new();
+    let mut type_names = Vec::new();
+    for (ty, attrs) in types.iter().zip(type_attrs) {
+        let mut finder = FindLifetime(None);
+        match ty.clone() {
+            SubstType::Path(mut path) => finder.visit_path_mut(&mut path),
+            SubstType::Type(mut ty) => finder.visit_type_mut(&mut ty),
+        }
+        if let Some(span) = finder.0 {
+            abort!(span,
+                "as_any requires 'static types, not '{}'", pathname(ty);
+                help = "only the 'static lifetime can be used in the types"
+            );
+        }
+        let type_name = pathname(ty);
+        impls.push(quote!(
+            #(#attrs)*
+            impl #name for #ty {
+                fn as_any(&self) -> &dyn ::core::any::Any {
+                    self
+                }
+            }
+        ));
+        arms.push(quote!(#(#attrs)* #type_name => self.as_any().is::<#ty>(),));
+        type_names.push(type_name);
+    }
+    quote!(
+        #vis trait #name: ::core::any::Any {
+            /// Gives the value as a `&dyn Any`, which can be downcast to its type.
+            fn as_any(&self) -> &dyn ::core::any::Any;
+        }
+
+        #(#impls)*
+
+        #[allow(dead_code)]
+        impl dyn #name {
+            /// Checks if the value has the type of the given name.
+            #vis fn is_named(&self, name: &str) -> bool {
+                match name {
+                    #(#arms)*
+                    _ => false,
+                }
+            }
+
+            /// Gives the name of the type of the value.
+            #vis fn type_name(&self) -> Option<&'static str> {
+                [#(#type_names),*]
+                    .iter()
+                    .copied()
+                    .find(|name| self.is_named(name))
+            }
+
+            /// Gives a reference to the value if it has the type `V`.
+            #vis fn downcast_ref<V: #name>(&self) -> Option<&V> {
+                self.as_any().downcast_ref::<V>()
+            }
+        }
+    )
+}
+
+/// Looks for a lifetime other than `'static`, including the elided lifetimes of references. The
+/// lifetimes of function pointers and `Fn` traits arguments are ignored, since they don't borrow
+/// anything.
+struct FindLifetime(Option<Span>);
+
+impl VisitMut for FindLifetime {
+    fn visit_lifetime_mut(&mut self, node: &mut syn::Lifetime) {
+        if node.ident != "static" {
+            self.0.get_or_insert(node.span());
+        }
+    }
+
+    fn visit_type_reference_mut(&mut self, node: &mut syn::TypeReference) {
+        if node.lifetime.is_none() {
+            self.0.get_or_insert(node.and_token.span());
+        }
+        syn::visit_mut::visit_type_reference_mut(self, node);
+    }
+
+    fn visit_type_bare_fn_mut(&mut self, _node: &mut syn::TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments_mut(
+        &mut self,
+        _node: &mut syn::ParenthesizedGenericArguments,
+    ) {
+    }
+}
+
+/// Looks for a path beginning with the generic argument, which would be substituted.
+struct FindGenericArg<'a>(&'a Path, Option<Span>);
+
+impl VisitMut for FindGenericArg<'_> {
+    fn visit_path_mut(&mut self, node: &mut Path) {
+        if path_prefix_len(self.0, node).is_some() {
+            self.1 = Some(node.span());
+        } else {
+            syn::visit_mut::visit_path_mut(self, node);
+        }
+    }
+}
+
+/// Generates an empty module documented with a markdown table of the types, giving their index,
+/// name, and `cfg` attributes if any of them has some:
+///
+/// ```text
+/// /// Types generated for `T`:
+/// ///
+/// /// | # | Type | Configuration |
+/// /// |---|------|---------------|
+/// /// | 0 | `Type1` | |
+/// /// | 1 | `Type2` | `cfg(feature = "a")` |
+/// mod name {}
+/// ```
+fn doc_table(
+    vis: &Visibility,
+    name: &Ident,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let with_cfg = type_attrs.iter().any(|attrs| !attrs.is_empty());
+    let mut lines = vec![
+        format!("Types generated for `{}`:", pathname(generic_arg)),
+        String::new(),
+    ];
+    if with_cfg {
+        lines.push("| # | Type | Configuration |".to_string());
+        lines.push("|---|------|---------------|".to_string());
+    } else {
+        lines.push("| # | Type |".to_string());
+        lines.push("|---|------|".to_string());
+    }
+    for (i, (ty, attrs)) in types.iter().zip(type_attrs).enumerate() {
+        let mut line = format!("| {} | `{}` |", i, pathname(ty));
+        if with_cfg {
+            let conditions = attrs
+                .iter()
+                .map(|attr| format!("`cfg{}`", attr.tokens))
+                .collect::<Vec<_>>();
+            if conditions.is_empty() {
+                line.push_str(" |");
+            } else {
+                line.push_str(&format!(" {} |", conditions.join(", ")));
+            }
+        }
+        lines.push(line);
+    }
+    let doc = lines.join("\n");
+    quote!(
+        #[doc = #doc]
+        #vis mod #name {}
+    )
+}
+
+/// Generates a function creating the default value of a type given by its name, as a boxed object
+/// of the trait implemented by the code, with the `cfg` attributes of the types:
+///
+/// ```text
+/// fn name(type_name: &str) -> Option<Box<dyn Trait>> {
+///     match type_name {
+///         "Type1" => Some(Box::new(<Type1 as Default>::default())),
+///         _ => None,
+///     }
+/// }
+/// ```
+fn factory(
+    vis: &Visibility,
+    name: &Ident,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+    ast: &File,
+) -> TokenStream {
+    let trait_path = implemented_trait("factory", name.span(), generic_arg, ast);
+    let arms = types.iter().zip(type_attrs).map(|(ty, attrs)| {
+        let ty_name = pathname(ty);
+        quote!(
+            #(#attrs)*
+            #ty_name => Some(Box::new(<#ty as ::core::default::Default>::default())),
+        )
+    });
+    quote!(
+        #vis fn #name(type_name: &str) -> Option<Box<dyn #trait_path>> {
+            match type_name {
+                #(#arms)*
+                _ => None,
+            }
+        }
+    )
+}
+
+/// Generates a constant array of functions creating the default value of each type, in the order
+/// of the list, as a boxed object of the trait implemented by the code:
+///
+/// ```text
+/// const NAME: [fn() -> Box<dyn Trait>; 2] = {
+///     fn ctor_0() -> Box<dyn Trait> {
+///         Box::new(<Type1 as Default>::default())
+///     }
+///     fn ctor_1() -> Box<dyn Trait> { ... }
+///     [ctor_0, ctor_1]
+/// };
+/// ```
+///
+/// The types can't have `cfg` attributes, since the array elements can't be disabled.
+fn ctor_array(
+    vis: &Visibility,
+    name: &Ident,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+    ast: &File,
+) -> TokenStream {
+    let trait_path = implemented_trait("ctor_array", name.span(), generic_arg, ast);
+    let mut ctors = Vec::new();
+    let mut ctor_names = Vec::new();
+    for (index, (ty, attrs)) in types.iter().zip(type_attrs).enumerate() {
+        if let Some(attr) = attrs.first() {
+            abort!(attr.span(),
+                "ctor_array doesn't support types with cfg attributes";
+                help = "the array would have a different length for each configuration"
+            );
+        }
+        let ctor_name = format_ident!("ctor_{}", index);
+        ctors.push(quote!(
+            fn #ctor_name() -> Box<dyn #trait_path> {
+                Box::new(<#ty as ::core::default::Default>::default())
+            }
+        ));
+        ctor_names.push(ctor_name);
+    }
+    let len = types.len();
+    quote!(
+        #vis const #name: [fn() -> Box<dyn #trait_path>; #len] = {
+            #(#ctors)*
+            [#(#ctor_names),*]
+        };
+    )
+}
+
+/// Gets the trait implemented by the code for the `option`, which must be the same in all the
+/// implementations and can't depend on the generic argument. `span` locates the errors when
+/// there's no trait implementation.
+fn implemented_trait<'a>(option: &str, span: Span, generic_arg: &Path, ast: &'a File) -> &'a Path {
+    let mut found: Option<&Path> = None;
+    for item in &ast.items {
+        if let Item::Impl(ItemImpl {
+            trait_: Some((None, path, _)),
+            ..
+        }) = item
+        {
+            match found {
+                Some(found) if found != path => abort!(
+                    path.span(),
+                    "{} requires the same trait in all the implementations",
+                    option
+                ),
+                _ => found = Some(path),
+            }
+        }
+    }
+    let found = match found {
+        Some(found) => found,
+        None => abort!(span, "{} requires a trait implementation", option),
+    };
+    let mut find_arg = FindGenericArg(generic_arg, None);
+    find_arg.visit_path_mut(&mut found.clone());
+    if let Some(span) = find_arg.1 {
+        abort!(
+            span,
+            "{} requires a trait that doesn't depend on '{}'",
+            option,
+            pathname(generic_arg)
+        );
+    }
+    found
+}
+
+/// Generates the blanket implementation of `trait_path` with the given `items` for all the
+/// types implementing the trait of the code, which must be the same in all the implementations
+/// and can't depend on the generic argument:
+///
+/// ```text
+/// impl<TraitGenBlanket: Trait> TraitPath for TraitGenBlanket {
+///     // items
+/// }
+/// ```
+fn blanket_impl(
+    trait_path: &Path,
+    items: &[ImplItem],
+    generic_arg: &Path,
+    ast: &File,
+) -> TokenStream {
+    let bound = implemented_trait("blanket_impl", trait_path.span(), generic_arg, ast);
+    let param = Ident::new("TraitGenBlanket", Span::call_site());
+    quote!(
+        impl<#param: #bound> #trait_path for #param {
+            #(#items)*
+        }
+    )
+}
+
+/// Generates a module, only compiled with `cfg(doctest)`, with an item for each type documented
+/// by a doctest of the `code`, in which `${T}` is replaced by the type, with the `cfg` attributes
+/// of the type:
+///
+/// ````text
+/// #[cfg(doctest)]
+/// mod name {
+///     /// ```
+///     /// code
+///     /// ```
+///     pub struct Type1;
+/// }
+/// ````
+///
+/// The items are named after the types, so that the doctest failures show the type.
+fn doctests(
+    name: &Ident,
+    code: &LitStr,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let doc = format!("```\n{}\n```", code.value().trim());
+    let items = types.iter().zip(type_attrs).map(|(ty, attrs)| {
+        let ident = match type_ident(ty) {
+            Some(ident) => ident,
+            None => abort!(
+                ty.span(),
+                "doctests requires type paths, not '{}'",
+                pathname(ty)
+            ),
+        };
+        let item = format_ident!("{}", to_camel_case(&ident.to_string()));
+        let new_type = match ty {
+            SubstType::Path(path) => Type::Path(TypePath {
+                qself: None,
+                path: path.clone(),
+            }),
+            SubstType::Type(ty) => ty.clone(),
+        };
+        let template = quote!(
+            #[doc = #doc]
+            pub struct #item;
+        );
+        let item = match substitute(template, generic_arg, &new_type) {
+            Ok(item) => item,
+            Err(e) => abort!(e.span(), "{}", e),
+        };
+        quote!(
+            #(#attrs)*
+            #item
+        )
+    });
+    quote!(
+        #[cfg(doctest)]
+        mod #name {
+            #(#items)*
+        }
+    )
+}
+
+/// Generates a test calling the generic function `check` for each type, with the `cfg`
+/// attributes of the type:
+///
+/// ```text
+/// #[cfg(test)]
+/// #[test]
+/// fn name() {
+///     check::<Type1>();
+///     check::<Type2>();
+/// }
+/// ```
+fn roundtrip_test(
+    name: &Ident,
+    check: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let calls = types.iter().zip(type_attrs).map(|(ty, attrs)| {
+        quote!(
+            #(#attrs)*
+            #check::<#ty>();
+        )
+    });
+    quote!(
+        #[cfg(test)]
+        #[test]
+        fn #name() {
+            #(#calls)*
+        }
+    )
+}
+
+/// Generates a test module with a strategy function for each type, in which the generic argument
+/// is substituted by the type, with the `cfg` attributes of the type:
+///
+/// ```text
+/// #[cfg(test)]
+/// mod name {
+///     use super::*;
+///     pub(super) fn type1() -> Strategy<Type1> { expr::<Type1> }
+///     pub(super) fn type2() -> Strategy<Type2> { expr::<Type2> }
+/// }
+/// ```
+///
+/// The function names are the type identifiers in snake case. The module also requires the
+/// `feature` if it's given.
+fn strategies(
+    name: &Ident,
+    ty: &Type,
+    expr: &Expr,
+    feature: Option<&LitStr>,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let functions = types.iter().zip(type_attrs).map(|(new_type, attrs)| {
+        let ident = match type_ident(new_type) {
+            Some(ident) => ident,
+            None => abort!(
+                new_type.span(),
+                "strategies requires type paths, not '{}'",
+                pathname(new_type)
+            ),
+        };
+        let function = format_ident!("{}", to_snake_case(&ident.to_string()));
+        let new_type = match new_type {
+            SubstType::Path(path) => Type::Path(TypePath {
+                qself: None,
+                path: path.clone(),
+            }),
+            SubstType::Type(ty) => ty.clone(),
+        };
+        let template = quote!(
+            pub(super) fn #function() -> #ty {
+                #expr
+            }
+        );
+        let function = match substitute(template, generic_arg, &new_type) {
+            Ok(function) => function,
+            Err(e) => abort!(e.span(), "{}", e),
+        };
+        quote!(
+            #(#attrs)*
+            #function
+        )
+    });
+    let cfg = match feature {
+        Some(feature) => quote!(#[cfg(all(test, feature = #feature))]),
+        None => quote!(#[cfg(test)]),
+    };
+    quote!(
+        #cfg
+        #[allow(dead_code)]
+        mod #name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #(#functions)*
+        }
+    )
+}
+
+/// Generates a test checking the implementations of the code for each type, with the `cfg`
+/// attributes of the type:
+///
+/// ```text
+/// #[cfg(test)]
+/// #[test]
+/// fn name() {
+///     let mut self_types: Vec<(usize, &str, &str, String)> = Vec::new();
+///     {
+///         fn implemented<TraitGenSelf: ?Sized + Trait>() {}
+///         implemented::<Type1>();
+///         self_types.push((0, "Type1", std::any::type_name::<Type1>(), trait_name));
+///     }
+///     // ... for each implementation and each type
+///     // then checks that the copies of each implementation have different self types or
+///     // trait arguments
+/// }
+/// ```
+///
+/// The implementations can't have generic parameters, so the bound can be checked on the types.
+fn coherence_test(
+    name: &Ident,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+    ast: &File,
+) -> TokenStream {
+    let impls = ast
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Impl(item_impl) => Some(item_impl),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if impls.is_empty() {
+        abort!(name.span(), "coherence_test requires an implementation");
+    }
+    let mut checks = Vec::new();
+    for (ty, attrs) in types.iter().zip(type_attrs) {
+        let new_type = match ty {
+            SubstType::Path(path) => Type::Path(TypePath {
+                qself: None,
+                path: path.clone(),
+            }),
+            SubstType::Type(ty) => ty.clone(),
+        };
+        let type_name = pathname(ty);
+        for (index, item_impl) in impls.iter().enumerate() {
+            if !item_impl.generics.params.is_empty() {
+                abort!(
+                    item_impl.generics.span(),
+                    "coherence_test requires implementations without generic parameters"
+                );
+            }
+            let self_ty = &item_impl.self_ty;
+            let (bound, trait_name) = match &item_impl.trait_ {
+                Some((None, path, _)) => (
+                    quote!(
+                        fn implemented<TraitGenSelf: ?Sized + #path>() {}
+                        implemented::<#self_ty>();
+                    ),
+                    trait_name(path),
+                ),
+                _ => (quote!(), quote!(String::new())),
+            };
+            let template = quote!(
+                fn check() {
+                    #bound
+                    self_types.push((
+                        #index,
+                        #type_name,
+                        ::std::any::type_name::<#self_ty>(),
+                        #trait_name,
+                    ));
+                }
+            );
+            let block = match substitute(template, generic_arg, &new_type)
+                .and_then(syn::parse2::<syn::ItemFn>)
+            {
+                Ok(check) => check.block,
+                Err(e) => abort!(e.span(), "{}", e),
+            };
+            checks.push(quote!(
+                #(#attrs)*
+                #block
+            ));
+        }
+    }
+    quote!(
+        #[cfg(test)]
+        #[test]
+        fn #name() {
+            let mut self_types: Vec<(usize, &str, &str, String)> = Vec::new();
+            #(#checks)*
+            for (i, (index, type_name, self_type, trait_name)) in self_types.iter().enumerate() {
+                for (other_index, other_name, other_type, other_trait) in &self_types[..i] {
+                    assert!(
+                        index != other_index || self_type != other_type || trait_name != other_trait,
+                        "the implementations generated for '{}' and '{}' are both for '{}'",
+                        other_name,
+                        type_name,
+                        self_type
+                    );
+                }
+            }
+        }
+    )
+}
+
+/// Generates an expression giving the name of the trait `path`, where the type arguments of
+/// the last segment are given by `std::any::type_name`, so that aliases have the same name.
+fn trait_name(path: &Path) -> TokenStream {
+    let mut path = path.clone();
+    let mut args = Vec::new();
+    if let Some(segment) = path.segments.last_mut() {
+        if let PathArguments::AngleBracketed(generic_args) = &segment.arguments {
+            for arg in &generic_args.args {
+                args.push(match arg {
+                    GenericArgument::Type(ty) => quote!(::std::any::type_name::<#ty>()),
+                    _ => {
+                        let arg = pathname(arg);
+                        quote!(#arg)
+                    }
+                });
+            }
+        }
+        segment.arguments = PathArguments::None;
+    }
+    let name = pathname(&path);
+    quote!([#name #(, #args)*].join(","))
+}
+
+/// Generates a function giving the map from the `TypeId` of each type to its `method`, which is
+/// built on the first call, with the `cfg` attributes of the types. `OnceLock` requires Rust 1.70,
+/// so the map is leaked in a `Box` under a `Once`:
+///
+/// ```text
+/// fn name() -> &'static HashMap<TypeId, fn(A) -> R> {
+///     static INIT: Once = Once::new();
+///     static mut MAP: Option<&'static HashMap<TypeId, fn(A) -> R>> = None;
+///     INIT.call_once(|| {
+///         let mut map = HashMap::new();
+///         map.insert(TypeId::of::<Type1>(), <Type1>::method as fn(A) -> R);
+///         unsafe { MAP = Some(Box::leak(Box::new(map))) };
+///     });
+///     unsafe { MAP.unwrap() }
+/// }
+/// ```
+///
+/// The signature of the method is taken from the code, so it must be the same for all the types:
+/// no `self` receiver, no type or const parameters, and no reference to `Self` or to the generic
+/// argument.
+fn type_id_map(
+    vis: &Visibility,
+    name: &Ident,
+    method: &Ident,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+    ast: &File,
+) -> TokenStream {
+    let sig = ast
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Impl(item_impl) => Some(&item_impl.items),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|item| match item {
+            ImplItem::Method(m) if &m.sig.ident == method => Some(&m.sig),
+            _ => None,
+        });
+    let sig = match sig {
+        Some(sig) => sig,
+        None => abort!(
+            method.span(),
+            "type_id_map requires a method '{}' in the implementations",
+            method
+        ),
+    };
+    if let Some(FnArg::Receiver(receiver)) = sig.inputs.first() {
+        abort!(
+            receiver.span(),
+            "method '{}' can't take 'self' to be in the type_id_map",
+            method
+        );
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        abort!(
+            asyncness.span(),
+            "type_id_map doesn't support async methods"
+        );
+    }
+    if let Some(param) = sig.generics.type_params().next() {
+        abort!(
+            param.span(),
+            "method '{}' can't have type parameters to be in the type_id_map",
+            method
+        );
+    }
+    if let Some(param) = sig.generics.const_params().next() {
+        abort!(
+            param.span(),
+            "method '{}' can't have const parameters to be in the type_id_map",
+            method
+        );
+    }
+    let args = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(pat_type) => Some(pat_type.ty.as_ref().clone()),
+            FnArg::Receiver(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let output = &sig.output;
+    let self_path: Path = syn::parse_quote!(Self);
+    for path in [generic_arg, &self_path] {
+        let mut find_arg = FindGenericArg(path, None);
+        for ty in &args {
+            find_arg.visit_type_mut(&mut ty.clone());
+        }
+        find_arg.visit_return_type_mut(&mut output.clone());
+        if let Some(span) = find_arg.1 {
+            abort!(
+                span,
+                "the signature of '{}' can't depend on '{}' to be in the type_id_map",
+                method,
+                pathname(path)
+            );
+        }
+    }
+    let lifetimes = sig
+        .generics
+        .lifetimes()
+        .map(|def| &def.lifetime)
+        .collect::<Vec<_>>();
+    let for_lifetimes = if lifetimes.is_empty() {
+        quote!()
+    } else {
+        quote!(for<#(#lifetimes),*>)
+    };
+    let unsafety = &sig.unsafety;
+    let abi = &sig.abi;
+    let fn_type = quote!(#for_lifetimes #unsafety #abi fn(#(#args),*) #output);
+    let inserts = types.iter().zip(type_attrs).map(|(ty, attrs)| {
+        quote!(
+            #(#attrs)*
+            map.insert(::std::any::TypeId::of::<#ty>(), <#ty>::#method as #fn_type);
+        )
+    });
+    quote!(
+        #vis fn #name() -> &'static ::std::collections::HashMap<::std::any::TypeId, #fn_type> {
+            static INIT: ::std::sync::Once = ::std::sync::Once::new();
+            static mut MAP: ::core::option::Option<
+                &'static ::std::collections::HashMap<::std::any::TypeId, #fn_type>,
+            > = ::core::option::Option::None;
+            INIT.call_once(|| {
+                let mut map = ::std::collections::HashMap::new();
+                #(#inserts)*
+                // SAFETY: MAP is only written once, here, before any read
+                unsafe { MAP = ::core::option::Option::Some(::std::boxed::Box::leak(::std::boxed::Box::new(map))) };
+            });
+            // SAFETY: MAP was written by `call_once`, which synchronizes with this read
+            unsafe { MAP.unwrap() }
+        }
+    )
+}
+
+/// Generates a visitor trait with a method for each type, which does nothing by default:
+///
+/// ```text
+/// trait Name {
+///     fn visit_type1(&mut self, _value: &Type1) {}
+///     fn visit_type2(&mut self, _value: &Type2) {}
+/// }
+/// ```
+///
+/// The method names are given by the `template`, in which `${T}` is replaced by the type
+/// identifier in snake case (by default, `visit_${T}`). The methods have the `cfg` attributes
+/// of their type.
+fn visitor(
+    vis: &Visibility,
+    name: &Ident,
+    template: Option<&LitStr>,
+    generic_arg: &Path,
+    types: &[SubstType],
+    type_attrs: &[Vec<Attribute>],
+) -> TokenStream {
+    let placeholder = format!("${{{}}}", pathname(generic_arg));
+    let template = match template {
+        Some(template) => template.value(),
+        None => format!("visit_{}", placeholder),
+    };
+    let methods = types.iter().zip(type_attrs).map(|(ty, attrs)| {
+        let ident = match type_ident(ty) {
+            Some(ident) => ident,
+            None => abort!(
+                ty.span(),
+                "visitor requires type paths, not '{}'",
+                pathname(ty)
+            ),
+        };
+        let method = template.replace(&placeholder, &to_snake_case(&ident.to_string()));
+        let method = match syn::parse_str::<Ident>(&method) {
+            Ok(method) => method,
+            Err(_) => abort!(ty.span(), "'{}' isn't a valid method name", method),
+        };
+        quote!(
+            #(#attrs)*
+            fn #method(&mut self, _value: &#ty) {}
+        )
+    });
+    quote!(
+        #vis trait #name {
+            #(#methods)*
+        }
+    )
+}
+
+/// Gets the index of the module group of `ty`, if any.
+pub(crate) fn mod_group(
+    groups: &[(Visibility, Ident, Vec<Type>)],
+    ty: &SubstType,
+) -> Option<usize> {
+    let name = pathname(ty);
+    groups.iter().position(|(_, _, group_types)| {
+        group_types.iter().any(|group_ty| {
+            let mut group_ty = group_ty.clone();
+            TurboFish.visit_type_mut(&mut group_ty);
+            pathname(&group_ty) == name
+        })
+    })
+}
+
+/// Generates the modules of the groups, with the code generated for their types, `contents`:
+///
+/// ```text
+/// vis mod name {
+///     #[allow(unused_imports)]
+///     use super::*;
+///     // code of the types
+/// }
+/// ```
+///
+/// Each type of a group must be one of the `types`.
+pub(crate) fn mod_groups(
+    groups: &[(Visibility, Ident, Vec<Type>)],
+    contents: Vec<TokenStream>,
+    types: &[SubstType],
+) -> TokenStream {
+    let names = types.iter().map(pathname).collect::<Vec<_>>();
+    for (_, _, group_types) in groups {
+        for group_ty in group_types {
+            let mut ty = group_ty.clone();
+            TurboFish.visit_type_mut(&mut ty);
+            if !names.contains(&pathname(&ty)) {
+                abort!(
+                    group_ty.span(),
+                    "'{}' of mod_groups isn't one of the types",
+                    pathname(group_ty)
+                );
+            }
+        }
+    }
+    let mods = groups
+        .iter()
+        .zip(contents)
+        .map(|((vis, name, _), content)| {
+            quote!(
+                #vis mod #name {
+                    #[allow(unused_imports)]
+                    use super::*;
+                    #content
+                }
+            )
+        });
+    quote!(#(#mods)*)
+}
+
+/// Wraps the generated `code` in a macro `name` without parameters, which emits the code where
+/// it's invoked:
+///
+/// ```text
+/// macro_rules! name {
+///     () => { code };
+/// }
+/// ```
+///
+/// The code can't include any `$` token, which would be interpreted by the macro.
+pub(crate) fn deferred_macro(name: &Ident, code: TokenStream) -> TokenStream {
+    if let Some(span) = find_dollar(code.clone()) {
+        abort!(span, "deferred_macro doesn't support '$' in the code");
+    }
+    quote!(
+        #[allow(unused_macros)]
+        macro_rules! #name {
+            () => { #code };
+        }
+    )
+}
+
+/// Looks for a `$` token, including in the groups.
+fn find_dollar(code: TokenStream) -> Option<Span> {
+    code.into_iter().find_map(|token| match token {
+        TokenTree::Punct(p) if p.as_char() == '$' => Some(p.span()),
+        TokenTree::Group(g) => find_dollar(g.stream()),
+        _ => None,
+    })
+}
+
+/// Returns the attributes of a top-level item, if it can have any.
+fn item_attrs_mut(item: &mut Item) -> Option<&mut Vec<Attribute>> {
+    match item {
+        Item::Const(i) => Some(&mut i.attrs),
+        Item::Enum(i) => Some(&mut i.attrs),
+        Item::ExternCrate(i) => Some(&mut i.attrs),
+        Item::Fn(i) => Some(&mut i.attrs),
+        Item::ForeignMod(i) => Some(&mut i.attrs),
+        Item::Impl(i) => Some(&mut i.attrs),
+        Item::Macro(i) => Some(&mut i.attrs),
+        Item::Macro2(i) => Some(&mut i.attrs),
+        Item::Mod(i) => Some(&mut i.attrs),
+        Item::Static(i) => Some(&mut i.attrs),
+        Item::Struct(i) => Some(&mut i.attrs),
+        Item::Trait(i) => Some(&mut i.attrs),
+        Item::TraitAlias(i) => Some(&mut i.attrs),
+        Item::Type(i) => Some(&mut i.attrs),
+        Item::Union(i) => Some(&mut i.attrs),
+        Item::Use(i) => Some(&mut i.attrs),
+        _ => None,
+    }
+}
+
+/// Adds the attributes in front of those of each top-level item of the generated code.
+pub(crate) fn add_attributes(ast: &mut File, attrs: &[Attribute]) {
+    if !attrs.is_empty() {
+        for item in &mut ast.items {
+            if let Some(item_attrs) = item_attrs_mut(item) {
+                item_attrs.splice(0..0, attrs.iter().cloned());
+            }
+        }
+    }
+}
@@ -11,11 +11,7 @@ use proc_macro2::{Span, TokenStream};
 
 impl SubstType {
     pub fn is_path(&self) -> bool {
-        if let SubstType::Path(_) = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, SubstType::Path(_))
     }
 }
 
@@ -221,3 +217,788 @@ fn test_replace_str() {
         Some("X cd X ef".to_string())
     );
 }
+
+#[test]
+fn parse_options() {
+    let tests: &[(&str, usize, Option<&str>, bool)] = &[
+        // parameters                                   # types dispatch_macro  error
+        (
+            "T -> u8, u16; dispatch_macro = by_name",
+            2,
+            Some("by_name"),
+            false,
+        ),
+        (
+            "T -> u8, [u16; 2]; dispatch_macro = by_name",
+            2,
+            Some("by_name"),
+            false,
+        ),
+        (
+            "u8, u16, u32; dispatch_macro = by_name",
+            2,
+            Some("by_name"),
+            false,
+        ),
+        ("T -> u8, u16;", 2, None, false),
+        ("T -> u8, u16", 2, None, false),
+        ("T -> u8; dispatch_macro", 0, None, true),
+        (
+            "T -> u8; dispatch_macro = a, dispatch_macro = b",
+            0,
+            None,
+            true,
+        ),
+        ("T -> u8; unknown_option", 0, None, true),
+        ("T -> ; dispatch_macro = by_name", 0, None, true),
+        ("T -> ; types_from = Units", 0, None, false),
+        ("T -> u8; types_from = units::Units", 1, None, false),
+        ("T -> ", 0, None, true),
+        ("u8, u16; types_from = Units", 0, None, true),
+        (
+            "T -> u8, u16; gate_by_feature = \"with-${T}\"",
+            2,
+            None,
+            false,
+        ),
+        (
+            "T -> u8, u16; gate_by_feature, dispatch_macro = m",
+            2,
+            Some("m"),
+            false,
+        ),
+        ("T -> u8, &u16; gate_by_feature", 0, None, true),
+        ("T -> u8; gate_by_feature = with_u8", 0, None, true),
+        (
+            "T -> u8, u16; gate_by_feature, gate_case = kebab",
+            2,
+            None,
+            false,
+        ),
+        (
+            "T -> u8; gate_by_feature = \"with-${T}\", gate_case = exact",
+            1,
+            None,
+            false,
+        ),
+        ("T -> u8; gate_by_feature, gate_case = upper", 1, None, true),
+        ("T -> u8; gate_case = lower", 1, None, true),
+        (
+            "T -> u8, u16 #[deprecated]; deprecated_note = \"use u32, not ${T}\"",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8, u16; deprecated_note = \"use u32\"", 2, None, true),
+        (
+            "T -> u8 #[deprecated]; deprecated_note = note",
+            1,
+            None,
+            true,
+        ),
+        ("T -> u8, u16; skip_diagonal", 2, None, false),
+        ("T -> u8, u16; zip", 2, None, false),
+        ("T -> u8, u16; zip, skip_diagonal", 2, None, true),
+        ("T -> u8, u16; newtype, builder", 0, None, true),
+        ("T -> u8, u16; newtype, ord", 2, None, false),
+        ("T -> u8, u16; bench_fn", 2, None, false),
+        ("T -> u8; bench_fn = clone", 1, None, true),
+        ("T -> u8, u16; partial_ord", 2, None, false),
+        ("T -> u8, u16; ord, partial_ord", 0, None, true),
+        ("T -> u8, u16; marker_trait = Unit", 2, None, false),
+        (
+            "T -> u8, u16; marker_trait = pub(crate) Unit",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; marker_trait", 1, None, true),
+        ("T -> u8, u16; as_any = Erased", 2, None, false),
+        ("T -> u8, u16; as_any = pub(crate) Erased", 2, None, false),
+        ("T -> u8; as_any", 1, None, true),
+        ("T -> u8, u16; error_enum = Error", 2, None, false),
+        (
+            "T -> u8, u16; error_enum = pub Error(Box<T>), error_variant = \"Bad${T}\"",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; error_enum = Error()", 1, None, true),
+        (
+            "T -> u8; error_enum = Error, error_variant = Bad",
+            1,
+            None,
+            true,
+        ),
+        ("T -> u8; error_variant = \"Bad${T}\"", 1, None, true),
+        ("T -> u8, u16; tag_const", 2, None, false),
+        ("T -> u8, u16; tag_const = pub", 2, None, false),
+        (
+            "T -> u8, u16; tag_const = \"${T}_ID\", tag_base = 1",
+            2,
+            None,
+            false,
+        ),
+        (
+            "T -> u8, u16; tag_const = pub(crate) \"${T}_ID\"",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; tag_const =", 1, None, true),
+        ("T -> u8; tag_const, tag_base = -1", 1, None, true),
+        ("T -> u8; tag_const, tag_base = 1.5", 1, None, true),
+        ("T -> u8; tag_base = 1", 1, None, true),
+        ("T -> u8, u16; tag_trait = HasTag", 2, None, false),
+        (
+            "T -> u8, u16; tag_trait = pub HasTag, tag_base = 1",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; tag_trait", 1, None, true),
+        ("T -> u8, u16; bit_const", 2, None, false),
+        (
+            "T -> u8, u16; bit_const = pub \"${T}_FLAG\", bit_type = u64",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; bit_const =", 1, None, true),
+        ("T -> u8; bit_const, bit_type = i32", 1, None, true),
+        ("T -> u8; bit_type = u64", 1, None, true),
+        (
+            "T -> u8, u16; blanket_impl = Describe { fn describe(&self) -> String; }",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8, u16; blanket_impl = Describe {}", 2, None, false),
+        ("T -> u8; blanket_impl = Describe", 1, None, true),
+        ("T -> u8, u16; doc_table = types", 2, None, false),
+        ("T -> u8, u16; doc_table = pub types", 2, None, false),
+        ("T -> u8; doc_table", 1, None, true),
+        ("T -> u8, u16; factory = make", 2, None, false),
+        ("T -> u8, u16; factory = pub(crate) make", 2, None, false),
+        ("T -> u8; factory", 1, None, true),
+        ("T -> u8, u16; roundtrip_test = all(check)", 2, None, false),
+        (
+            "T -> u8, u16; roundtrip_test = all(tests::check)",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; roundtrip_test = all", 1, None, true),
+        ("T -> u8; roundtrip_test(check)", 1, None, true),
+        ("T -> u8, u16; ctor_array = CTORS", 2, None, false),
+        (
+            "T -> u8, u16; ctor_array = pub(crate) CTORS",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; ctor_array", 1, None, true),
+        (
+            "T -> u8, u16; strategies = arb(impl Strategy<Value = T> => any::<T>())",
+            2,
+            None,
+            false,
+        ),
+        (
+            "T -> u8; strategies = arb(Vec<T> => vec![]), strategy_feature = \"proptest\"",
+            1,
+            None,
+            false,
+        ),
+        ("T -> u8; strategies = arb(any::<T>())", 1, None, true),
+        ("T -> u8; strategy_feature = proptest", 1, None, true),
+        ("T -> u8; strategy_feature = \"proptest\"", 1, None, true),
+        (
+            "T -> u8, u16; doctests = checks(\"let _ = ${T}::MAX;\")",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; doctests = checks", 1, None, true),
+        ("T -> u8; doctests(\"let _ = 0;\")", 1, None, true),
+        ("T -> u8, u16; sorted_names = NAMES", 2, None, false),
+        (
+            "T -> u8, u16; sorted_names = pub(crate) NAMES",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; sorted_names", 1, None, true),
+        ("T -> u8, u16; name_fn = name_of", 2, None, false),
+        ("T -> u8, u16; name_fn = pub name_of", 2, None, false),
+        ("T -> u8; name_fn = \"name_of\"", 1, None, true),
+        (
+            "T -> u8, u16; visitor = Visitor, visit_method = \"on_${T}\"",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; visit_method = \"on_${T}\"", 1, None, true),
+        ("T -> u8, u16; coherence_test = coherence", 2, None, false),
+        ("T -> u8, u16; type_enum = Kind", 2, None, false),
+        ("T -> u8, u16; type_enum = pub(crate) Kind", 2, None, false),
+        (
+            "T -> u8, u16; type_enum = Kind, tag_order = alphabetical",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8, u16; tag_const, tag_order = list", 2, None, false),
+        ("T -> u8; tag_order = sorted", 1, None, true),
+        ("T -> u8; tag_order = alphabetical", 1, None, true),
+        ("T -> u8, u16; type_enum = Kind, enum_all", 2, None, false),
+        ("T -> u8, u16; enum_all, type_enum = Kind", 2, None, false),
+        ("T -> u8; enum_all", 1, None, true),
+        ("T -> u8; type_enum = Kind, enum_all = true", 1, None, true),
+        (
+            "T -> u8, u16; type_enum = Kind, enum_try_from",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; enum_try_from", 1, None, true),
+        ("T -> u8; type_enum", 1, None, true),
+        ("T -> u8; coherence_test", 1, None, true),
+        ("T -> u8, u16; type_id_map = parsers(parse)", 2, None, false),
+        (
+            "T -> u8, u16; type_id_map = pub(crate) parsers(parse)",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; type_id_map = parsers", 1, None, true),
+        (
+            "T -> u8; type_id_map = parsers(parse, other)",
+            1,
+            None,
+            true,
+        ),
+        ("T -> u8, u16; anon_const", 2, None, false),
+        (
+            "T -> u8, u16; mod_groups(a = [u8], pub(crate) b = [u16])",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8, u16; mod_groups(a = [u8, u16],)", 2, None, false),
+        ("T -> u8; mod_groups()", 1, None, true),
+        ("T -> u8; mod_groups(a = u8)", 1, None, true),
+        ("T -> u8, u16; deferred_macro = impl_all", 2, None, false),
+        ("T -> u8; deferred_macro", 1, None, true),
+        (
+            "T -> u8; deferred_macro = a, deferred_macro = b",
+            1,
+            None,
+            true,
+        ),
+        (
+            "T -> u8, u16; bidirectional, from_roundtrip_tests",
+            2,
+            None,
+            false,
+        ),
+        (
+            "T -> u8; from_roundtrip_tests, from_roundtrip_tests",
+            1,
+            None,
+            true,
+        ),
+        ("T -> u8, u16; try_from_name", 2, None, false),
+        ("T -> u8; try_from_name = true", 1, None, true),
+        ("T -> u8, u16; replace_self", 2, None, false),
+        ("T -> u8; replace_self, replace_self", 1, None, true),
+        ("T -> u8, u16; max_size = 8", 2, None, false),
+        (
+            "T -> u8, u16; max_size = LIMIT * 2, object_safe",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; max_size", 1, None, true),
+        ("T -> u8, u16; assert_bounds", 2, None, false),
+        (
+            "T -> u8, u16; assert_bounds(Send + Unpin + 'static), max_size = 8",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; assert_bounds()", 1, None, true),
+        ("T -> u8; assert_bounds(Send, Sync)", 1, None, true),
+        ("T -> u8; assert_bounds = Send", 1, None, true),
+        ("T -> u8, u16; schema_version = 3", 2, None, false),
+        (
+            "T -> u8, u16; schema_version = BASE + 1, max_size = 8",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; schema_version", 1, None, true),
+        ("T -> u8, u16; type_tag", 2, None, false),
+        (
+            "T -> u8, u16; type_tag = tag, type_tag_path = full",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; type_tag =", 1, None, true),
+        ("T -> u8; type_tag, type_tag_path = first", 1, None, true),
+        ("T -> u8; type_tag_path = full", 1, None, true),
+        ("T -> u8, u16; object_safe", 2, None, false),
+        ("T -> u8; object_safe, object_safe", 1, None, true),
+        ("T -> u8, u16; inline_never", 2, None, false),
+        ("T -> u8; inline_never, inline_never", 1, None, true),
+        ("T -> u8, u16; register = inventory::submit", 2, None, false),
+        ("T -> u8, u16; register", 2, None, true),
+        ("T -> u8; register = a, register = b", 1, None, true),
+        ("T -> u8, u16; trace_calls", 2, None, false),
+        ("T -> u8, u16; trace_calls = crate::trace", 2, None, false),
+        ("T -> u8; trace_calls = \"trace\"", 1, None, true),
+        ("T -> u8, u16; doc_alias", 2, None, false),
+        ("T -> u8, u16; doc_alias(new, get)", 2, None, false),
+        ("T -> u8; doc_alias()", 1, None, true),
+        (
+            "T -> u8, u16; doc_example = \"let _ = ${T}::MAX;\"",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; doc_example", 1, None, true),
+        (
+            "T -> u8, u16; allow(dead_code, clippy::float_cmp)",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8; allow()", 1, None, true),
+        ("T -> u8; allow(\"dead_code\")", 1, None, true),
+        ("T -> u8, u16; require_methods(new, get)", 2, None, false),
+        ("T -> u8; require_methods()", 1, None, true),
+        ("T -> u8; require_methods(a::b)", 1, None, true),
+        ("T -> u8, u16; distinct_copies", 2, None, false),
+        ("T -> u8; distinct_copies, distinct_copies", 1, None, true),
+        ("u8, u16; skip_diagonal", 0, None, true),
+        ("u8, u16; zip", 0, None, true),
+        // attributes of the types
+        ("T -> u8 #[cfg(feature = \"a\")], u16", 2, None, false),
+        (
+            "T -> u8, u16 #[cfg(not(test))]; dispatch_macro = m",
+            2,
+            Some("m"),
+            false,
+        ),
+        ("u8 #[cfg(test)], u16 #[cfg(not(test))]", 1, None, false),
+        (
+            "T -> u8 #[cfg(not(test))] #[deprecated], u16 #[doc(hidden)]",
+            2,
+            None,
+            false,
+        ),
+        ("T -> u8 #[cfg(feature = )]", 0, None, true),
+    ];
+    let mut error = 0;
+    for (idx, &(string, nbr_types, dispatch_macro, parse_error)) in tests.iter().enumerate() {
+        let report = format!("test #{idx} on '{string}': ");
+        let stream = tokenstream!(string, error);
+        let mut new_error = true;
+        match try_parse::<Subst>(stream, string) {
+            Ok(subst) => match () {
+                _ if parse_error => println!("{report}expecting parse error"),
+                _ if subst.new_types.len() != nbr_types => println!(
+                    "{report}expecting {nbr_types} type(s) instead of {}",
+                    subst.new_types.len()
+                ),
+                _ if subst
+                    .options
+                    .dispatch_macro
+                    .as_ref()
+                    .map(|m| m.to_string())
+                    .as_deref()
+                    != dispatch_macro =>
+                {
+                    println!(
+                        "{report}expecting dispatch_macro {:?} instead of {:?}",
+                        dispatch_macro, subst.options.dispatch_macro
+                    )
+                }
+                _ => new_error = false,
+            },
+            Err(e) => {
+                if !parse_error {
+                    println!("{report}parse error:\n{e}");
+                } else {
+                    new_error = false;
+                }
+            }
+        }
+        if !new_error {
+            // tests AttrParams::parse, which must keep the options
+            new_error = true;
+            let pstring = format!("({string})");
+            let stream = tokenstream!(&pstring, error);
+            match try_parse::<AttrParams>(stream, &pstring) {
+                Ok(params) => match () {
+                    _ if parse_error => new_error = false, // options aren't parsed here
+                    _ if params.new_types.len() != nbr_types => println!(
+                        "{report}expecting {nbr_types} type(s) instead of {}",
+                        params.new_types.len()
+                    ),
+                    _ if dispatch_macro.is_some() && params.options.is_empty() => {
+                        println!("{report}expecting options")
+                    }
+                    _ => new_error = false,
+                },
+                Err(e) => {
+                    if !parse_error {
+                        println!("{report}parse error:\n{e}");
+                    } else {
+                        new_error = false;
+                    }
+                }
+            }
+        }
+        if new_error {
+            error += 1;
+        };
+    }
+    assert!(error == 0, "{} error(s)", error);
+}
+
+#[test]
+fn subst_trait_items() {
+    // associated type defaults are unstable, so they're only checked here
+    let tests: &[(&str, &str, &[&str])] = &[
+        // parameters   trait                                       expected copies
+        (
+            "T -> u8, Box<u16>",
+            "trait Foo { type Bar: From<T> = Vec<T>; fn get(&self) -> T; }",
+            &[
+                "trait Foo { type Bar : From < u8 > = Vec < u8 > ; fn get (& self) -> u8 ; }",
+                "trait Foo { type Bar : From < Box :: < u16 > > = Vec < Box :: < u16 > > ; \
+                fn get (& self) -> Box :: < u16 > ; }",
+            ],
+        ),
+        (
+            "T -> u8",
+            "trait Foo { type Bar<'a> = &'a T::Inner where T: 'a; }",
+            &["trait Foo { type Bar < 'a > = & 'a u8 :: Inner where u8 : 'a ; }"],
+        ),
+    ];
+    let mut error = 0;
+    for (idx, &(args, code, expected)) in tests.iter().enumerate() {
+        let report = format!("test #{idx} on '{code}': ");
+        let mut subst = parse_str!(Subst, args, error);
+        let ast = parse_str!(File, code, error);
+        for exp in expected {
+            let mut copy = ast.clone();
+            subst.visit_file_mut(&mut copy);
+            let result = copy.to_token_stream().to_string();
+            if result != *exp {
+                println!("{report}expecting\n  {exp}\ninstead of\n  {result}");
+                error += 1;
+            }
+            subst.new_types.remove(0);
+        }
+    }
+    assert!(error == 0, "{} error(s)", error);
+}
+
+#[test]
+fn substitute_tokens() {
+    let tests: &[(&str, &str, &str, &str)] = &[
+        // generic  type        code                                                expected
+        ("T", "u64", "impl Foo for T { fn new() -> T { T::MAX } }", "impl Foo for u64 { fn new () -> u64 { u64 :: MAX } }"),
+        ("T", "Vec<u8>", "impl Foo for T { const N: &str = \"${T}\"; fn new() -> T { T::new() } }",
+            "impl Foo for Vec :: < u8 > { const N : & str = \"Vec::<u8>\" ; fn new () -> Vec :: < u8 > { Vec :: < u8 > :: new () } }"),
+        ("T", "&u8", "impl Foo for T { fn get(x: T) -> T { <T>::clone(&x) } }", "impl Foo for & u8 { fn get (x : & u8) -> & u8 { < & u8 > :: clone (& x) } }"),
+        ("U", "i32", "impl Foo for super::U { fn f() -> U { 0 } }", "impl Foo for super :: U { fn f () -> i32 { 0 } }"),
+    ];
+    for (idx, &(generic_arg, ty, code, expected)) in tests.iter().enumerate() {
+        let generic_arg = parse_str::<Path>(generic_arg).unwrap();
+        let ty = parse_str::<Type>(ty).unwrap();
+        let tokens = TokenStream::from_str(code).unwrap();
+        let result = substitute(tokens, &generic_arg, &ty).unwrap().to_string();
+        assert_eq!(result, expected, "test #{idx} failed");
+    }
+    let tokens = TokenStream::from_str("impl Foo for T;").unwrap();
+    assert!(substitute(tokens, &parse_str("T").unwrap(), &parse_str("u8").unwrap()).is_err());
+}
+
+#[test]
+fn parse_deprecated_note() {
+    let subst = parse_str::<Subst>(
+        "T -> u8, u16 #[deprecated], u32 #[deprecated(note = \"kept\")]; deprecated_note = \"use u64, not ${T}\"",
+    )
+    .unwrap();
+    let attrs = subst
+        .type_attrs
+        .iter()
+        .map(|attrs| quote!(#(#attrs)*).to_string())
+        .collect::<Vec<_>>();
+    let expected = [
+        quote!(),
+        quote!(#[deprecated(note = "use u64, not u16")]),
+        quote!(#[deprecated(note = "kept")]),
+    ]
+    .map(|attrs| attrs.to_string());
+    assert_eq!(attrs, expected);
+}
+
+#[test]
+fn companions_doc_table() {
+    let subst = parse_str::<Subst>(
+        "T -> u8, [u16; 3] #[cfg(feature = \"a\")], Vec<u8>; doc_table = pub types",
+    )
+    .unwrap();
+    let ast = parse_str::<File>("impl Zero for T {}").unwrap();
+    let code = output::companions(
+        &subst.options,
+        &subst.generic_arg,
+        &subst.new_types,
+        &subst.type_attrs,
+        &ast,
+    );
+    let doc = "Types generated for `T`:\n\n\
+        | # | Type | Configuration |\n\
+        |---|------|---------------|\n\
+        | 0 | `u8` | |\n\
+        | 1 | `[u16;3]` | `cfg(feature = \"a\")` |\n\
+        | 2 | `Vec::<u8>` | |";
+    let expected = quote!(
+        #[doc = #doc]
+        pub mod types {}
+    );
+    assert_eq!(code.to_string(), expected.to_string());
+}
+
+#[test]
+fn companions_doctests() {
+    let subst = parse_str::<Subst>(
+        "T -> u8, Vec<u8> #[cfg(feature = \"a\")]; doctests = checks(\"let x: ${T} = Default::default();\")",
+    )
+    .unwrap();
+    let ast = parse_str::<File>("impl Zero for T {}").unwrap();
+    let code = output::companions(
+        &subst.options,
+        &subst.generic_arg,
+        &subst.new_types,
+        &subst.type_attrs,
+        &ast,
+    );
+    let expected = quote!(
+        #[cfg(doctest)]
+        mod checks {
+            #[doc = "```\nlet x: u8 = Default::default();\n```"]
+            pub struct U8;
+            #[cfg(feature = "a")]
+            #[doc = "```\nlet x: Vec::<u8> = Default::default();\n```"]
+            pub struct Vec;
+        }
+    );
+    assert_eq!(code.to_string(), expected.to_string());
+}
+
+#[test]
+fn process_copy_doc_hidden() {
+    let options = parse2::<Options>(quote!(doc_hidden, forward_arc_mutex)).unwrap();
+    let ty = SubstType::Path(parse_str("Meter").unwrap());
+    let mut ast = parse_str::<File>(
+        "impl Length for Meter { fn meters(&self) -> f64 { self.0 } } struct Meter(f64);",
+    )
+    .unwrap();
+    output::process_copy(&options, &ty, &mut ast);
+    assert_eq!(ast.items.len(), 3);
+    for item in &ast.items {
+        let code = item.to_token_stream().to_string();
+        // only the top-level items are hidden
+        assert!(code.starts_with("# [doc (hidden)]"), "not hidden: {code}");
+        assert_eq!(code.matches("doc (hidden)").count(), 1, "{code}");
+    }
+}
+
+#[test]
+fn process_copy_replace_self() {
+    let options = parse2::<Options>(quote!(replace_self)).unwrap();
+    let tests: &[(&str, &str, &str)] = &[
+        // type     code                                            expected
+        (
+            "Meter",
+            "impl Len for Meter { type Out = f64; fn f(&self) -> Self { let x: Self::Out = Self::ZERO; Self(x) } }",
+            "impl Len for Meter { type Out = f64 ; fn f (& self) -> Self { let x : Self :: Out = Meter :: ZERO ; Meter (x) } }",
+        ),
+        (
+            "Vec<u8>",
+            "impl Len for Vec<u8> { fn f() -> Self { <Self as Default>::default(); Self::new() } }",
+            "impl Len for Vec < u8 > { fn f () -> Self { < Vec < u8 > as Default > :: default () ; Vec :: < u8 > :: new () } }",
+        ),
+        (
+            "&u8",
+            "impl Len for &u8 { fn f(&self) -> Self { let x: Self = Self::clone(self); x } }",
+            "impl Len for & u8 { fn f (& self) -> Self { let x : & u8 = Self :: clone (self) ; x } }",
+        ),
+        (
+            "Meter",
+            "impl Len for Meter { fn f(&self) { struct A; impl A { fn g() -> Self { Self } } println!(\"{:?}\", Self::ZERO); } }",
+            "impl Len for Meter { fn f (& self) { struct A ; impl A { fn g () -> Self { Self } } println ! (\"{:?}\" , Self :: ZERO) ; } }",
+        ),
+    ];
+    for (idx, &(ty, code, expected)) in tests.iter().enumerate() {
+        let ty = SubstType::Type(parse_str(ty).unwrap());
+        let mut ast = parse_str::<File>(code).unwrap();
+        output::process_copy(&options, &ty, &mut ast);
+        let result = ast.to_token_stream().to_string();
+        assert_eq!(result, expected, "test #{idx} failed");
+    }
+}
+
+#[test]
+fn process_copy_object_safe() {
+    let options = parse2::<Options>(quote!(object_safe)).unwrap();
+    let ty = SubstType::Path(parse_str("Meter").unwrap());
+    let mut ast = parse_str::<File>(
+        "impl<'a> Length<'a, f64> for Meter { type Unit = Vec<u8>; fn meters(&self) -> f64 { self.0 } } impl Meter { }",
+    )
+    .unwrap();
+    output::process_copy(&options, &ty, &mut ast);
+    assert_eq!(ast.items.len(), 3);
+    let code = ast.items[2].to_token_stream().to_string();
+    assert_eq!(
+        code,
+        "const _ : () = { fn _assert_object_safe < 'a > (_ : & dyn Length < 'a , f64 , Unit = Vec < u8 > >) { } } ;"
+    );
+}
+
+#[test]
+fn process_copy_inline_never() {
+    let options = parse2::<Options>(quote!(inline_never)).unwrap();
+    let ty = SubstType::Path(parse_str("Meter").unwrap());
+    let mut ast = parse_str::<File>(
+        "impl Length for Meter { const UNIT: &'static str = \"m\"; #[inline(always)] fn meters(&self) -> f64 { self.0 } }",
+    )
+    .unwrap();
+    output::process_copy(&options, &ty, &mut ast);
+    let code = ast.to_token_stream().to_string();
+    assert_eq!(code.matches("inline").count(), 1, "{code}");
+    assert!(
+        code.starts_with("impl Length for Meter { const UNIT")
+            && code.contains("# [inline (never)] fn meters"),
+        "{code}"
+    );
+}
+
+#[test]
+fn process_copy_trace_calls() {
+    let options = parse2::<Options>(quote!(trace_calls)).unwrap();
+    let ty = SubstType::Path(parse_str("Meter").unwrap());
+    let mut ast = parse_str::<File>(
+        "impl Length for Meter { fn meters(&self) -> f64 { self.0 } } impl Meter { fn new() -> Self { Meter(0.0) } }",
+    )
+    .unwrap();
+    output::process_copy(&options, &ty, &mut ast);
+    let code = ast.to_token_stream().to_string();
+    assert!(
+        code.contains("{ log :: trace ! (\"<Meter as Length>::meters\") ; self . 0 }"),
+        "{code}"
+    );
+    assert!(
+        code.contains("{ log :: trace ! (\"Meter::new\") ; Meter (0.0) }"),
+        "{code}"
+    );
+}
+
+#[test]
+fn process_copy_doc_alias() {
+    let ty = SubstType::Path(parse_str("Meter").unwrap());
+    let code = "impl Length for Meter { fn new() -> Self { Meter(0.0) } fn meters(&self) -> f64 { self.0 } }";
+    let tests = [
+        (quote!(doc_alias), ["Meter", "Meter"]),
+        (quote!(doc_alias(meters)), ["", "Meter"]),
+    ];
+    for (options, expected) in tests {
+        let options = parse2::<Options>(options).unwrap();
+        let mut ast = parse_str::<File>(code).unwrap();
+        output::process_copy(&options, &ty, &mut ast);
+        let item_impl = match &ast.items[0] {
+            syn::Item::Impl(item_impl) => item_impl,
+            _ => panic!("expected an implementation"),
+        };
+        let aliases = item_impl
+            .items
+            .iter()
+            .map(|item| match item {
+                ImplItem::Method(method) => method
+                    .attrs
+                    .iter()
+                    .map(|a| a.tokens.to_string())
+                    .collect::<String>(),
+                _ => panic!("expected a method"),
+            })
+            .collect::<Vec<_>>();
+        let expected = expected.map(|alias| {
+            if alias.is_empty() {
+                String::new()
+            } else {
+                format!("(alias = \"{alias}\")")
+            }
+        });
+        assert_eq!(aliases, expected);
+    }
+}
+
+#[test]
+fn doc_example_substitution() {
+    let mut subst = parse_str::<Subst>("T -> u8; doc_example = \"let x: ${T} = 0;\"").unwrap();
+    let mut ast = parse_str::<File>("/// Zero\nimpl Zero for T {}").unwrap();
+    output::add_doc_example(subst.options.doc_example.as_ref().unwrap(), &mut ast);
+    subst.visit_file_mut(&mut ast);
+    let expected = quote!(
+        #[doc = " Zero"]
+        #[doc = ""]
+        #[doc = "# Example\n\n```\nlet x: u8 = 0;\n```"]
+        impl Zero for u8 {}
+    );
+    assert_eq!(ast.to_token_stream().to_string(), expected.to_string());
+}
+
+#[test]
+fn subst_allowed_attributes() {
+    let tests: &[(&str, &str, &str)] = &[
+        // parameters                   attribute                           expected
+        (
+            "T -> u8; subst_attrs(mock::helper)",
+            "#[mock::helper(ty = T, list(T, Vec<T>), other::T, self.T, \"${T}\")]",
+            "# [mock :: helper (ty = u8 , list (u8 , Vec < u8 >) , other :: T , self . T , \"u8\")]",
+        ),
+        (
+            "my::T -> Box<u8>; subst_attrs(helper)",
+            "#[helper(my::T, T, [my::T; 2])]",
+            "# [helper (Box :: < u8 > , T , [Box :: < u8 > ; 2])]",
+        ),
+        (
+            "T -> u8; subst_attrs(helper)",
+            "#[other(T)]",
+            "# [other (T)]",
+        ),
+    ];
+    let mut error = 0;
+    for (idx, &(args, attr, expected)) in tests.iter().enumerate() {
+        let report = format!("test #{idx} on '{attr}': ");
+        let mut subst = parse_str!(Subst, args, error);
+        let code = format!("{attr} struct Foo;");
+        let mut ast = parse_str!(File, &code, error);
+        subst.visit_file_mut(&mut ast);
+        let result = match &ast.items[0] {
+            syn::Item::Struct(item) => item.attrs[0].to_token_stream().to_string(),
+            _ => unreachable!(),
+        };
+        if result != expected {
+            println!("{report}expecting\n  {expected}\ninstead of\n  {result}");
+            error += 1;
+        }
+    }
+    assert!(error == 0, "{} error(s)", error);
+}
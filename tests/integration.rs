@@ -2,6 +2,12 @@
 //
 // Integration tests.
 
+// The generated code is identical for all the types, so some casts are only required by a
+// subset of them:
+#![allow(clippy::unnecessary_cast)]
+// The tests use more recent features of the language than the library's MSRV:
+#![allow(clippy::incompatible_msrv)]
+
 // =============================================================================
 // Main format:
 //
@@ -14,7 +20,7 @@
 // -----------------------------------------------------------------------------
 
 mod supported_formats {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     struct Test<T>(T);
 
@@ -88,7 +94,7 @@ mod supported_formats {
 }
 
 mod type_case_01 {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait MyLog {
         fn my_log2(self) -> u32;
@@ -127,7 +133,7 @@ mod type_case_01 {
 }
 
 mod type_case_02 {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait MyLog {
         fn my_log2(self) -> u32;
@@ -175,7 +181,7 @@ mod type_case_02 {
 }
 
 mod type_case_03 {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait Name {
         fn name(&self) -> String;
@@ -210,8 +216,8 @@ mod type_case_03 {
 }
 
 mod type_case_04 {
+    use conditional_trait_gen::trait_gen;
     use std::ops::Deref;
-    use trait_gen::trait_gen;
 
     #[derive(Debug, PartialEq)]
     struct Meter(i64);
@@ -233,6 +239,7 @@ mod type_case_04 {
 
     #[trait_gen(U -> &T, &mut T, Box<T>)]
     #[trait_gen(T -> Meter, Foot)]
+    #[allow(suspicious_double_ref_op)]
     impl Negate for U {
         type Output = T;
         fn negate(self) -> Self::Output {
@@ -267,8 +274,8 @@ struct Meter<T>(T);
 struct Foot<T>(T);
 
 mod path_case_01 {
+    use conditional_trait_gen::trait_gen;
     use std::ops::{Add, Neg};
-    use trait_gen::trait_gen;
 
     pub mod inner {}
 
@@ -316,8 +323,8 @@ mod path_case_02 {
     struct Foot<T>(T);
 
     pub mod inner {
+        use conditional_trait_gen::trait_gen;
         use std::ops::Add;
-        use trait_gen::trait_gen;
 
         #[trait_gen(gen::U -> super::Meter<f32>, super::Foot<f32>)]
         impl Add for gen::U {
@@ -346,8 +353,8 @@ mod path_case_02 {
 }
 
 mod path_case_03 {
+    use conditional_trait_gen::trait_gen;
     use std::fmt::Display;
-    use trait_gen::trait_gen;
 
     struct Name<'a>(&'a str);
     struct Value(i32);
@@ -386,7 +393,7 @@ mod path_case_03 {
 }
 
 mod path_case_04 {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     struct Name<'a>(&'a str);
     struct Value<'a>(&'a f64);
@@ -414,7 +421,7 @@ mod path_case_05 {
     struct Value<'a>(&'a f64);
     mod inner {
         mod depth {
-            use trait_gen::trait_gen;
+            use conditional_trait_gen::trait_gen;
 
             trait Show {
                 fn show(&self) -> String;
@@ -437,7 +444,7 @@ mod path_case_05 {
 }
 
 mod path_case_06 {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     struct Name<'a>(&'a str);
     struct Value<'a>(&'a f64);
@@ -461,7 +468,8 @@ mod path_case_06 {
 }
 
 mod literals {
-    use trait_gen::trait_gen;
+    #![allow(static_mut_refs)]
+    use conditional_trait_gen::trait_gen;
     static mut CALLS: Vec<String> = Vec::new();
 
     trait Lit {
@@ -494,8 +502,8 @@ mod literals {
 }
 
 mod subst_cases {
+    use conditional_trait_gen::trait_gen;
     use std::ops::{Add, Sub};
-    use trait_gen::trait_gen;
 
     trait AddMod {
         fn add_mod(self, other: Self, m: Self) -> Self;
@@ -524,9 +532,10 @@ mod subst_cases {
 }
 
 mod type_args {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait Number<X, T> {
+        #[allow(dead_code)]
         fn fake(x: X) -> T;
     }
 
@@ -567,7 +576,7 @@ mod type_args {
 }
 
 mod type_fn_args {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait Transformer<T: Copy> {
         fn transform<F: Fn(T) -> T>(&self, f: F) -> Vec<T>;
@@ -592,8 +601,8 @@ mod type_fn_args {
 }
 
 mod cross_product {
+    use conditional_trait_gen::trait_gen;
     use std::ops::Neg;
-    use trait_gen::trait_gen;
 
     #[derive(PartialEq, Debug)]
     struct Meter<U>(U);
@@ -672,8 +681,8 @@ mod cross_product {
 }
 
 mod ex01a {
+    use conditional_trait_gen::trait_gen;
     use std::ops::Add;
-    use trait_gen::trait_gen;
 
     #[derive(Clone, Copy)]
     /// Length in meter
@@ -735,7 +744,7 @@ mod ex01a {
 }
 
 mod ex02a {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait AddMod {
         fn add_mod(self, other: Self, m: Self) -> Self;
@@ -761,7 +770,7 @@ mod ex02a {
 }
 
 mod ex03a {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait ToU64 {
         fn into_u64(self) -> u64;
@@ -801,7 +810,7 @@ mod ex03a {
 }
 
 mod ex04 {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait A where
         Self: Sized,
@@ -853,8 +862,8 @@ mod ex04 {
 // -----------------------------------------------------------------------------
 
 mod ex01b {
+    use conditional_trait_gen::trait_gen;
     use std::ops::Add;
-    use trait_gen::trait_gen;
 
     #[derive(Clone, Copy)]
     /// Length in meter
@@ -928,7 +937,7 @@ mod ex01b {
 }
 
 mod ex02b {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait AddMod {
         fn add_mod(self, other: Self, m: Self) -> Self;
@@ -954,7 +963,7 @@ mod ex02b {
 }
 
 mod ex03b {
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     trait ToU64 {
         fn into_u64(self) -> u64;
@@ -1010,7 +1019,7 @@ mod ex03b {
 
 mod conditional {
     use async_trait::async_trait;
-    use trait_gen::{trait_gen, when};
+    use conditional_trait_gen::{trait_gen, when};
 
     mod sqlx {
         pub mod sqlite {
@@ -1125,7 +1134,7 @@ mod conditional {
 
 mod impl_type_01 {
     use super::{Foot, Meter};
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     #[trait_gen(T -> f32, f64)]
     impl Foot<T> {
@@ -1146,7 +1155,7 @@ mod impl_type_01 {
 
 mod impl_type_02 {
     use super::{Foot, Meter};
-    use trait_gen::trait_gen;
+    use conditional_trait_gen::trait_gen;
 
     #[trait_gen(T -> f32, f64)]
     impl Meter<T> {
@@ -1165,3 +1174,2250 @@ mod impl_type_02 {
         assert!((Meter::<f64>::from_foot(Foot(1.0_f64)).0 - 0.29656_f64).abs() < 1e-5);
     }
 }
+
+// =============================================================================
+// Substitution in specific constructs.
+// -----------------------------------------------------------------------------
+
+mod question_mark {
+    use conditional_trait_gen::trait_gen;
+    use std::str::FromStr;
+
+    #[derive(Debug, PartialEq)]
+    struct ParseError(String);
+    #[derive(Debug, PartialEq)]
+    struct HalfError(String);
+
+    impl From<ParseError> for HalfError {
+        fn from(e: ParseError) -> Self {
+            HalfError(format!("cannot halve: {}", e.0))
+        }
+    }
+
+    fn parse_value<V: FromStr>(s: &str) -> Result<V, ParseError> {
+        s.parse::<V>().map_err(|_| ParseError(s.to_string()))
+    }
+
+    trait Halve: Sized {
+        fn halve(s: &str) -> Result<Self, HalfError>;
+        fn halve_twice(s: &str) -> Result<(Self, Self), HalfError>;
+    }
+
+    #[trait_gen(T -> u8, i32, f64)]
+    impl Halve for T {
+        fn halve(s: &str) -> Result<T, HalfError> {
+            let value: T = parse_value::<T>(s)?;
+            Ok(value / (2 as T))
+        }
+
+        fn halve_twice(s: &str) -> Result<(T, T), HalfError> {
+            let half = T::halve(s)?;
+            Ok((half, half / (2 as T)))
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::halve("10"), Ok(5));
+        assert_eq!(i32::halve("-10"), Ok(-5));
+        assert_eq!(f64::halve("5"), Ok(2.5));
+        assert_eq!(u8::halve_twice("20"), Ok((10, 5)));
+        assert_eq!(f64::halve_twice("5"), Ok((2.5, 1.25)));
+        assert_eq!(
+            u8::halve("-10"),
+            Err(HalfError("cannot halve: -10".to_string()))
+        );
+        assert_eq!(
+            i32::halve_twice("x"),
+            Err(HalfError("cannot halve: x".to_string()))
+        );
+    }
+}
+
+mod local_static {
+    use conditional_trait_gen::trait_gen;
+    use std::sync::Mutex;
+
+    trait Cache: Sized {
+        fn cache(value: Self) -> usize;
+        fn cached() -> Vec<Self>;
+    }
+
+    #[trait_gen(T -> u8, i64, String)]
+    impl Cache for T {
+        fn cache(value: T) -> usize {
+            static CACHE: Mutex<Vec<T>> = Mutex::new(Vec::new());
+            let mut cache = CACHE.lock().unwrap();
+            cache.push(value);
+            cache.len()
+        }
+
+        fn cached() -> Vec<T> {
+            static EMPTY: Mutex<Option<T>> = Mutex::new(None::<T>);
+            EMPTY.lock().unwrap().iter().cloned().collect()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::cache(1), 1);
+        assert_eq!(u8::cache(2), 2);
+        assert_eq!(i64::cache(-1), 1);
+        assert_eq!(String::cache("a".to_string()), 1);
+        assert_eq!(u8::cache(3), 3);
+        assert_eq!(u8::cached(), Vec::<u8>::new());
+        assert_eq!(String::cached(), Vec::<String>::new());
+    }
+}
+
+mod assert_macros {
+    use conditional_trait_gen::trait_gen;
+
+    trait Reset: Sized {
+        fn reset(&mut self) -> Self;
+    }
+
+    #[trait_gen(T -> u8, i64, f32, String)]
+    impl Reset for T {
+        fn reset(&mut self) -> T {
+            let old = std::mem::take(self);
+            assert_eq!(*self, T::default());
+            assert_ne!(T::default(), old, "{} was already reset", "${T}");
+            debug_assert!(*self == T::default());
+            debug_assert_eq!(self.clone(), <T as Default>::default());
+            std::assert!(T::default() == *self, "{:?}", T::default());
+            old
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(5_u8.reset(), 5);
+        assert_eq!((-3_i64).reset(), -3);
+        assert_eq!(1.5_f32.reset(), 1.5);
+        assert_eq!("a".to_string().reset(), "a");
+    }
+
+    #[test]
+    #[should_panic(expected = "u8 was already reset")]
+    fn test_panic() {
+        0_u8.reset();
+    }
+}
+
+mod matches_or_patterns {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Clone, Copy)]
+    enum Light {
+        Red,
+        Amber,
+        Green,
+        Blink(u8),
+    }
+
+    #[derive(Clone, Copy)]
+    enum Arrow {
+        Red,
+        Amber,
+        Green,
+        Blink(u8),
+    }
+
+    trait Signal {
+        fn is_stop(&self) -> bool;
+        fn is_slow(&self) -> bool;
+    }
+
+    #[trait_gen(T -> Light, Arrow)]
+    impl Signal for T {
+        fn is_stop(&self) -> bool {
+            matches!(self, T::Red | T::Amber | T::Blink(0))
+        }
+
+        fn is_slow(&self) -> bool {
+            std::matches!(*self, | T::Amber | T::Blink(_) if !matches!(self, T::Blink(0)),)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert!(Light::Red.is_stop());
+        assert!(Light::Amber.is_stop());
+        assert!(!Light::Green.is_stop());
+        assert!(Arrow::Blink(0).is_stop());
+        assert!(!Arrow::Blink(1).is_stop());
+        assert!(Arrow::Amber.is_slow());
+        assert!(Arrow::Blink(2).is_slow());
+        assert!(!Light::Blink(0).is_slow());
+        assert!(!Light::Red.is_slow());
+        assert!(Arrow::Red.is_stop());
+        assert!(!Arrow::Green.is_slow());
+    }
+}
+
+mod impl_trait_argument {
+    use conditional_trait_gen::trait_gen;
+
+    trait PushInto<V> {
+        fn push_into(&mut self, value: impl Into<V>) -> usize;
+    }
+
+    #[trait_gen(T -> u32, i64, f64)]
+    impl PushInto<T> for Vec<T> {
+        fn push_into(&mut self, value: impl Into<T>) -> usize {
+            let value: T = value.into();
+            self.push(value);
+            self.len()
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut a = Vec::<u32>::new();
+        assert_eq!(a.push_into(5_u8), 1);
+        assert_eq!(a.push_into(6_u16), 2);
+        assert_eq!(a, [5, 6]);
+        let mut b = Vec::<i64>::new();
+        b.push_into(-3_i32);
+        assert_eq!(b, [-3]);
+        let mut c = Vec::<f64>::new();
+        c.push_into(1.5_f32);
+        assert_eq!(c, [1.5]);
+    }
+}
+
+mod transmute_turbofish {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Rgba(u8, u8, u8, u8);
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Bgra(u8, u8, u8, u8);
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(transparent)]
+    struct Packed([u8; 4]);
+
+    trait ToBytes {
+        fn to_bytes(self) -> [u8; 4];
+    }
+
+    // the types have the same size as the target, so the transmutes are sound
+    #[trait_gen(T -> Rgba, Bgra, Packed)]
+    impl ToBytes for T {
+        fn to_bytes(self) -> [u8; 4] {
+            let bytes = unsafe { std::mem::transmute::<T, [u8; 4]>(self) };
+            // the fixed type isn't substituted:
+            let back = unsafe { core::mem::transmute::<[u8; 4], T>(bytes) };
+            assert_eq!(back, self);
+            bytes
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Rgba(1, 2, 3, 4).to_bytes(), [1, 2, 3, 4]);
+        assert_eq!(Bgra(4, 3, 2, 1).to_bytes(), [4, 3, 2, 1]);
+        assert_eq!(Packed([5, 6, 7, 8]).to_bytes(), [5, 6, 7, 8]);
+    }
+}
+
+mod async_move_block {
+    use conditional_trait_gen::trait_gen;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    /// Polls a future which doesn't wait for anything.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        fn raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        let waker = unsafe { Waker::from_raw(raw_waker()) };
+        let mut future = Box::pin(future);
+        match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(value) => value,
+            Poll::Pending => panic!("the future isn't ready"),
+        }
+    }
+
+    async fn build<V: From<u8>>(value: u8) -> V {
+        V::from(value)
+    }
+
+    trait Spawn: Sized {
+        fn spawn(self) -> Pin<Box<dyn Future<Output = Vec<Self>>>>;
+    }
+
+    #[trait_gen(T -> u16, i32, f64)]
+    impl Spawn for T {
+        fn spawn(self) -> Pin<Box<dyn Future<Output = Vec<T>>>> {
+            Box::pin(async move {
+                let x: T = build::<T>(2).await;
+                let y = async { T::from(3_u8) }.await;
+                vec![self, x, y]
+            })
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(block_on(1_u16.spawn()), [1, 2, 3]);
+        assert_eq!(block_on((-1_i32).spawn()), [-1, 2, 3]);
+        assert_eq!(block_on(0.5_f64.spawn()), [0.5, 2.0, 3.0]);
+    }
+}
+
+mod vec_macro {
+    use conditional_trait_gen::trait_gen;
+
+    trait Defaults: Sized {
+        const N: usize;
+        fn defaults() -> Vec<Self>;
+        fn pair(self) -> Vec<Self>;
+    }
+
+    #[trait_gen(T -> u8, f64, String)]
+    impl Defaults for T {
+        const N: usize = 3;
+
+        fn defaults() -> Vec<T> {
+            vec![T::default(); <T as Defaults>::N]
+        }
+
+        fn pair(self) -> Vec<T> {
+            vec![self, <T>::default()]
+        }
+    }
+
+    #[test]
+    fn test() {
+        let a: Vec<u8> = u8::defaults();
+        assert_eq!(a, [0, 0, 0]);
+        assert_eq!(f64::defaults(), [0.0; 3]);
+        assert_eq!(String::defaults(), ["", "", ""]);
+        assert_eq!(7_u8.pair(), [7, 0]);
+        assert_eq!("a".to_string().pair(), ["a", ""]);
+    }
+}
+
+mod array_from_fn {
+    use conditional_trait_gen::trait_gen;
+
+    trait Filled: Sized {
+        fn filled() -> [Self; 3];
+        fn indexed() -> [Self; 4];
+    }
+
+    #[trait_gen(T -> u16, i64, f32)]
+    impl Filled for T {
+        fn filled() -> [T; 3] {
+            core::array::from_fn(|_| T::default())
+        }
+
+        fn indexed() -> [T; 4] {
+            std::array::from_fn(|i| {
+                let value: T = (i as u8).into();
+                value * <T>::from(2_u8)
+            })
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u16::filled(), [0; 3]);
+        assert_eq!(f32::filled(), [0.0; 3]);
+        assert_eq!(u16::indexed(), [0, 2, 4, 6]);
+        assert_eq!(i64::indexed(), [0, 2, 4, 6]);
+        assert_eq!(f32::indexed(), [0.0, 2.0, 4.0, 6.0]);
+    }
+}
+
+mod loops {
+    use conditional_trait_gen::trait_gen;
+
+    trait Sum: Sized {
+        fn sum_while(values: Vec<Self>) -> Self;
+        fn sum_loop(values: &[Self]) -> Self;
+    }
+
+    // patterns can't have a type annotation in `while let`, so the types are annotated in the
+    // scrutinee and in the bindings of the bodies
+    #[trait_gen(T -> u32, i64, f64)]
+    impl Sum for T {
+        fn sum_while(values: Vec<T>) -> T {
+            let mut iter = values.into_iter();
+            let mut total = T::default();
+            while let Some(x) = Iterator::next(&mut iter as &mut dyn Iterator<Item = T>) {
+                let value: T = x;
+                total += value;
+            }
+            total
+        }
+
+        fn sum_loop(values: &[T]) -> T {
+            let mut iter = values.iter();
+            let mut total = <T>::default();
+            loop {
+                let next: Option<&T> = iter.next();
+                match next {
+                    Some(&x) => total += x as T,
+                    None => break total,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u32::sum_while(vec![1, 2, 3]), 6);
+        assert_eq!(i64::sum_while(vec![-1, 2]), 1);
+        assert_eq!(f64::sum_while(vec![0.5, 0.25]), 0.75);
+        assert_eq!(u32::sum_loop(&[4, 5]), 9);
+        assert_eq!(f64::sum_loop(&[]), 0.0);
+    }
+}
+
+mod iter_constructors {
+    use conditional_trait_gen::trait_gen;
+    use std::iter;
+
+    trait Repeated: Sized {
+        fn once_default() -> Vec<Self>;
+        fn repeated(n: usize) -> Vec<Self>;
+    }
+
+    #[trait_gen(T -> u16, i64, f64)]
+    impl Repeated for T {
+        fn once_default() -> Vec<T> {
+            iter::once(T::default()).collect()
+        }
+
+        fn repeated(n: usize) -> Vec<T> {
+            std::iter::repeat(<T as Default>::default())
+                .take(n)
+                .chain(iter::once(T::from(1_u8)))
+                .collect::<Vec<T>>()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u16::once_default(), [0]);
+        assert_eq!(f64::once_default(), [0.0]);
+        assert_eq!(u16::repeated(2), [0, 0, 1]);
+        assert_eq!(i64::repeated(0), [1]);
+        assert_eq!(f64::repeated(1), [0.0, 1.0]);
+    }
+}
+
+mod mixed_turbofish {
+    use conditional_trait_gen::trait_gen;
+
+    fn make<V: Default + Copy, const N: usize>() -> [V; N] {
+        [V::default(); N]
+    }
+
+    fn fill<V: Copy, const N: usize>(value: V) -> [V; N] {
+        [value; N]
+    }
+
+    trait Make: Sized {
+        fn make4() -> [Self; 4];
+        fn fill2(self) -> [Self; 2];
+    }
+
+    #[trait_gen(T -> u8, f64, bool)]
+    impl Make for T {
+        fn make4() -> [T; 4] {
+            make::<T, 4>()
+        }
+
+        fn fill2(self) -> [T; 2] {
+            fill::<T, { 1 + 1 }>(self)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::make4(), [0; 4]);
+        assert_eq!(f64::make4(), [0.0; 4]);
+        assert_eq!(bool::make4(), [false; 4]);
+        assert_eq!(3_u8.fill2(), [3, 3]);
+        assert_eq!(true.fill2(), [true, true]);
+    }
+}
+
+mod move_closure {
+    use conditional_trait_gen::trait_gen;
+
+    struct Maker<V>(V);
+
+    #[trait_gen(T -> u32, String, Vec<i8>)]
+    impl Maker<T> {
+        fn maker() -> impl Fn() -> T {
+            move || T::default()
+        }
+
+        fn repeater(self) -> impl FnMut() -> Vec<T> {
+            let mut items: Vec<T> = Vec::new();
+            move || {
+                items.push(<T as Clone>::clone(&self.0));
+                items.clone()
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        let make = Maker::<u32>::maker();
+        assert_eq!(make(), 0);
+        assert_eq!(Maker::<String>::maker()(), "");
+        assert!(Maker::<Vec<i8>>::maker()().is_empty());
+        let mut repeat = Maker(7_u32).repeater();
+        repeat();
+        assert_eq!(repeat(), [7, 7]);
+        let mut repeat = Maker(vec![1_i8]).repeater();
+        assert_eq!(repeat(), [[1]]);
+        let mut repeat = Maker("a".to_string()).repeater();
+        assert_eq!(repeat(), ["a"]);
+    }
+}
+
+mod raw_pointers {
+    use conditional_trait_gen::trait_gen;
+    use std::ptr;
+
+    trait RawPtr: Sized {
+        fn null_ptr() -> *const Self;
+        fn first_byte(&self) -> u8;
+        fn set_through(&mut self, value: Self);
+    }
+
+    #[trait_gen(T -> u16, u32, i64)]
+    impl RawPtr for T {
+        fn null_ptr() -> *const T {
+            ptr::null::<T>()
+        }
+
+        fn first_byte(&self) -> u8 {
+            // the fixed cast target isn't substituted:
+            unsafe { *(self as *const T as *const u8) }
+        }
+
+        fn set_through(&mut self, value: T) {
+            let p = self as *mut T;
+            unsafe { std::ptr::write::<T>(p, value) }
+            assert!(!p.is_null() && ptr::null_mut::<T>().is_null());
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert!(u16::null_ptr().is_null());
+        assert!(i64::null_ptr().is_null());
+        assert_eq!(0x0102_u16.first_byte(), 0x0102_u16.to_ne_bytes()[0]);
+        assert_eq!(0x01020304_u32.first_byte(), 0x01020304_u32.to_ne_bytes()[0]);
+        assert_eq!((-1_i64).first_byte(), 0xff);
+        let mut x = 1_u32;
+        x.set_through(5);
+        assert_eq!(x, 5);
+        let mut y = 0_i64;
+        y.set_through(-3);
+        assert_eq!(y, -3);
+        let mut z = 0_u16;
+        z.set_through(9);
+        assert_eq!(z, 9);
+    }
+}
+
+mod function_values {
+    use conditional_trait_gen::trait_gen;
+    use std::convert::identity;
+
+    trait Values: Sized {
+        fn defaults(n: usize) -> Vec<Self>;
+        fn same(values: Vec<Self>) -> Vec<Self>;
+        fn apply(self, f: fn(Self) -> Self) -> Self {
+            f(self)
+        }
+    }
+
+    #[trait_gen(T -> u8, i64, String)]
+    impl Values for T {
+        fn defaults(n: usize) -> Vec<T> {
+            std::iter::repeat_with(T::default).take(n).collect()
+        }
+
+        fn same(values: Vec<T>) -> Vec<T> {
+            let id = identity::<T>;
+            values
+                .into_iter()
+                .map(id)
+                .map(std::convert::identity::<T>)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::defaults(2), [0, 0]);
+        assert_eq!(String::defaults(1), [""]);
+        assert!(i64::defaults(0).is_empty());
+        assert_eq!(i64::same(vec![-1, 2]), [-1, 2]);
+        assert_eq!(u8::same(vec![3]), [3]);
+        assert_eq!(String::same(vec!["a".to_string()]), ["a"]);
+        assert_eq!(5_u8.apply(identity::<u8>), 5);
+    }
+}
+
+mod binding_patterns {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Light {
+        Off,
+        On(u8),
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Arrow {
+        Off,
+        On(u8),
+    }
+
+    trait Level: Sized {
+        fn is_dim(&self) -> bool;
+        fn dimmed(self) -> Option<Self>;
+    }
+
+    #[trait_gen(T -> Light, Arrow)]
+    impl Level for T {
+        fn is_dim(&self) -> bool {
+            matches!(*self, v @ T::On(_) if v != T::On(255))
+        }
+
+        fn dimmed(self) -> Option<T> {
+            match self {
+                off @ T::Off => Some(off),
+                T::On(level @ 1..=u8::MAX) => Some(T::On(level / 2)),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert!(Light::On(3).is_dim());
+        assert!(!Light::On(255).is_dim());
+        assert!(!Arrow::Off.is_dim());
+        assert_eq!(Arrow::On(10).dimmed(), Some(Arrow::On(5)));
+        assert_eq!(Light::Off.dimmed(), Some(Light::Off));
+        assert_eq!(Light::On(0).dimmed(), None);
+    }
+}
+
+mod iterator_chains {
+    use conditional_trait_gen::trait_gen;
+
+    trait Widen: Sized {
+        fn widen(a: u8, b: u8) -> Vec<Self>;
+        fn low_bytes(values: &[Self]) -> Vec<u8>;
+    }
+
+    #[trait_gen(T -> u16, i64, f32)]
+    impl Widen for T {
+        fn widen(a: u8, b: u8) -> Vec<T> {
+            let tail: std::array::IntoIter<T, 1> = [T::from(1_u8)].into_iter();
+            [a, b]
+                .into_iter()
+                .map(|x| x as T)
+                .chain(tail)
+                .chain(<[T; 0] as IntoIterator>::into_iter([]))
+                .collect::<Vec<T>>()
+        }
+
+        fn low_bytes(values: &[T]) -> Vec<u8> {
+            // the fixed cast target isn't substituted:
+            values
+                .iter()
+                .copied()
+                .map(|x: T| x as u8)
+                .filter_map(|x| Some(x).filter(|&b| b != 0))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u16::widen(1, 2), [1, 2, 1]);
+        assert_eq!(i64::widen(0, 255), [0, 255, 1]);
+        assert_eq!(f32::widen(3, 4), [3.0, 4.0, 1.0]);
+        assert_eq!(u16::low_bytes(&[0x102, 0x100]), [2]);
+        assert_eq!(f32::low_bytes(&[2.5, 0.5]), [2]);
+    }
+}
+
+mod format_args_macro {
+    use conditional_trait_gen::trait_gen;
+    use std::fmt::Write;
+
+    trait Describe {
+        fn describe(&self) -> String;
+    }
+
+    #[trait_gen(T -> u8, f64, String)]
+    impl Describe for T {
+        fn describe(&self) -> String {
+            let mut s = String::new();
+            s.write_fmt(format_args!(
+                "{} T {:?} {default:?}",
+                self,
+                T::default(),
+                default = <T as Default>::default()
+            ))
+            .unwrap();
+            s
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(5_u8.describe(), "5 T 0 0");
+        assert_eq!(1.5_f64.describe(), "1.5 T 0.0 0.0");
+        assert_eq!("a".to_string().describe(), "a T \"\" \"\"");
+    }
+}
+
+mod associated_fn_turbofish {
+    use conditional_trait_gen::trait_gen;
+
+    trait Containers: Sized {
+        fn boxed(self) -> Box<Self>;
+        fn buffer(n: usize) -> Vec<Self>;
+    }
+
+    #[trait_gen(T -> u16, f32, String)]
+    impl Containers for T {
+        fn boxed(self) -> Box<T> {
+            Box::<T>::new(self)
+        }
+
+        fn buffer(n: usize) -> Vec<T> {
+            let mut v = Vec::<T>::with_capacity(n);
+            v.push(T::default());
+            v
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(*5_u16.boxed(), 5);
+        assert_eq!(*1.5_f32.boxed(), 1.5);
+        assert_eq!(*"a".to_string().boxed(), "a");
+        let v = u16::buffer(8);
+        assert!(v.capacity() >= 8);
+        assert_eq!(v, [0]);
+        assert_eq!(f32::buffer(2), [0.0]);
+        assert_eq!(String::buffer(1), [""]);
+    }
+}
+
+mod size_of_val {
+    use conditional_trait_gen::trait_gen;
+    use std::mem::size_of_val;
+
+    trait ValueSize {
+        fn value_size() -> usize;
+        fn array_size() -> usize;
+    }
+
+    #[trait_gen(T -> u8, f64, (u16, u32))]
+    impl ValueSize for T {
+        fn value_size() -> usize {
+            let x: T = Default::default();
+            size_of_val(&x)
+        }
+
+        fn array_size() -> usize {
+            let a: [T; 3] = Default::default();
+            std::mem::size_of_val(&a)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::value_size(), 1);
+        assert_eq!(f64::value_size(), 8);
+        assert_eq!(<(u16, u32)>::value_size(), 8);
+        assert_eq!(u8::array_size(), 3);
+        assert_eq!(f64::array_size(), 24);
+        assert_eq!(<(u16, u32)>::array_size(), 24);
+    }
+}
+
+mod maybe_sized_bounds {
+    use conditional_trait_gen::trait_gen;
+
+    struct Boxed<S: ?Sized>(Box<S>);
+
+    trait Matches<U: ?Sized> {
+        fn matches(&self, other: &U) -> bool;
+    }
+
+    #[trait_gen(T -> str, [u8])]
+    impl<U> Matches<U> for Boxed<T>
+    where
+        U: ?Sized + AsRef<T>,
+        T: PartialEq,
+    {
+        fn matches(&self, other: &U) -> bool {
+            *self.0 == *other.as_ref()
+        }
+    }
+
+    #[trait_gen(T -> str, [u8])]
+    impl Boxed<T> {
+        fn len_of<V: ?Sized + AsRef<T>>(value: &V) -> usize {
+            <T>::len(value.as_ref())
+        }
+    }
+
+    #[test]
+    fn test() {
+        let text: Boxed<str> = Boxed("abc".into());
+        let bytes: Boxed<[u8]> = Boxed(vec![1, 2].into_boxed_slice());
+        assert!(text.matches("abc"));
+        assert!(text.matches(&"abc".to_string()));
+        assert!(!text.matches("ab"));
+        assert!(bytes.matches(&vec![1, 2]));
+        assert!(bytes.matches(&[1_u8, 2][..]));
+        assert_eq!(Boxed::<str>::len_of(&"abcd".to_string()), 4);
+        assert_eq!(Boxed::<[u8]>::len_of(&vec![0_u8; 3]), 3);
+    }
+}
+
+mod free_fn_turbofish {
+    use conditional_trait_gen::trait_gen;
+    use std::cmp;
+
+    trait Clamp: Sized {
+        fn clamp_to(self, low: Self, high: Self) -> Self;
+        fn zero() -> Self;
+    }
+
+    #[trait_gen(T -> u8, i64, char)]
+    impl Clamp for T {
+        fn clamp_to(self, low: T, high: T) -> T {
+            cmp::min::<T>(std::cmp::max::<T>(self, low), high)
+        }
+
+        fn zero() -> T {
+            core::mem::take::<T>(&mut T::default())
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(20_u8.clamp_to(1, 10), 10);
+        assert_eq!((-5_i64).clamp_to(-2, 2), -2);
+        assert_eq!('m'.clamp_to('a', 'z'), 'm');
+        assert_eq!(u8::zero(), 0);
+        assert_eq!(char::zero(), '\0');
+    }
+}
+
+mod nested_collect_turbofish {
+    use conditional_trait_gen::trait_gen;
+    use std::collections::HashMap;
+
+    trait Collect: Sized {
+        fn by_name(values: &[Self]) -> HashMap<String, Self>;
+        fn some_values(values: &[Self]) -> Vec<Option<Self>>;
+    }
+
+    #[trait_gen(T -> u8, String, Vec<i16>)]
+    impl Collect for T {
+        fn by_name(values: &[T]) -> HashMap<String, T> {
+            values
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(i, v)| (format!("v{i}"), v))
+                .collect::<HashMap<String, T>>()
+        }
+
+        fn some_values(values: &[T]) -> Vec<Option<T>> {
+            values
+                .iter()
+                .cloned()
+                .map(Some)
+                .chain(std::iter::once(None::<T>))
+                .collect::<Vec<Option<T>>>()
+        }
+    }
+
+    #[test]
+    fn test() {
+        let map = u8::by_name(&[3, 4]);
+        assert_eq!(map["v1"], 4);
+        assert_eq!(String::by_name(&["a".to_string()])["v0"], "a");
+        assert_eq!(<Vec<i16>>::by_name(&[vec![-1]])["v0"], [-1]);
+        assert_eq!(u8::some_values(&[1]), [Some(1), None]);
+        assert_eq!(String::some_values(&[]), [None]);
+        assert_eq!(<Vec<i16>>::some_values(&[vec![]]), [Some(vec![]), None]);
+    }
+}
+
+mod qualified_from_iterator {
+    use conditional_trait_gen::trait_gen;
+    use std::collections::BTreeSet;
+
+    trait Gather: Sized {
+        fn gather(items: &[Self]) -> Vec<Self>;
+        fn sorted(items: &[Self]) -> BTreeSet<Self>;
+    }
+
+    #[trait_gen(T -> u32, char, String)]
+    impl Gather for T {
+        fn gather(items: &[T]) -> Vec<T> {
+            <Vec<T> as FromIterator<T>>::from_iter(items.iter().cloned())
+        }
+
+        fn sorted(items: &[T]) -> BTreeSet<T> {
+            <BTreeSet<T> as std::iter::FromIterator<T>>::from_iter(items.to_vec())
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u32::gather(&[1, 2]), [1, 2]);
+        assert_eq!(char::gather(&['a']), ['a']);
+        assert_eq!(String::gather(&["x".to_string()]), ["x"]);
+        assert_eq!(
+            u32::sorted(&[3, 1, 3]).into_iter().collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(
+            char::sorted(&['b', 'a']).into_iter().collect::<String>(),
+            "ab"
+        );
+        assert_eq!(String::sorted(&["a".to_string()]).len(), 1);
+    }
+}
+
+mod raw_slices {
+    use conditional_trait_gen::trait_gen;
+    use std::slice;
+
+    trait RawSlice: Sized {
+        fn first_two(values: &[Self]) -> &[Self];
+        fn zero_in_place(values: &mut [Self]);
+    }
+
+    #[trait_gen(T -> u8, f64, (i16, bool))]
+    impl RawSlice for T {
+        fn first_two(values: &[T]) -> &[T] {
+            assert!(values.len() >= 2);
+            unsafe { slice::from_raw_parts::<T>(values.as_ptr() as *const T, 2) }
+        }
+
+        fn zero_in_place(values: &mut [T]) {
+            let len = values.len();
+            let zeroed =
+                unsafe { std::slice::from_raw_parts_mut::<'_, T>(values.as_mut_ptr(), len) };
+            for value in zeroed {
+                *value = <T>::default();
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::first_two(&[1, 2, 3]), [1, 2]);
+        assert_eq!(f64::first_two(&[0.5, 1.5]), [0.5, 1.5]);
+        assert_eq!(
+            <(i16, bool)>::first_two(&[(1, true), (2, false)]),
+            [(1, true), (2, false)]
+        );
+        let mut v = [3.5_f64, 4.5];
+        f64::zero_in_place(&mut v);
+        assert_eq!(v, [0.0, 0.0]);
+        let mut t = [(7_i16, true)];
+        <(i16, bool)>::zero_in_place(&mut t);
+        assert_eq!(t, [(0, false)]);
+    }
+}
+
+mod maybe_uninit {
+    use conditional_trait_gen::trait_gen;
+    use core::mem::MaybeUninit;
+
+    trait Init: Sized {
+        fn init_with(value: Self) -> Self;
+        fn init_array() -> [Self; 2];
+    }
+
+    #[trait_gen(T -> u32, f64, String)]
+    impl Init for T {
+        fn init_with(value: T) -> T {
+            let mut slot = MaybeUninit::<T>::uninit();
+            slot.write(value);
+            unsafe { slot.assume_init() }
+        }
+
+        fn init_array() -> [T; 2] {
+            let mut slots = [
+                MaybeUninit::<T>::uninit(),
+                core::mem::MaybeUninit::<T>::uninit(),
+            ];
+            for slot in slots.iter_mut() {
+                slot.write(T::default());
+            }
+            unsafe { [slots[0].assume_init_read(), slots[1].assume_init_read()] }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u32::init_with(5), 5);
+        assert_eq!(f64::init_with(0.25), 0.25);
+        assert_eq!(String::init_with("a".to_string()), "a");
+        assert_eq!(u32::init_array(), [0, 0]);
+        assert_eq!(String::init_array(), ["", ""]);
+    }
+}
+
+mod generic_writer {
+    use conditional_trait_gen::trait_gen;
+    use std::fmt::{self, Write};
+
+    trait Dump {
+        fn dump<W: Write>(&self, w: &mut W) -> fmt::Result;
+        fn dump_default<W>(w: W) -> W
+        where
+            W: Write;
+    }
+
+    #[trait_gen(T -> u16, f32, char)]
+    impl Dump for T {
+        fn dump<W: Write>(&self, w: &mut W) -> fmt::Result {
+            let copy: T = *self;
+            write!(w, "${T}={}", copy)
+        }
+
+        fn dump_default<W>(mut w: W) -> W
+        where
+            W: Write,
+        {
+            writeln!(w, "{:?}", T::default()).unwrap();
+            w
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut s = String::new();
+        7_u16.dump(&mut s).unwrap();
+        1.5_f32.dump(&mut s).unwrap();
+        'x'.dump(&mut s).unwrap();
+        assert_eq!(s, "u16=7f32=1.5char=x");
+        assert_eq!(u16::dump_default(String::new()), "0\n");
+        assert_eq!(char::dump_default(String::from(">")), ">'\\0'\n");
+    }
+}
+
+mod ufcs_default {
+    use conditional_trait_gen::trait_gen;
+
+    trait Reset {
+        fn reset(&mut self) -> Self;
+    }
+
+    #[trait_gen(T -> u64, bool, String, (u8, char))]
+    impl Reset for T {
+        fn reset(&mut self) -> Self {
+            let x: T = Default::default();
+            let y: T = std::default::Default::default();
+            assert!(x == y);
+            std::mem::replace(self, x)
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut a = 5_u64;
+        assert_eq!(a.reset(), 5);
+        assert_eq!(a, 0);
+        let mut b = true;
+        assert!(b.reset());
+        assert!(!b);
+        let mut c = "text".to_string();
+        assert_eq!(c.reset(), "text");
+        assert_eq!(c, "");
+        let mut d = (3_u8, 'z');
+        assert_eq!(d.reset(), (3, 'z'));
+        assert_eq!(d, (0, '\0'));
+    }
+}
+
+mod black_box {
+    use conditional_trait_gen::trait_gen;
+    use std::hint::black_box;
+
+    trait Bench {
+        fn bench_add(n: usize) -> Self;
+    }
+
+    #[trait_gen(T -> u32, i64, f64)]
+    impl Bench for T {
+        fn bench_add(n: usize) -> T {
+            let mut acc: T = black_box(T::default());
+            for _ in 0..n {
+                let one: T = black_box(1 as T);
+                acc = black_box(acc + black_box::<T>(one));
+            }
+            acc
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u32::bench_add(3), 3);
+        assert_eq!(i64::bench_add(4), 4);
+        assert_eq!(f64::bench_add(2), 2.0);
+    }
+}
+
+mod addr_of {
+    use conditional_trait_gen::trait_gen;
+    use std::ptr;
+
+    trait RawCopy {
+        fn raw_copy(&self) -> Self;
+        fn raw_max() -> Self;
+    }
+
+    #[trait_gen(T -> u16, i64)]
+    impl RawCopy for T {
+        fn raw_copy(&self) -> T {
+            let p = ptr::addr_of!(*(self as *const T));
+            unsafe { p.read() }
+        }
+
+        fn raw_max() -> T {
+            let mut value: T = 0;
+            let p = std::ptr::addr_of_mut!(*(&mut value as *mut T));
+            unsafe { p.write(T::MAX) };
+            value
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(12_u16.raw_copy(), 12);
+        assert_eq!((-3_i64).raw_copy(), -3);
+        assert_eq!(u16::raw_max(), u16::MAX);
+        assert_eq!(i64::raw_max(), i64::MAX);
+    }
+}
+
+mod matches_range_bound {
+    use conditional_trait_gen::trait_gen;
+
+    trait Bounded {
+        fn is_small(n: u32) -> bool;
+        fn is_large(n: u32) -> bool;
+    }
+
+    struct Byte;
+    struct Percent;
+
+    impl Byte {
+        const LIMIT: u32 = 255;
+    }
+
+    impl Percent {
+        const LIMIT: u32 = 100;
+    }
+
+    // the constant is reached through the type, since only types can be substituted
+    #[trait_gen(T -> Byte, Percent)]
+    impl Bounded for T {
+        fn is_small(n: u32) -> bool {
+            matches!(n, 0..=T::LIMIT)
+        }
+
+        fn is_large(n: u32) -> bool {
+            matches!(n, x @ <T>::LIMIT..=u32::MAX if x != T::LIMIT)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert!(Byte::is_small(200));
+        assert!(!Percent::is_small(200));
+        assert!(Percent::is_large(101));
+        assert!(!Percent::is_large(100));
+        assert!(!Byte::is_large(101));
+    }
+}
+
+mod generic_param_default {
+    use conditional_trait_gen::trait_gen;
+    use std::marker::PhantomData;
+
+    trait Tag {
+        const TAG: &'static str;
+    }
+
+    impl Tag for u8 {
+        const TAG: &'static str = "byte";
+    }
+
+    impl Tag for f32 {
+        const TAG: &'static str = "float";
+    }
+
+    impl Tag for char {
+        const TAG: &'static str = "char";
+    }
+
+    // the default of `U` is substituted along with the field type
+    #[trait_gen(T -> u8, f32; newtype)]
+    struct Tagged<U: Tag = T>(T, PhantomData<U>);
+
+    #[trait_gen(T -> U8Tagged, F32Tagged)]
+    impl<U: Tag> T<U> {
+        fn tag(&self) -> &'static str {
+            U::TAG
+        }
+    }
+
+    #[test]
+    fn test() {
+        let a: U8Tagged = U8Tagged(1, PhantomData);
+        let b: F32Tagged = F32Tagged(1.5, PhantomData);
+        let c = U8Tagged::<char>(2, PhantomData);
+        assert_eq!((a.tag(), a.0), ("byte", 1));
+        assert_eq!((b.tag(), b.0), ("float", 1.5));
+        assert_eq!((c.tag(), c.0), ("char", 2));
+    }
+}
+
+mod nested_item_attributes {
+    use conditional_trait_gen::trait_gen;
+
+    trait Double {
+        fn double(&self) -> Self;
+    }
+
+    // the lints are denied by the quality checks, so the attributes must survive each copy
+    #[trait_gen(T -> u32, f64)]
+    impl Double for T {
+        fn double(&self) -> T {
+            #[allow(non_upper_case_globals)]
+            const factor: T = 2 as T;
+
+            #[inline(always)]
+            fn scale(x: T) -> T {
+                x * factor
+            }
+
+            #[allow(dead_code, unused_variables)]
+            fn unused(x: T) -> T {
+                let ignored: T = x;
+                x
+            }
+
+            #[cfg(any())]
+            fn removed() -> T {
+                compile_error!("the cfg attribute of nested items must be kept")
+            }
+
+            scale(*self)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(21_u32.double(), 42);
+        assert_eq!(1.25_f64.double(), 2.5);
+    }
+}
+
+mod debug_struct_name {
+    use conditional_trait_gen::trait_gen;
+    use std::fmt;
+
+    struct Meter(f64);
+    struct Foot(f64);
+
+    #[trait_gen(T -> Meter, Foot)]
+    impl fmt::Debug for T {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let value: &f64 = &self.0;
+            f.debug_struct("${T}").field("value", value).finish()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(format!("{:?}", Meter(1.5)), "Meter { value: 1.5 }");
+        assert_eq!(format!("{:?}", Foot(2.0)), "Foot { value: 2.0 }");
+    }
+}
+
+mod range_types {
+    use conditional_trait_gen::trait_gen;
+    use std::ops::{Range, RangeInclusive};
+
+    trait Span: Sized {
+        fn span_to(self, end: Self) -> Range<Self>;
+        fn clamp_to(self, range: &RangeInclusive<Self>) -> Self;
+    }
+
+    #[trait_gen(T -> u8, i32, f64)]
+    impl Span for T {
+        fn span_to(self, end: T) -> Range<T> {
+            self..end
+        }
+
+        fn clamp_to(self, range: &RangeInclusive<T>) -> T {
+            let bounds: core::ops::RangeInclusive<T> = range.clone();
+            let (start, end): (T, T) = bounds.into_inner();
+            if self < start {
+                start
+            } else if self > end {
+                end
+            } else {
+                self
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(2_u8.span_to(5), 2..5);
+        assert_eq!((-1_i32).span_to(1).len(), 2);
+        assert_eq!(0.5_f64.span_to(1.5), 0.5..1.5);
+        assert_eq!(9_u8.clamp_to(&(1..=4)), 4);
+        assert_eq!((-7_i32).clamp_to(&(-5..=5)), -5);
+        assert_eq!(0.25_f64.clamp_to(&(0.0..=1.0)), 0.25);
+    }
+}
+
+mod cow_types {
+    use conditional_trait_gen::trait_gen;
+    use std::borrow::Cow;
+
+    trait Doubled: Clone {
+        fn doubled(value: Cow<'_, Self>) -> Cow<'_, Self>;
+    }
+
+    #[trait_gen(T -> u32, i64)]
+    impl Doubled for T {
+        fn doubled(value: Cow<'_, T>) -> Cow<'_, T> {
+            let borrowed: &T = value.as_ref();
+            if *borrowed == T::default() {
+                value
+            } else {
+                let owned: T = *borrowed * 2;
+                Cow::Owned(owned)
+            }
+        }
+    }
+
+    trait Label {
+        fn label<'a>(&self, prefix: Cow<'a, str>) -> Cow<'a, str>;
+    }
+
+    #[trait_gen(T -> u8, bool)]
+    impl Label for T {
+        fn label<'a>(&self, prefix: Cow<'a, str>) -> Cow<'a, str> {
+            let value: &T = self;
+            Cow::Owned(format!("{prefix}{value}"))
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert!(matches!(u32::doubled(Cow::Borrowed(&0)), Cow::Borrowed(0)));
+        assert_eq!(u32::doubled(Cow::Owned(21)).into_owned(), 42);
+        assert_eq!(i64::doubled(Cow::Borrowed(&-4)).into_owned(), -8);
+        assert_eq!(7_u8.label(Cow::Borrowed("n=")), "n=7");
+        assert_eq!(true.label(Cow::Owned("b=".to_string())), "b=true");
+    }
+}
+
+mod wrapping_types {
+    use conditional_trait_gen::trait_gen;
+    use std::num::{Saturating, Wrapping};
+
+    trait Step: Sized {
+        fn wrapping_step(value: Wrapping<Self>) -> Wrapping<Self>;
+        fn saturating_step(value: Saturating<Self>) -> Saturating<Self>;
+    }
+
+    #[trait_gen(T -> u8, i16, u32)]
+    impl Step for T {
+        fn wrapping_step(value: Wrapping<T>) -> Wrapping<T> {
+            let step: Wrapping<T> = Wrapping(T::MAX);
+            value + step
+        }
+
+        fn saturating_step(value: Saturating<T>) -> Saturating<T> {
+            let step: std::num::Saturating<T> = Saturating(T::MAX);
+            value + step
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::wrapping_step(Wrapping(1)), Wrapping(0));
+        assert_eq!(i16::wrapping_step(Wrapping(1)), Wrapping(i16::MIN));
+        assert_eq!(u32::wrapping_step(Wrapping(2)), Wrapping(1));
+        assert_eq!(u8::saturating_step(Saturating(1)), Saturating(u8::MAX));
+        assert_eq!(
+            i16::saturating_step(Saturating(-1)),
+            Saturating(i16::MAX - 1)
+        );
+        assert_eq!(u32::saturating_step(Saturating(0)), Saturating(u32::MAX));
+    }
+}
+
+mod const_expr_bounds {
+    use conditional_trait_gen::trait_gen;
+
+    trait ToBytes {
+        fn to_byte_vec(&self) -> Vec<u8>;
+    }
+
+    // once substituted, the array length doesn't depend on any generic parameter, so the
+    // bound is accepted by the stable compiler, unlike its generic version
+    #[trait_gen(T -> u16, u32, u64)]
+    impl ToBytes for T {
+        fn to_byte_vec(&self) -> Vec<u8>
+        where
+            [(); T::BITS as usize / 8]: Sized,
+        {
+            let bytes: [u8; T::BITS as usize / 8] = self.to_le_bytes();
+            bytes.to_vec()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(0x0102_u16.to_byte_vec(), [2, 1]);
+        assert_eq!(1_u32.to_byte_vec().len(), 4);
+        assert_eq!(1_u64.to_byte_vec().len(), 8);
+    }
+}
+
+mod result_impl_trait {
+    use conditional_trait_gen::trait_gen;
+    use std::marker::PhantomData;
+    use std::num::ParseIntError;
+
+    struct Parser<U>(PhantomData<U>);
+
+    // the return-position `impl Trait` must be in a type implementation, since the MSRV is older
+    // than its support in traits
+    #[trait_gen(T -> u8, i32, u64)]
+    impl Parser<T> {
+        fn parse(text: &str) -> Result<impl Iterator<Item = T>, ParseIntError> {
+            let values = text
+                .split(',')
+                .map(|item| item.trim().parse::<T>())
+                .collect::<Result<Vec<T>, _>>()?;
+            Ok(values.into_iter())
+        }
+
+        fn sum(text: &str) -> Result<T, ParseIntError> {
+            Ok(Self::parse(text)?.sum::<T>())
+        }
+    }
+
+    #[test]
+    fn test() {
+        let values = Parser::<u8>::parse("1, 2, 3").unwrap();
+        assert_eq!(values.collect::<Vec<u8>>(), [1, 2, 3]);
+        assert!(Parser::<u8>::parse("1, 256").is_err());
+        assert_eq!(Parser::<u8>::sum("7").unwrap(), 7);
+        assert_eq!(Parser::<i32>::sum("-5, 2").unwrap(), -3);
+        assert_eq!(Parser::<u64>::sum("4294967296, 1").unwrap(), 4294967297);
+        assert!(Parser::<u64>::sum("1, x").is_err());
+    }
+}
+
+mod btree_map_generic_value {
+    use conditional_trait_gen::trait_gen;
+    use std::collections::BTreeMap;
+
+    trait GroupByLen<V> {
+        fn index_by_len(values: Vec<V>) -> BTreeMap<Self, Vec<V>>
+        where
+            Self: Sized;
+    }
+
+    // only `T` is substituted in the map, `V` remains the generic parameter of the implementation
+    #[trait_gen(T -> u8, usize, i64)]
+    impl<V: AsRef<str>> GroupByLen<V> for T {
+        fn index_by_len(values: Vec<V>) -> BTreeMap<T, Vec<V>> {
+            let mut map: BTreeMap<T, Vec<V>> = BTreeMap::new();
+            for value in values {
+                let len = value.as_ref().len() as T;
+                map.entry(len).or_default().push(value);
+            }
+            map
+        }
+    }
+
+    #[test]
+    fn test() {
+        let map = u8::index_by_len(vec!["a", "bc", "d"]);
+        assert_eq!(map.get(&1), Some(&vec!["a", "d"]));
+        assert_eq!(map.get(&2_u8), Some(&vec!["bc"]));
+        let map = usize::index_by_len(vec![String::from("xyz")]);
+        assert_eq!(map.keys().copied().collect::<Vec<usize>>(), [3]);
+        let map = i64::index_by_len(Vec::<&str>::new());
+        assert!(map.is_empty());
+    }
+}
+
+mod index_traits {
+    use conditional_trait_gen::trait_gen;
+    use std::ops::{Index, IndexMut};
+
+    struct Registers([u32; 4]);
+
+    #[trait_gen(T -> u8, u16, usize)]
+    impl Index<T> for Registers {
+        type Output = u32;
+
+        fn index(&self, index: T) -> &u32 {
+            &self.0[index as usize]
+        }
+    }
+
+    #[trait_gen(T -> u8, u16, usize)]
+    impl IndexMut<T> for Registers {
+        fn index_mut(&mut self, index: T) -> &mut <Self as Index<T>>::Output {
+            &mut self.0[index as usize]
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut regs = Registers([0; 4]);
+        regs[1_u8] = 10;
+        regs[2_u16] += 20;
+        regs[3_usize] = regs[1_u8] + regs[2_u16];
+        assert_eq!(regs[3_u8], 30);
+        assert_eq!(regs.0, [0, 10, 20, 30]);
+    }
+}
+
+mod multi_bound_trait_object {
+    use conditional_trait_gen::trait_gen;
+
+    trait Converter<U> {
+        fn convert(&self, value: U) -> String;
+    }
+
+    struct Hex;
+
+    #[trait_gen(T -> u8, u16, u32)]
+    impl Converter<T> for Hex {
+        fn convert(&self, value: T) -> String {
+            format!("{:#x}", value)
+        }
+    }
+
+    trait Provider {
+        fn converter() -> Box<dyn Converter<Self> + Send + Sync>;
+    }
+
+    // only the argument of the first bound is substituted, the marker bounds are kept
+    #[trait_gen(T -> u8, u16, u32)]
+    impl Provider for T {
+        fn converter() -> Box<dyn Converter<T> + Send + Sync> {
+            let converter: Box<dyn Converter<T> + Send + Sync + 'static> = Box::new(Hex);
+            converter
+        }
+    }
+
+    fn is_send_sync<X: Send + Sync + ?Sized>(_: &X) -> bool {
+        true
+    }
+
+    #[test]
+    fn test() {
+        let converter = u8::converter();
+        assert!(is_send_sync(&converter));
+        assert_eq!(converter.convert(255), "0xff");
+        assert_eq!(u16::converter().convert(0x1234), "0x1234");
+        assert_eq!(u32::converter().convert(1 << 20), "0x100000");
+    }
+}
+
+mod turbofish_try_chain {
+    use conditional_trait_gen::trait_gen;
+    use std::error::Error;
+
+    trait ParseWide: Sized {
+        fn parse_wide(text: &str) -> Result<(Self, i128), Box<dyn Error>>;
+    }
+
+    #[trait_gen(T -> u8, i16, u32)]
+    impl ParseWide for T {
+        fn parse_wide(text: &str) -> Result<(T, i128), Box<dyn Error>> {
+            let value = text.trim().parse::<T>()?;
+            let wide: i128 = text.trim().parse::<T>()?.into();
+            let sum = text
+                .split('+')
+                .map(|item| item.trim().parse::<T>())
+                .collect::<Result<Vec<T>, _>>()?
+                .into_iter()
+                .map(i128::from)
+                .sum::<i128>();
+            Ok((value, wide + sum))
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::parse_wide("200").unwrap(), (200, 400));
+        assert_eq!(i16::parse_wide(" -7 ").unwrap(), (-7, -14));
+        assert_eq!(u32::parse_wide("70000").unwrap(), (70000, 140000));
+        assert!(u8::parse_wide("300").is_err());
+        assert!(i16::parse_wide("1 + 2").is_err());
+    }
+}
+
+mod mem_replace_take {
+    use conditional_trait_gen::trait_gen;
+    use std::mem;
+
+    struct Slot<U> {
+        value: U,
+        history: Vec<U>,
+    }
+
+    trait Swap<U> {
+        fn swap_in(&mut self, new: U) -> usize;
+        fn drain(&mut self) -> (U, Vec<U>);
+    }
+
+    #[trait_gen(T -> u8, String, Vec<i32>)]
+    impl Swap<T> for Slot<T> {
+        fn swap_in(&mut self, new: T) -> usize {
+            let old = mem::replace::<T>(&mut self.value, new);
+            self.history.push(old);
+            self.history.len()
+        }
+
+        fn drain(&mut self) -> (T, Vec<T>) {
+            (
+                std::mem::take::<T>(&mut self.value),
+                core::mem::take::<Vec<T>>(&mut self.history),
+            )
+        }
+    }
+
+    #[test]
+    fn test() {
+        let mut slot = Slot {
+            value: 1_u8,
+            history: vec![],
+        };
+        assert_eq!(slot.swap_in(2), 1);
+        assert_eq!(slot.swap_in(3), 2);
+        assert_eq!(slot.drain(), (3, vec![1, 2]));
+        assert_eq!((slot.value, slot.history.len()), (0, 0));
+        let mut slot = Slot {
+            value: "a".to_string(),
+            history: vec![],
+        };
+        slot.swap_in("b".to_string());
+        assert_eq!(slot.drain(), ("b".to_string(), vec!["a".to_string()]));
+        let mut slot = Slot {
+            value: vec![1],
+            history: vec![],
+        };
+        slot.swap_in(vec![2, 3]);
+        assert_eq!(slot.drain(), (vec![2, 3], vec![vec![1]]));
+        assert!(slot.value.is_empty());
+    }
+}
+
+mod iter_successors {
+    use conditional_trait_gen::trait_gen;
+
+    trait Powers: Sized {
+        fn powers_of_two() -> Vec<Self>;
+    }
+
+    // the seed and the closure both use the substituted type
+    #[trait_gen(T -> u8, i16, u32)]
+    impl Powers for T {
+        fn powers_of_two() -> Vec<T> {
+            core::iter::successors::<T, _>(Some(T::default() + 1), |x: &T| x.checked_mul(2 as T))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::powers_of_two(), [1, 2, 4, 8, 16, 32, 64, 128]);
+        assert_eq!(i16::powers_of_two().len(), 15);
+        assert_eq!(u32::powers_of_two().last(), Some(&(1 << 31)));
+    }
+}
+
+mod refcell_vec_field {
+    use conditional_trait_gen::trait_gen;
+    use std::cell::RefCell;
+
+    // the `Vec<T>` nested in the `RefCell` of the generated structures is substituted
+    #[trait_gen(T -> u8, String, Option<i32>; newtype)]
+    #[derive(Default)]
+    struct Log(RefCell<Vec<T>>);
+
+    #[trait_gen(L -> U8Log, StringLog)]
+    impl L {
+        fn record(&self, count: usize) -> usize {
+            let mut entries = self.0.borrow_mut();
+            let len = entries.len() + count;
+            entries.resize_with(len, Default::default);
+            len
+        }
+    }
+
+    #[test]
+    fn test() {
+        let log = U8Log::default();
+        assert_eq!(log.record(2), 2);
+        assert_eq!(log.record(1), 3);
+        assert_eq!(*log.0.borrow(), [0, 0, 0]);
+        let log = StringLog(RefCell::new(vec!["a".to_string()]));
+        assert_eq!(log.record(1), 2);
+        let log = OptionLog(RefCell::new(Vec::<Option<i32>>::new()));
+        log.0.borrow_mut().push(Some(1));
+        assert_eq!(log.0.into_inner(), [Some(1)]);
+    }
+}
+
+mod hrtb_reference_bound {
+    use conditional_trait_gen::trait_gen;
+
+    trait Total {
+        fn total(&self) -> i64;
+    }
+
+    // the binder and the reference are kept, only `T` is substituted
+    #[trait_gen(T -> Vec<i8>, [i8; 3], std::collections::VecDeque<i8>)]
+    impl Total for T
+    where
+        for<'a> &'a T: IntoIterator<Item = &'a i8>,
+    {
+        fn total(&self) -> i64 {
+            let mut sum = 0;
+            for value in self {
+                sum += *value as i64;
+            }
+            sum
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(vec![1_i8, 2, 3].total(), 6);
+        assert_eq!([-1_i8, -2, 10].total(), 7);
+        let deque: std::collections::VecDeque<i8> = vec![100, 100].into();
+        assert_eq!(deque.total(), 200);
+    }
+}
+
+mod deref_target_binding {
+    use conditional_trait_gen::trait_gen;
+    use std::ops::Deref;
+    use std::rc::Rc;
+
+    trait Shared {
+        fn shared(self) -> Box<dyn Deref<Target = Self>>;
+    }
+
+    // `T` is substituted in the associated type binding of the trait object
+    #[trait_gen(T -> u8, String, Vec<f32>)]
+    impl Shared for T {
+        fn shared(self) -> Box<dyn Deref<Target = T>> {
+            let target: Box<dyn Deref<Target = T>> = Box::new(Rc::new(self));
+            target
+        }
+    }
+
+    fn peek(value: &dyn Deref<Target = String>) -> usize {
+        value.len()
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(**5_u8.shared(), 5);
+        let text = "abc".to_string().shared();
+        assert_eq!(peek(&*text), 3);
+        assert_eq!(vec![1.5_f32].shared()[0], 1.5);
+    }
+}
+
+mod gat_iterator {
+    use conditional_trait_gen::trait_gen;
+
+    // GATs are more recent than the MSRV of the crate
+    trait Values {
+        type Item;
+        type Iter<'a>: Iterator<Item = Self::Item>
+        where
+            Self: 'a;
+
+        fn values(&self) -> Self::Iter<'_>;
+    }
+
+    // `T` is substituted in the generic associated type and its where clause
+    #[trait_gen(T -> u8, i64, f32)]
+    impl Values for Vec<T> {
+        type Item = T;
+        type Iter<'a>
+            = std::iter::Copied<std::slice::Iter<'a, T>>
+        where
+            Vec<T>: 'a;
+
+        fn values(&self) -> Self::Iter<'_> {
+            self.iter().copied()
+        }
+    }
+
+    fn total<V: Values>(container: &V) -> f64
+    where
+        V::Item: Into<f64>,
+    {
+        container.values().map(|value| value.into()).sum()
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(total(&vec![1_u8, 2, 3]), 6.0);
+        assert_eq!(total(&vec![0.5_f32, 0.25]), 0.75);
+        assert_eq!(vec![-4_i64, 9].values().max(), Some(9));
+    }
+}
+
+mod future_output_bound {
+    use conditional_trait_gen::trait_gen;
+    use std::future::Future;
+    use std::pin::Pin;
+
+    trait Accumulate: Sized {
+        fn accumulate<F>(self, other: F) -> Pin<Box<dyn Future<Output = Self>>>
+        where
+            F: Future<Output = Self> + 'static;
+    }
+
+    // `T` is substituted in the `Output` binding of the `Future` bound
+    #[trait_gen(T -> u32, i64, f64)]
+    impl Accumulate for T {
+        fn accumulate<F>(self, other: F) -> Pin<Box<dyn Future<Output = T>>>
+        where
+            F: Future<Output = T> + 'static,
+        {
+            Box::pin(async move { self + other.await })
+        }
+    }
+
+    async fn ready<V>(value: V) -> V {
+        value
+    }
+
+    #[tokio::test]
+    async fn test() {
+        assert_eq!(1_u32.accumulate(ready(2)).await, 3);
+        assert_eq!((-5_i64).accumulate(ready(2)).await, -3);
+        let nested = 0.5_f64.accumulate(0.25_f64.accumulate(ready(0.125)));
+        assert_eq!(nested.await, 0.875);
+    }
+}
+
+mod add_explicit_rhs {
+    use conditional_trait_gen::trait_gen;
+    use std::ops::Add;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Meter(f64);
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Foot(f64);
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Mile(f64);
+
+    // `T` is substituted in the explicit `Rhs` argument, which is also its default value
+    #[trait_gen(T -> Meter, Foot, Mile)]
+    impl Add<T> for T {
+        type Output = T;
+
+        fn add(self, rhs: T) -> Self::Output {
+            T(self.0 + rhs.0)
+        }
+    }
+
+    // relies on the default `Rhs = Self`
+    fn double<A: Add<Output = A> + Copy>(value: A) -> A {
+        value + value
+    }
+
+    fn same_output<A: Add<A, Output = A>>(a: A, b: A) -> A {
+        a + b
+    }
+
+    #[test]
+    fn test() {
+        let sum: <Meter as Add>::Output = Meter(1.0) + Meter(2.5);
+        assert_eq!(sum, Meter(3.5));
+        assert_eq!(double(Foot(1.5)), Foot(3.0));
+        assert_eq!(same_output(Mile(0.25), Mile(0.5)), Mile(0.75));
+    }
+}
+
+mod phantom_pinned_marker {
+    use conditional_trait_gen::trait_gen;
+    use std::marker::{PhantomData, PhantomPinned};
+    use std::pin::Pin;
+
+    // `T` is substituted in the tuple argument of the `PhantomData` marker
+    #[trait_gen(T -> u8, String, Vec<i32>; newtype)]
+    struct Pinned {
+        value: T,
+        _marker: PhantomData<(T, PhantomPinned)>,
+    }
+
+    #[trait_gen(T -> u8, String, Vec<i32>; zip)]
+    #[trait_gen(P -> U8Pinned, StringPinned, VecPinned)]
+    impl P {
+        fn pinned(value: T) -> Pin<Box<Self>> {
+            Box::pin(P {
+                value,
+                _marker: PhantomData,
+            })
+        }
+    }
+
+    #[test]
+    fn test() {
+        let pinned = U8Pinned::pinned(7);
+        assert_eq!(pinned.value, 7);
+        let marker: &PhantomData<(String, PhantomPinned)> =
+            &StringPinned::pinned("a".into())._marker;
+        assert_eq!(std::mem::size_of_val(marker), 0);
+        assert_eq!(VecPinned::pinned(vec![1, 2]).as_ref().value, [1, 2]);
+    }
+}
+
+mod ord_typed_comparison {
+    use conditional_trait_gen::trait_gen;
+    use std::cmp::Ordering;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Priority(u8);
+    #[derive(Debug, PartialEq, Eq)]
+    struct Version(u32, u32);
+    #[derive(Debug, PartialEq, Eq)]
+    struct Label(String);
+
+    // the `other: &T` parameters are substituted by each type
+    #[trait_gen(T -> Priority, Version, Label)]
+    impl Ord for T {
+        fn cmp(&self, other: &T) -> Ordering {
+            // reversed, to make sure the generated implementation is used
+            other.key().cmp(&self.key())
+        }
+    }
+
+    #[trait_gen(T -> Priority, Version, Label)]
+    impl PartialOrd for T {
+        fn partial_cmp(&self, other: &T) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    trait Key {
+        type Key: Ord;
+        fn key(&self) -> Self::Key;
+    }
+
+    impl Key for Priority {
+        type Key = u8;
+        fn key(&self) -> u8 {
+            self.0
+        }
+    }
+
+    impl Key for Version {
+        type Key = (u32, u32);
+        fn key(&self) -> (u32, u32) {
+            (self.0, self.1)
+        }
+    }
+
+    impl Key for Label {
+        type Key = String;
+        fn key(&self) -> String {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Priority(1).cmp(&Priority(2)), Ordering::Greater);
+        assert!(Version(1, 2) > Version(1, 3));
+        assert_eq!(Version(2, 0).cmp(&Version(2, 0)), Ordering::Equal);
+        let mut labels = [Label("a".into()), Label("c".into()), Label("b".into())];
+        labels.sort();
+        assert_eq!(
+            labels.iter().map(|l| l.0.as_str()).collect::<String>(),
+            "cba"
+        );
+        assert_eq!(Priority(3).max(Priority(9)), Priority(3));
+    }
+}
+
+mod range_bounds_generic {
+    use conditional_trait_gen::trait_gen;
+    use std::ops::{Bound, RangeBounds};
+
+    trait Clamp: Sized {
+        fn clamp_to<R: RangeBounds<Self>>(self, range: R) -> Self;
+    }
+
+    // `T` is substituted in the bound of the generic `R`, which stays generic
+    #[trait_gen(T -> u8, i32, u64)]
+    impl Clamp for T {
+        fn clamp_to<R: RangeBounds<T>>(self, range: R) -> T {
+            let low = match range.start_bound() {
+                Bound::Included(&low) => low,
+                Bound::Excluded(&low) => low + 1,
+                Bound::Unbounded => T::MIN,
+            };
+            let high = match range.end_bound() {
+                Bound::Included(&high) => high,
+                Bound::Excluded(&high) => high - 1,
+                Bound::Unbounded => T::MAX,
+            };
+            self.max(low).min(high)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(200_u8.clamp_to(10..100), 99);
+        assert_eq!((-5_i32).clamp_to(0..=10), 0);
+        assert_eq!(7_u64.clamp_to(..), 7);
+        assert_eq!(7_u64.clamp_to((Bound::Excluded(7), Bound::Unbounded)), 8);
+    }
+}
+
+mod named_adaptor_return {
+    use conditional_trait_gen::trait_gen;
+    use std::iter::Map;
+    use std::slice::Iter;
+
+    trait Doubled: Sized {
+        fn doubled(values: &[Self]) -> Map<Iter<'_, Self>, fn(&Self) -> Self>;
+    }
+
+    // `T` is substituted in the arguments of the named adaptor types and of the function pointer
+    #[trait_gen(T -> u16, i32, f64)]
+    impl Doubled for T {
+        fn doubled(values: &[T]) -> Map<Iter<'_, T>, fn(&T) -> T> {
+            values.iter().map(|value| value + value)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u16::doubled(&[1, 2]).collect::<Vec<_>>(), [2, 4]);
+        let doubled: Map<Iter<'_, i32>, fn(&i32) -> i32> = i32::doubled(&[-3]);
+        assert_eq!(doubled.sum::<i32>(), -6);
+        assert_eq!(f64::doubled(&[0.25]).next(), Some(0.5));
+    }
+}
+
+mod ffi_void_pointer {
+    use conditional_trait_gen::trait_gen;
+    use std::ffi::c_void;
+
+    trait Opaque: Sized {
+        fn into_raw(self) -> *mut c_void;
+        /// # Safety
+        /// `ptr` must come from `into_raw` of the same type.
+        unsafe fn from_raw(ptr: *mut c_void) -> Self;
+    }
+
+    // `T` is substituted in the `*mut T` casts, while `c_void` is kept
+    #[trait_gen(T -> u32, String, Vec<f64>)]
+    impl Opaque for T {
+        fn into_raw(self) -> *mut c_void {
+            Box::into_raw(Box::new(self)) as *mut T as *mut c_void
+        }
+
+        unsafe fn from_raw(ptr: *mut c_void) -> T {
+            *Box::from_raw(ptr as *mut T)
+        }
+    }
+
+    /// Simulates a C callback receiving an opaque pointer.
+    extern "C" fn callback(data: *mut c_void) -> usize {
+        let text = unsafe { String::from_raw(data) };
+        text.len()
+    }
+
+    #[test]
+    fn test() {
+        let ptr = 7_u32.into_raw();
+        assert_eq!(unsafe { u32::from_raw(ptr) }, 7);
+        assert_eq!(callback("abc".to_string().into_raw()), 3);
+        let ptr = vec![0.5, 1.5].into_raw();
+        assert_eq!(unsafe { <Vec<f64>>::from_raw(ptr) }, [0.5, 1.5]);
+    }
+}
+
+mod hash_set_hasher_generic {
+    use conditional_trait_gen::trait_gen;
+    use std::collections::hash_map::RandomState;
+    use std::collections::HashSet;
+    use std::hash::{BuildHasher, BuildHasherDefault, Hasher};
+
+    trait Distinct: Sized {
+        fn distinct<S: BuildHasher + Default>(values: &[Self]) -> HashSet<Self, S>;
+    }
+
+    // `T` is substituted in the set, while the hasher `S` stays generic
+    #[trait_gen(T -> u8, char, String)]
+    impl Distinct for T {
+        fn distinct<S: BuildHasher + Default>(values: &[T]) -> HashSet<T, S> {
+            let mut set = HashSet::<T, S>::with_hasher(S::default());
+            set.extend(values.iter().cloned());
+            set
+        }
+    }
+
+    /// Weak hasher, only meant to check that the hasher is generic.
+    #[derive(Default)]
+    struct Sum(u64);
+
+    impl Hasher for Sum {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+        fn write(&mut self, bytes: &[u8]) {
+            self.0 = bytes
+                .iter()
+                .fold(self.0, |sum, &b| sum.wrapping_add(b as u64));
+        }
+    }
+
+    #[test]
+    fn test() {
+        let set: HashSet<u8, RandomState> = u8::distinct(&[1, 2, 2, 3]);
+        assert_eq!(set.len(), 3);
+        let set: HashSet<char, BuildHasherDefault<Sum>> = char::distinct(&['a', 'b', 'a']);
+        assert!(set.contains(&'b') && set.len() == 2);
+        let words = ["x".to_string(), "x".to_string()];
+        assert_eq!(String::distinct::<RandomState>(&words).len(), 1);
+    }
+}
+
+mod control_flow_return {
+    use conditional_trait_gen::trait_gen;
+    use std::ops::ControlFlow;
+
+    trait Accumulate: Sized {
+        fn accumulate(self, value: Self, limit: Self) -> ControlFlow<(), Self>;
+    }
+
+    // `T` is substituted in the continue type, the break type `()` is kept
+    #[trait_gen(T -> u8, i32, f64)]
+    impl Accumulate for T {
+        fn accumulate(self, value: T, limit: T) -> ControlFlow<(), T> {
+            let sum: T = self + value;
+            if sum > limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(sum)
+            }
+        }
+    }
+
+    fn sum_until<V: Accumulate + Copy>(values: &[V], zero: V, limit: V) -> Option<V> {
+        match values
+            .iter()
+            .try_fold(zero, |sum, &value| sum.accumulate(value, limit))
+        {
+            ControlFlow::Continue(sum) => Some(sum),
+            ControlFlow::Break(()) => None,
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(sum_until(&[1_u8, 2, 3], 0, 10), Some(6));
+        assert_eq!(sum_until(&[5_i32, 6], 0, 10), None);
+        assert_eq!(0.5_f64.accumulate(0.25, 1.0), ControlFlow::Continue(0.75));
+    }
+}
+
+mod arc_dyn_generic_trait {
+    use conditional_trait_gen::trait_gen;
+    use std::sync::Arc;
+
+    trait Converter<V> {
+        fn convert(&self, value: V) -> String;
+    }
+
+    struct Hex;
+
+    #[trait_gen(T -> u8, u32, i64)]
+    impl Converter<T> for Hex {
+        fn convert(&self, value: T) -> String {
+            format!("{:x}", value)
+        }
+    }
+
+    trait Shared: Sized {
+        fn converter() -> Arc<dyn Converter<Self> + Send + Sync>;
+    }
+
+    // `T` is substituted in the generic argument of the shared trait object
+    #[trait_gen(T -> u8, u32, i64)]
+    impl Shared for T {
+        fn converter() -> Arc<dyn Converter<T> + Send + Sync> {
+            Arc::new(Hex)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::converter().convert(255), "ff");
+        let shared = u32::converter();
+        let other = Arc::clone(&shared);
+        assert_eq!(other.convert(4096), "1000");
+        assert_eq!(Arc::strong_count(&shared), 2);
+        assert_eq!(i64::converter().convert(-1), "ffffffffffffffff");
+    }
+}
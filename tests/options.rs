@@ -0,0 +1,2731 @@
+// Copyright 2023 Redglyph
+//
+// Integration tests of the attribute options.
+
+// The generated code is identical for all the types, so some casts are only required by a
+// subset of them:
+#![allow(clippy::unnecessary_cast)]
+
+// =============================================================================
+// Options are given after the types, separated by a semicolon:
+//
+//     #[trait_gen(T -> Meter, Foot, Mile; option1, option2 = value)]
+// -----------------------------------------------------------------------------
+
+mod dispatch_macro {
+    use conditional_trait_gen::trait_gen;
+
+    struct Meter(f64);
+
+    trait Name {
+        fn name() -> &'static str;
+    }
+
+    #[trait_gen(T -> u8, u16, Meter; dispatch_macro = by_name)]
+    impl Name for T {
+        fn name() -> &'static str {
+            "${T}"
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(<by_name!("u8")>::name(), "u8");
+        assert_eq!(<by_name!("u16")>::name(), "u16");
+        assert_eq!(<by_name!("Meter")>::name(), "Meter");
+        let x: by_name!("u16") = 1000;
+        assert_eq!(x, 1000_u16);
+        let m: by_name!("Meter") = Meter(2.0);
+        assert_eq!(m.0, 2.0);
+    }
+}
+
+// =============================================================================
+// Attributes attached to the types, which only apply to the code generated for them:
+//
+//     #[trait_gen(T -> Meter, Foot #[cfg(feature = "foot")], Mile)]
+// -----------------------------------------------------------------------------
+
+mod type_cfg {
+    use conditional_trait_gen::trait_gen;
+
+    trait Backend {
+        fn backend() -> &'static str;
+    }
+
+    // 'in_format' stands for any feature providing a better implementation of u16,
+    // both builds are tested (with and without --all-features):
+    #[trait_gen(T -> u8, u16 #[cfg(not(feature = "in_format"))], u32)]
+    impl Backend for T {
+        fn backend() -> &'static str {
+            "generic ${T}"
+        }
+    }
+
+    #[cfg(feature = "in_format")]
+    impl Backend for u16 {
+        fn backend() -> &'static str {
+            "specific u16"
+        }
+    }
+
+    // legacy format
+    #[trait_gen(i8 #[cfg(not(feature = "in_format"))], i16)]
+    impl Backend for i8 {
+        fn backend() -> &'static str {
+            "generic"
+        }
+    }
+
+    #[cfg(feature = "in_format")]
+    impl Backend for i8 {
+        fn backend() -> &'static str {
+            "specific i8"
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::backend(), "generic u8");
+        assert_eq!(u32::backend(), "generic u32");
+        assert_eq!(i16::backend(), "generic");
+        if cfg!(feature = "in_format") {
+            assert_eq!(u16::backend(), "specific u16");
+            assert_eq!(i8::backend(), "specific i8");
+        } else {
+            assert_eq!(u16::backend(), "generic u16");
+            assert_eq!(i8::backend(), "generic");
+        }
+    }
+}
+
+mod type_attrs {
+    use conditional_trait_gen::trait_gen;
+
+    // the options generating items for all the types only use the 'cfg' attributes:
+    #[trait_gen(
+        T -> u8 #[cfg(any())],
+             u16 #[deprecated = "use U32Id"] #[derive(Debug)],
+             u32 #[derive(Debug, PartialEq)];
+        newtype, marker_trait = Small
+    )]
+    pub struct Id(pub T);
+
+    fn is_small<S: Small>() -> bool {
+        true
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test() {
+        assert_eq!(format!("{:?}", U16Id(3)), "U16Id(3)");
+        assert_eq!(U32Id(1), U32Id(1));
+        assert!(is_small::<u16>() && is_small::<u32>());
+    }
+}
+
+mod types_from {
+    use conditional_trait_gen::trait_gen;
+
+    pub struct Meter;
+    pub struct Liter;
+
+    pub trait Units {
+        type Length;
+        type Volume;
+    }
+
+    pub trait Name {
+        fn name() -> &'static str;
+    }
+
+    pub struct Registry;
+
+    #[trait_gen(T -> ; types_from = Units, anon_const)]
+    mod units {
+        use super::*;
+
+        impl Units for Registry {
+            type Length = Meter;
+            type Volume = self::super::Liter;
+        }
+
+        impl Name for T {
+            fn name() -> &'static str {
+                "${T}"
+            }
+        }
+    }
+
+    pub trait Bits {
+        fn bits() -> u32;
+    }
+
+    pub mod ints {
+        pub trait Ints {
+            type Small;
+            type Large;
+        }
+    }
+
+    #[trait_gen(T -> u8; types_from = ints::Ints, anon_const)]
+    mod bits {
+        use super::*;
+
+        impl ints::Ints for Registry {
+            type Small = u16;
+            type Large = u64;
+        }
+
+        impl Bits for T {
+            fn bits() -> u32 {
+                T::BITS
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Meter::name(), "Meter");
+        assert_eq!(<Registry as Units>::Volume::name(), "self::super::Liter");
+        assert_eq!((u8::bits(), u16::bits(), u64::bits()), (8, 16, 64));
+        let _: (<Registry as Units>::Length, <Registry as ints::Ints>::Small) = (Meter, 0);
+    }
+}
+
+mod forward_arc_mutex {
+    use conditional_trait_gen::trait_gen;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    struct Meter(f64);
+    struct Foot(f64);
+
+    trait Length {
+        type Unit;
+        const FACTOR: f64;
+        fn meters(&self) -> f64;
+        fn describe(&self, prefix: &str, pair: (u8, u8)) -> String;
+        fn convert<U: From<f64>>(&self) -> U;
+        fn unit(&self) -> Self::Unit;
+        fn symbol(&self) -> Option<&'static str>;
+    }
+
+    #[trait_gen(T -> Meter, Foot; forward_arc_mutex)]
+    impl Length for T {
+        type Unit = &'static str;
+        const FACTOR: f64 = if "${T}".len() == 5 { 1.0 } else { 0.3048 };
+
+        fn meters(&self) -> f64 {
+            self.0 * Self::FACTOR
+        }
+
+        fn describe(&self, prefix: &str, (a, b): (u8, u8)) -> String {
+            format!("{prefix}{}{a}{b}", self.meters())
+        }
+
+        fn convert<U: From<f64>>(&self) -> U {
+            U::from(self.meters())
+        }
+
+        fn unit(&self) -> Self::Unit {
+            "${T}"
+        }
+
+        fn symbol(&self) -> Option<&'static str> {
+            "${T}".get(..1)
+        }
+    }
+
+    fn meters<L: Length>(length: &L) -> f64 {
+        length.meters()
+    }
+
+    #[test]
+    fn test() {
+        let meter = Arc::new(Mutex::new(Meter(2.0)));
+        let foot = Arc::new(Mutex::new(Foot(10.0)));
+        assert_eq!(meter.meters(), 2.0);
+        assert_eq!(meter.describe("> ", (1, 2)), "> 212");
+        assert_eq!(meter.convert::<f64>(), 2.0);
+        assert_eq!(meter.unit(), "Meter");
+        assert_eq!(foot.unit(), "Foot");
+        assert_eq!(foot.symbol(), Some("F"));
+        assert_eq!(<Arc<Mutex<Foot>> as Length>::FACTOR, 0.3048);
+        assert_eq!(meters(&meter) + meters(&foot), 5.048);
+
+        let shared = meter.clone();
+        thread::spawn(move || shared.lock().unwrap().0 = 5.0)
+            .join()
+            .unwrap();
+        assert_eq!(meter.meters(), 5.0);
+    }
+}
+
+mod self_static {
+    use conditional_trait_gen::trait_gen;
+    use std::any::Any;
+    use std::fmt::Debug;
+
+    struct Wrapper<'a, U>(&'a U);
+
+    trait AsAny {
+        fn as_any(&self) -> &dyn Any;
+    }
+
+    // `as_any` requires `Self: 'static`
+    #[trait_gen(T -> u8, String, Wrapper<'static, i32>; self_static)]
+    impl AsAny for T {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    trait Describe {
+        fn describe(&self) -> String;
+    }
+
+    // existing lifetime bounds and where clauses are kept
+    #[trait_gen(T -> Wrapper<'a, U>, Box<Wrapper<'a, U>>; self_static)]
+    impl<'a, U: 'a> Describe for T
+    where
+        U: Debug,
+    {
+        fn describe(&self) -> String {
+            format!("${T}: {:?}", self.0)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(5_u8.as_any().downcast_ref::<u8>(), Some(&5));
+        assert!("a".to_string().as_any().downcast_ref::<u8>().is_none());
+        static VALUE: i32 = 3;
+        assert_eq!(
+            Wrapper(&VALUE)
+                .as_any()
+                .downcast_ref::<Wrapper<i32>>()
+                .map(|w| *w.0),
+            Some(3)
+        );
+        assert_eq!(Wrapper(&VALUE).describe(), "Wrapper::<'a,U>: 3");
+        assert_eq!(
+            Box::new(Wrapper(&VALUE)).describe(),
+            "Box::<Wrapper<'a,U>>: 3"
+        );
+    }
+}
+
+mod replace_self {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Debug, PartialEq)]
+    struct Wrapper<U>(U);
+
+    trait Combine {
+        type Part;
+        fn combine(&self, other: &Self) -> Self;
+        fn from_parts(parts: &[Self::Part]) -> Self;
+    }
+
+    #[trait_gen(T -> u16, f64; replace_self)]
+    impl Combine for Wrapper<T> {
+        type Part = T;
+
+        fn combine(&self, other: &Self) -> Self {
+            let parts: [Self::Part; 2] = [self.0, other.0];
+            <Self as Combine>::from_parts(&parts)
+        }
+
+        fn from_parts(parts: &[Self::Part]) -> Self {
+            let sum: Self::Part = parts.iter().copied().sum();
+            Self(sum)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Wrapper(2_u16).combine(&Wrapper(3)), Wrapper(5));
+        assert_eq!(Wrapper(0.5).combine(&Wrapper(1.0)), Wrapper(1.5));
+        assert_eq!(Wrapper::<u16>::from_parts(&[1, 2, 3]), Wrapper(6));
+    }
+}
+
+mod builder {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Debug, PartialEq)]
+    struct Meter {
+        value: f64,
+        precision: Option<u8>,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Foot {
+        value: f64,
+        precision: Option<u8>,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Scaled<U> {
+        value: f64,
+        precision: Option<U>,
+    }
+
+    #[trait_gen(T -> Meter, Foot, Scaled<u8>; builder)]
+    /// Builder of ${T}
+    #[derive(Debug)]
+    struct Builder {
+        /// Value in ${T}
+        value: f64,
+        precision: Option<u8>,
+    }
+
+    #[test]
+    fn test() {
+        let meter = MeterBuilder::new().value(2.5).precision(2).build();
+        assert_eq!(
+            meter,
+            Ok(Meter {
+                value: 2.5,
+                precision: Some(2)
+            })
+        );
+        let foot = Foot::builder().value(1.0).build();
+        assert_eq!(
+            foot,
+            Ok(Foot {
+                value: 1.0,
+                precision: None
+            })
+        );
+        assert_eq!(
+            FootBuilder::default().precision(1).build(),
+            Err("missing field 'value' in FootBuilder")
+        );
+        let scaled = ScaledBuilder::new()
+            .value(0.5)
+            .precision(3)
+            .build()
+            .unwrap();
+        assert_eq!(scaled.precision, Some(3_u8));
+    }
+}
+
+mod bidirectional {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Celsius(f64);
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Kelvin(f64);
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Fahrenheit(f64);
+
+    #[trait_gen(T -> Kelvin, Fahrenheit; bidirectional)]
+    impl From<T> for Celsius {
+        fn from(value: T) -> Self {
+            if "${T}" == "Kelvin" {
+                Celsius(value.0 - 273.0)
+            } else {
+                Celsius((value.0 - 32.0) / 1.8)
+            }
+        }
+
+        fn reverse(Celsius(c): Celsius) -> T {
+            T(if "${T}" == "Kelvin" {
+                c + 273.0
+            } else {
+                c * 1.8 + 32.0
+            })
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Celsius::from(Kelvin(300.0)), Celsius(27.0));
+        assert_eq!(Kelvin::from(Celsius(27.0)), Kelvin(300.0));
+        assert_eq!(Celsius::from(Fahrenheit(212.0)), Celsius(100.0));
+        let f: Fahrenheit = Celsius(100.0).into();
+        assert_eq!(f, Fahrenheit(212.0));
+        // round trip
+        for k in [0.0, 73.0, 373.0] {
+            let c: Celsius = Kelvin(k).into();
+            assert_eq!(Kelvin::from(c), Kelvin(k));
+            let f = Fahrenheit::from(c);
+            assert_eq!(Kelvin::from(Celsius::from(f)), Kelvin(k));
+        }
+    }
+}
+
+mod doc_hidden {
+    use conditional_trait_gen::trait_gen;
+
+    pub trait Name {
+        fn name(&self) -> &'static str;
+    }
+
+    #[trait_gen(T -> u8, u16; doc_hidden)]
+    /// Hidden implementation for ${T}
+    impl Name for T {
+        fn name(&self) -> &'static str {
+            "${T}"
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(1_u8.name(), "u8");
+        assert_eq!(1_u16.name(), "u16");
+    }
+}
+
+#[deny(dead_code, clippy::too_many_arguments)]
+mod allow {
+    use conditional_trait_gen::trait_gen;
+
+    pub struct Sum<V>(V);
+
+    #[trait_gen(T -> u8, u32; allow(dead_code, clippy::too_many_arguments))]
+    impl Sum<T> {
+        fn unused() {}
+
+        pub fn sum8(a: T, b: T, c: T, d: T, e: T, f: T, g: T, h: T) -> T {
+            a + b + c + d + e + f + g + h
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Sum::<u8>::sum8(1, 2, 3, 4, 5, 6, 7, 8), 36);
+        assert_eq!(Sum::<u32>::sum8(1, 1, 1, 1, 1, 1, 1, 1), 8);
+    }
+}
+
+// `out_format` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod gate_by_feature {
+    use conditional_trait_gen::trait_gen;
+
+    struct In;
+    struct Out;
+    struct InFormat;
+
+    trait Gated {
+        fn is_gated(&self) -> bool;
+    }
+
+    #[trait_gen(T -> In, Out; gate_by_feature = "${T}_format")]
+    impl Gated for T {
+        fn is_gated(&self) -> bool {
+            true
+        }
+    }
+
+    // feature name "in_format" taken from the type name
+    #[trait_gen(T -> InFormat; gate_by_feature)]
+    impl Gated for T {
+        fn is_gated(&self) -> bool {
+            true
+        }
+    }
+
+    // the fallback implementations would conflict with the generated ones if they weren't gated
+    #[trait_gen(T -> In, InFormat)]
+    #[cfg(not(feature = "in_format"))]
+    impl Gated for T {
+        fn is_gated(&self) -> bool {
+            false
+        }
+    }
+
+    #[cfg(not(feature = "out_format"))]
+    impl Gated for Out {
+        fn is_gated(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(In.is_gated(), cfg!(feature = "in_format"));
+        assert_eq!(InFormat.is_gated(), cfg!(feature = "in_format"));
+        assert!(!Out.is_gated());
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod layout_table {
+    mod units {
+        use conditional_trait_gen::trait_gen;
+
+        pub trait Zero {
+            fn zero() -> Self;
+        }
+
+        #[trait_gen(T -> u8, u32, [u16; 3], (u8, u64); layout_table = pub(crate) LAYOUTS)]
+        impl Zero for T {
+            fn zero() -> Self {
+                Default::default()
+            }
+        }
+
+        #[cfg(feature = "no_such_feature")]
+        #[derive(Default)]
+        pub struct Missing;
+
+        // the row of `Missing` is removed by its cfg attribute
+        #[trait_gen(T -> i16, Missing #[cfg(feature = "no_such_feature")]; layout_table = pub(crate) GATED_LAYOUTS)]
+        impl Zero for T {
+            fn zero() -> Self {
+                Default::default()
+            }
+        }
+    }
+
+    use units::{Zero, GATED_LAYOUTS, LAYOUTS};
+
+    // the table can be evaluated in constant expressions
+    const SIZES: usize = LAYOUTS[0].1 + LAYOUTS[1].1 + LAYOUTS[2].1;
+    const _: () = assert!(LAYOUTS[3].2 == std::mem::align_of::<u64>());
+
+    #[test]
+    fn test() {
+        const ALIGN_U64: usize = std::mem::align_of::<u64>();
+        assert_eq!(
+            LAYOUTS,
+            [
+                ("u8", 1, 1),
+                ("u32", 4, 4),
+                ("[u16;3]", 6, 2),
+                ("(u8,u64)", 2 * ALIGN_U64, ALIGN_U64)
+            ]
+        );
+        assert_eq!(SIZES, 11);
+        assert_eq!(<(u8, u64)>::zero(), (0, 0));
+        assert_eq!(GATED_LAYOUTS, [("i16", 2, 2)]);
+    }
+}
+
+mod skip_diagonal {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Meter(f64);
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Foot(f64);
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Mile(f64);
+
+    trait Factor {
+        const FACTOR: f64;
+    }
+
+    impl Factor for Meter {
+        const FACTOR: f64 = 1.0;
+    }
+    impl Factor for Foot {
+        const FACTOR: f64 = 0.25;
+    }
+    impl Factor for Mile {
+        const FACTOR: f64 = 2000.0;
+    }
+
+    // `From<T> for T` would conflict with the blanket implementation in `core`
+    #[trait_gen(T -> Meter, Foot, Mile; skip_diagonal)]
+    #[trait_gen(U -> Meter, Foot, Mile)]
+    impl From<T> for U {
+        fn from(value: T) -> Self {
+            U(value.0 * T::FACTOR / U::FACTOR)
+        }
+    }
+
+    trait Unit {
+        fn unit(&self) -> String;
+    }
+
+    // a single type is left for `u8`, and no copy is generated for `u16`
+    #[trait_gen(T -> u8, u16; skip_diagonal)]
+    #[trait_gen(U -> u16)]
+    impl Unit for (T, U) {
+        fn unit(&self) -> String {
+            "(${T}, ${U})".to_string()
+        }
+    }
+
+    impl Unit for (u16, u16) {
+        fn unit(&self) -> String {
+            "same".to_string()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Foot::from(Meter(1.0)), Foot(4.0));
+        assert_eq!(Mile::from(Meter(1000.0)), Mile(0.5));
+        assert_eq!(Meter::from(Foot(4.0)), Meter(1.0));
+        assert_eq!(Mile::from(Foot(4000.0)), Mile(0.5));
+        assert_eq!(Meter::from(Mile(1.0)), Meter(2000.0));
+        assert_eq!(Foot::from(Mile(1.0)), Foot(8000.0));
+        assert_eq!((1_u8, 2_u16).unit(), "(u8, u16)");
+        assert_eq!((1_u16, 2_u16).unit(), "same");
+    }
+}
+
+mod subst_attrs {
+    use conditional_trait_gen::trait_gen;
+    use std::mem::size_of;
+
+    trait Tag {
+        fn tag(&self) -> usize;
+    }
+
+    #[trait_gen(T -> u8, u16, u32; subst_attrs(repr))]
+    impl Tag for T {
+        fn tag(&self) -> usize {
+            #[repr(T)]
+            #[allow(dead_code)]
+            enum Tagged {
+                A,
+                B,
+            }
+            size_of::<Tagged>()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(0_u8.tag(), 1);
+        assert_eq!(0_u16.tag(), 2);
+        assert_eq!(0_u32.tag(), 4);
+    }
+}
+
+mod visitor {
+    use conditional_trait_gen::trait_gen;
+
+    struct Meter(f64);
+    struct Foot(f64);
+    struct BigMile(f64);
+
+    trait Value {
+        fn value(&self) -> f64;
+    }
+
+    #[trait_gen(T -> Meter, Foot, BigMile; visitor = pub(crate) Visitor)]
+    impl Value for T {
+        fn value(&self) -> f64 {
+            self.0
+        }
+    }
+
+    #[trait_gen(T -> u8, u16; visitor = IntVisitor, visit_method = "on_${T}_value")]
+    impl Value for T {
+        fn value(&self) -> f64 {
+            *self as f64
+        }
+    }
+
+    enum Length {
+        Meter(Meter),
+        Foot(Foot),
+        BigMile(BigMile),
+    }
+
+    impl Length {
+        fn accept<V: Visitor>(&self, visitor: &mut V) {
+            match self {
+                Length::Meter(m) => visitor.visit_meter(m),
+                Length::Foot(f) => visitor.visit_foot(f),
+                Length::BigMile(b) => visitor.visit_big_mile(b),
+            }
+        }
+    }
+
+    /// Only visits the feet
+    #[derive(Default)]
+    struct Feet(f64);
+
+    impl Visitor for Feet {
+        fn visit_foot(&mut self, value: &Foot) {
+            self.0 += value.0;
+        }
+    }
+
+    #[derive(Default)]
+    struct Total(f64);
+
+    impl Visitor for Total {
+        fn visit_meter(&mut self, value: &Meter) {
+            self.0 += value.value();
+        }
+        fn visit_foot(&mut self, value: &Foot) {
+            self.0 += value.0 * 0.25;
+        }
+        fn visit_big_mile(&mut self, value: &BigMile) {
+            self.0 += value.0 * 2000.0;
+        }
+    }
+
+    struct Ints(u32);
+
+    impl IntVisitor for Ints {
+        fn on_u16_value(&mut self, value: &u16) {
+            self.0 += value.value() as u32;
+        }
+    }
+
+    #[test]
+    fn test() {
+        let lengths = [
+            Length::Meter(Meter(1.0)),
+            Length::Foot(Foot(4.0)),
+            Length::BigMile(BigMile(0.5)),
+            Length::Foot(Foot(2.0)),
+        ];
+        let mut feet = Feet::default();
+        let mut total = Total::default();
+        for length in &lengths {
+            length.accept(&mut feet);
+            length.accept(&mut total);
+        }
+        assert_eq!(feet.0, 6.0);
+        assert_eq!(total.0, 1002.5);
+        let mut ints = Ints(0);
+        ints.on_u8_value(&1);
+        ints.on_u16_value(&2);
+        assert_eq!(ints.0, 2);
+    }
+}
+
+mod forward {
+    use conditional_trait_gen::trait_gen;
+
+    struct Wrapper<U>(U);
+
+    trait Describe {
+        fn kind() -> String;
+        fn describe(&self, prefix: &str) -> String;
+        fn scale<F: From<u8>>(&self, factor: F) -> (f64, F);
+    }
+
+    impl<U: Into<f64> + Copy> Describe for Wrapper<U> {
+        fn kind() -> String {
+            "wrapper".to_string()
+        }
+
+        fn describe(&self, prefix: &str) -> String {
+            format!("{prefix}{}", self.0.into())
+        }
+
+        fn scale<F: From<u8>>(&self, factor: F) -> (f64, F) {
+            (self.0.into() * 2.0, factor)
+        }
+    }
+
+    #[trait_gen(T -> u8, f32; forward)]
+    impl Describe for T {
+        fn kind() -> String {
+            forward!(Wrapper<T>)
+        }
+
+        fn describe(&self, prefix: &str) -> String {
+            forward!(Wrapper<T>, &Wrapper(*self))
+        }
+
+        fn scale<F: From<u8>>(&self, factor: F) -> (f64, F) {
+            forward!(Wrapper<T>, &Wrapper::<T>(*self));
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::kind(), "wrapper");
+        assert_eq!(3_u8.describe("> "), "> 3");
+        assert_eq!(1.5_f32.describe(""), "1.5");
+        assert_eq!(1.5_f32.scale(7_u32), (3.0, 7));
+    }
+}
+
+mod newtype {
+    use conditional_trait_gen::trait_gen;
+    use std::marker::PhantomData;
+    use std::mem::{align_of, size_of};
+
+    pub struct Meter(f64);
+
+    #[trait_gen(T -> u8, f32, Meter; newtype)]
+    /// FFI-safe wrapper of ${T}
+    pub struct Wrapper(pub T);
+
+    struct Tag;
+
+    #[trait_gen(T -> u16, i64; newtype)]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Tagged {
+        value: T,
+        tag: PhantomData<Tag>,
+    }
+
+    #[test]
+    fn test() {
+        let a = U8Wrapper(3);
+        let b = F32Wrapper(1.5);
+        let c = MeterWrapper(Meter(2.0));
+        assert_eq!(a.0 as f32 + b.0 + c.0 .0 as f32, 6.5);
+        assert_eq!(size_of::<MeterWrapper>(), size_of::<f64>());
+        assert_eq!(align_of::<U8Wrapper>(), 1);
+        let t = I64Tagged {
+            value: -1,
+            tag: PhantomData,
+        };
+        assert_eq!(t.value, -1);
+        assert_eq!(size_of::<U16Tagged>(), 2);
+        // the layout is the same as the inner type's:
+        let x: u16 = unsafe {
+            std::mem::transmute(U16Tagged {
+                value: 7,
+                tag: PhantomData,
+            })
+        };
+        assert_eq!(x, 7);
+    }
+}
+
+mod ord {
+    use conditional_trait_gen::trait_gen;
+    use std::cmp::Ordering;
+    use std::marker::PhantomData;
+
+    #[trait_gen(T -> u8, i64; newtype, ord)]
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct Id(pub T);
+
+    struct Tag;
+
+    #[trait_gen(T -> f32, f64; newtype, partial_ord)]
+    #[derive(PartialEq)]
+    struct Tagged {
+        tag: PhantomData<Tag>,
+        value: T,
+    }
+
+    #[test]
+    fn test() {
+        let mut ids = vec![I64Id(3), I64Id(-1), I64Id(2)];
+        ids.sort();
+        assert_eq!(ids, vec![I64Id(-1), I64Id(2), I64Id(3)]);
+        assert_eq!(U8Id(4).cmp(&U8Id(4)), Ordering::Equal);
+        assert_eq!(U8Id(1).partial_cmp(&U8Id(2)), Some(Ordering::Less));
+        let a = F32Tagged {
+            tag: PhantomData,
+            value: 1.5,
+        };
+        let b = F32Tagged {
+            tag: PhantomData,
+            value: f32::NAN,
+        };
+        assert!(
+            a < F32Tagged {
+                tag: PhantomData,
+                value: 2.0
+            }
+        );
+        assert_eq!(a.partial_cmp(&b), None);
+        let c = F64Tagged {
+            tag: PhantomData,
+            value: -0.5,
+        };
+        assert!(c <= c);
+    }
+}
+
+mod slice_impl {
+    use conditional_trait_gen::trait_gen;
+
+    trait Stats {
+        const UNIT: &'static str;
+        fn total(&self) -> f64;
+        fn log(&self, output: &mut Vec<String>);
+        fn scale(&mut self, factor: u8);
+    }
+
+    #[trait_gen(T -> u8, f32; slice_impl)]
+    impl Stats for T {
+        const UNIT: &'static str = "${T}";
+
+        fn total(&self) -> f64 {
+            *self as f64
+        }
+
+        fn slice_total(&self) -> f64 {
+            self.iter().map(|elem| elem.total()).sum()
+        }
+
+        fn log(&self, output: &mut Vec<String>) {
+            output.push(format!("{self}"));
+        }
+
+        fn scale(&mut self, factor: u8) {
+            *self *= factor as T;
+        }
+    }
+
+    fn total<S: Stats + ?Sized>(value: &S) -> f64 {
+        value.total()
+    }
+
+    #[test]
+    fn test() {
+        let mut values = [1_u8, 2, 3];
+        assert_eq!(values[..].total(), 6.0);
+        assert_eq!(total(&values[1..]), 5.0);
+        values.scale(2);
+        assert_eq!(values, [2, 4, 6]);
+        let mut output = Vec::new();
+        [0.5_f32, 1.5].log(&mut output);
+        assert_eq!(output, ["0.5", "1.5"]);
+        assert_eq!(<[f32] as Stats>::UNIT, "f32");
+        assert_eq!(2_u8.total(), 2.0);
+    }
+}
+
+mod require_methods {
+    use conditional_trait_gen::{trait_gen, when};
+
+    trait Limits: Sized {
+        fn low() -> Self;
+        fn high() -> Self;
+        fn name() -> String {
+            "unknown".to_string()
+        }
+    }
+
+    #[trait_gen(T -> u8, i16; require_methods(low, high, name))]
+    impl Limits for T {
+        fn low() -> T {
+            T::MIN
+        }
+
+        #[when(u8 -> high)]
+        fn high_u8() -> T {
+            100
+        }
+
+        #[when(i16 -> high)]
+        fn high_i16() -> T {
+            1000
+        }
+
+        fn name() -> String {
+            "${T}".to_string()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!((u8::low(), u8::high()), (0, 100));
+        assert_eq!((i16::low(), i16::high()), (i16::MIN, 1000));
+        assert_eq!(i16::name(), "i16");
+    }
+}
+
+mod inline_never {
+    use conditional_trait_gen::trait_gen;
+
+    trait Scale {
+        const FACTOR: Self;
+        fn scale(self) -> Self;
+    }
+
+    #[trait_gen(T -> u32, f64; inline_never)]
+    impl Scale for T {
+        const FACTOR: T = 3 as T;
+
+        #[inline]
+        fn scale(self) -> T {
+            self * T::FACTOR
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(2_u32.scale(), 6);
+        assert_eq!(0.5_f64.scale(), 1.5);
+    }
+}
+
+mod register {
+    use conditional_trait_gen::trait_gen;
+
+    trait Codec {
+        fn encode(&self) -> Vec<u8>;
+    }
+
+    trait Registered {
+        const NAME: &'static str;
+    }
+
+    macro_rules! register_codec {
+        ($t:ty) => {
+            impl Registered for $t {
+                const NAME: &'static str = stringify!($t);
+            }
+        };
+    }
+
+    #[trait_gen(T -> u16, u32, u64 #[cfg(any())]; register = register_codec)]
+    impl Codec for T {
+        fn encode(&self) -> Vec<u8> {
+            self.to_le_bytes().to_vec()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(0x102_u16.encode(), vec![2, 1]);
+        assert_eq!(<u16 as Registered>::NAME, "u16");
+        assert_eq!(<u32 as Registered>::NAME, "u32");
+    }
+}
+
+mod object_safe {
+    use conditional_trait_gen::trait_gen;
+
+    trait Source<'a> {
+        type Item;
+        fn next_item(&self) -> Option<Self::Item>;
+        fn name(&self) -> &'a str;
+    }
+
+    struct Counter(u32);
+    struct Letters(char);
+
+    #[trait_gen(T -> Counter, Letters; object_safe)]
+    impl<'a> Source<'a> for T {
+        type Item = String;
+
+        fn next_item(&self) -> Option<String> {
+            Some(self.0.to_string())
+        }
+
+        fn name(&self) -> &'a str {
+            "${T}"
+        }
+    }
+
+    #[test]
+    fn test() {
+        let sources: Vec<Box<dyn Source<Item = String>>> =
+            vec![Box::new(Counter(49)), Box::new(Letters('a'))];
+        let items = sources
+            .iter()
+            .map(|s| format!("{}:{}", s.name(), s.next_item().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(items, ["Counter:49", "Letters:a"]);
+    }
+}
+
+mod trace_calls {
+    use conditional_trait_gen::trait_gen;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static CALLS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    }
+
+    macro_rules! record {
+        ($name:literal) => {
+            CALLS.with(|calls| calls.borrow_mut().push($name))
+        };
+    }
+
+    trait Double: Sized {
+        fn double(self) -> Self;
+    }
+
+    struct Meter(f64);
+
+    #[trait_gen(T -> u8, f32; trace_calls = record)]
+    impl Double for T {
+        fn double(self) -> T {
+            self + self
+        }
+    }
+
+    #[trait_gen(T -> Meter; trace_calls = record)]
+    impl T {
+        fn new(value: f64) -> Self {
+            T(value)
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(2_u8.double(), 4);
+        assert_eq!(1.5_f32.double(), 3.0);
+        assert_eq!(Meter::new(2.0).0, 2.0);
+        let calls = CALLS.with(|calls| calls.borrow().clone());
+        assert_eq!(
+            calls,
+            [
+                "<u8 as Double>::double",
+                "<f32 as Double>::double",
+                "Meter::new"
+            ]
+        );
+    }
+}
+
+mod distinct_copies {
+    use conditional_trait_gen::trait_gen;
+
+    trait Name {
+        fn name(&self) -> String;
+    }
+
+    // the copies only differ by the string literals, which is enough
+    #[trait_gen(T -> u8, u16, i32; distinct_copies)]
+    impl Name for T {
+        fn name(&self) -> String {
+            format!("{}: ${T}", self)
+        }
+    }
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    #[trait_gen(T -> u8, u16; distinct_copies)]
+    #[trait_gen(U -> f32, f64; distinct_copies)]
+    impl Zero for (T, U) {
+        fn zero() -> Self {
+            (0, 0.0)
+        }
+    }
+
+    // legacy format: the original code, for `i8`, is compared too
+    #[trait_gen(i8, i16, i64; distinct_copies)]
+    impl Zero for i8 {
+        fn zero() -> Self {
+            0
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(1_u8.name(), "1: u8");
+        assert_eq!((-2_i32).name(), "-2: i32");
+        assert_eq!(<(u16, f64)>::zero(), (0, 0.0));
+        assert_eq!(i8::zero(), 0);
+        assert_eq!(i64::zero(), 0);
+    }
+}
+
+mod doc_alias {
+    use conditional_trait_gen::trait_gen;
+
+    pub trait Length {
+        fn new(len: f64) -> Self;
+        fn meters(&self) -> f64;
+    }
+
+    pub struct Meter(f64);
+    pub struct Foot(f64);
+
+    #[trait_gen(T -> Meter, Foot; doc_alias(meters))]
+    impl Length for T {
+        fn new(len: f64) -> Self {
+            T(len)
+        }
+
+        fn meters(&self) -> f64 {
+            if "${T}" == "Foot" {
+                self.0 * 0.3048
+            } else {
+                self.0
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Meter::new(2.0).meters(), 2.0);
+        assert_eq!(Foot::new(10.0).meters(), 3.048);
+    }
+}
+
+mod anon_const {
+    use conditional_trait_gen::trait_gen;
+
+    pub trait Describe {
+        fn describe(&self) -> String;
+    }
+
+    // the module and its helper have the same names in all the copies, which would conflict
+    // without the anonymous scopes
+    #[trait_gen(T -> u8, i32, f64; anon_const)]
+    mod imp {
+        use super::Describe;
+
+        fn helper(value: T) -> String {
+            format!("${T} {value}")
+        }
+
+        impl Describe for T {
+            fn describe(&self) -> String {
+                helper(*self)
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(3_u8.describe(), "u8 3");
+        assert_eq!((-1_i32).describe(), "i32 -1");
+        assert_eq!(0.5_f64.describe(), "f64 0.5");
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod tag_const {
+    use conditional_trait_gen::trait_gen;
+
+    pub struct Meter(pub f64);
+    #[allow(dead_code)]
+    pub struct Foot(pub f64);
+    pub struct BigInt<V>(pub V);
+
+    trait Length {
+        fn meters(&self) -> f64;
+    }
+
+    #[trait_gen(T -> Meter, Foot #[cfg(feature = "no_such_feature")], BigInt<u8>; tag_const)]
+    impl Length for T {
+        fn meters(&self) -> f64 {
+            self.0 as f64
+        }
+    }
+
+    trait Bits {
+        fn bits() -> u32;
+    }
+
+    #[trait_gen(T -> u8, u16, u32; tag_const = pub(crate) "TAG_${T}", tag_base = 100)]
+    impl Bits for T {
+        fn bits() -> u32 {
+            T::BITS
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(METER_TAG, 0);
+        // the position in the list is kept when a type is disabled
+        assert_eq!(BIG_INT_TAG, 2);
+        assert_eq!(BigInt(3_u8).meters() + Meter(1.0).meters(), 4.0);
+        let tags = [
+            (TAG_U8, u8::bits()),
+            (TAG_U16, u16::bits()),
+            (TAG_U32, u32::bits()),
+        ];
+        assert_eq!(tags, [(100, 8), (101, 16), (102, 32)]);
+    }
+}
+
+mod error_enum {
+    use conditional_trait_gen::trait_gen;
+    use std::num::{ParseFloatError, ParseIntError};
+
+    trait Percent: Sized {
+        fn percent(self) -> Result<Self, RangeError>;
+    }
+
+    #[trait_gen(T -> u8, f32, i64; error_enum = pub RangeError(T), error_variant = "Invalid${T}")]
+    impl Percent for T {
+        fn percent(self) -> Result<Self, RangeError> {
+            if (0 as T..=100 as T).contains(&self) {
+                Ok(self)
+            } else {
+                Err(self.into())
+            }
+        }
+    }
+
+    trait Parse: Sized {
+        fn parse(s: &str) -> Result<Self, ParseError>;
+    }
+
+    // the data types are substituted, but without conversions since they don't depend on T
+    #[trait_gen(T -> u8, f32; error_enum = ParseError(String))]
+    impl Parse for T {
+        fn parse(s: &str) -> Result<Self, ParseError> {
+            s.parse::<T>().map_err(|e| {
+                if "${T}" == "u8" {
+                    ParseError::U8(e.to_string())
+                } else {
+                    ParseError::F32(e.to_string())
+                }
+            })
+        }
+    }
+
+    pub struct Meter(pub f64);
+    #[allow(dead_code)]
+    pub struct Foot(pub f64);
+
+    trait Length: Sized {
+        fn checked(value: f64) -> Result<Self, LengthError>;
+    }
+
+    #[trait_gen(T -> Meter, Foot #[cfg(any())]; error_enum = LengthError)]
+    impl Length for T {
+        fn checked(value: f64) -> Result<Self, LengthError> {
+            if value >= 0.0 {
+                Ok(T(value))
+            } else {
+                Err(LengthError::Meter)
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(50_u8.percent().unwrap(), 50);
+        assert!(matches!(150_u8.percent(), Err(RangeError::InvalidU8(150))));
+        assert!(matches!(
+            (-1_i64).percent(),
+            Err(RangeError::InvalidI64(-1))
+        ));
+        match 100.5_f32.percent() {
+            Err(RangeError::InvalidF32(x)) => assert_eq!(x, 100.5),
+            _ => panic!("expecting RangeError::InvalidF32"),
+        }
+        assert!(matches!(RangeError::from(3_u8), RangeError::InvalidU8(3)));
+        assert_eq!(u8::parse("7").unwrap(), 7);
+        let int_error = "x".parse::<u8>().unwrap_err();
+        let float_error = "x".parse::<f32>().unwrap_err();
+        assert!(matches!(u8::parse("x"), Err(ParseError::U8(e)) if e == int_error.to_string()));
+        assert!(matches!(f32::parse("x"), Err(ParseError::F32(e)) if e == float_error.to_string()));
+        let _: (ParseIntError, ParseFloatError) = (int_error, float_error);
+        assert_eq!(Meter::checked(1.0).unwrap().0, 1.0);
+        assert!(matches!(Meter::checked(-1.0), Err(LengthError::Meter)));
+        assert_eq!(format!("{:?}", LengthError::Meter), "Meter");
+    }
+}
+
+mod marker_trait {
+    use conditional_trait_gen::trait_gen;
+
+    pub struct Meter(pub f64);
+    pub struct Foot(pub f64);
+
+    pub trait Length: Unit {
+        fn meters(&self) -> f64;
+    }
+
+    #[trait_gen(T -> Meter, Foot; marker_trait = pub Unit)]
+    impl Length for T {
+        fn meters(&self) -> f64 {
+            if "${T}" == "Foot" {
+                self.0 * 0.3048
+            } else {
+                self.0
+            }
+        }
+    }
+
+    trait Bits {
+        fn bits() -> u32;
+    }
+
+    #[trait_gen(T -> u8, u16 #[cfg(any())], u32; marker_trait = Small)]
+    impl Bits for T {
+        fn bits() -> u32 {
+            T::BITS
+        }
+    }
+
+    fn total<U: Unit + Length>(lengths: &[U]) -> f64 {
+        lengths.iter().map(|l| l.meters()).sum()
+    }
+
+    fn small_bits<S: Small + Bits>() -> u32 {
+        S::bits()
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(total(&[Meter(1.0), Meter(2.0)]), 3.0);
+        assert_eq!(total(&[Foot(10.0)]), 3.048);
+        assert_eq!(small_bits::<u8>(), 8);
+        assert_eq!(small_bits::<u32>(), 32);
+    }
+}
+
+mod blanket_impl {
+    use conditional_trait_gen::trait_gen;
+
+    struct Meter(f64);
+    struct Foot(f64);
+
+    trait Length {
+        fn meters(&self) -> f64;
+    }
+
+    trait Describe {
+        const UNIT: &'static str;
+        fn describe(&self) -> String;
+    }
+
+    #[trait_gen(T -> Meter, Foot; blanket_impl = Describe {
+        const UNIT: &'static str = "m";
+
+        fn describe(&self) -> String {
+            format!("{} {}", self.meters(), Self::UNIT)
+        }
+    })]
+    impl Length for T {
+        fn meters(&self) -> f64 {
+            if "${T}" == "Foot" {
+                self.0 * 0.3048
+            } else {
+                self.0
+            }
+        }
+    }
+
+    trait Bits {
+        fn bits() -> u32;
+    }
+
+    trait Bytes {
+        fn bytes() -> u32;
+    }
+
+    #[trait_gen(T -> u8, u16 #[cfg(any())], u32; blanket_impl = Bytes {
+        fn bytes() -> u32 {
+            Self::bits() / 8
+        }
+    })]
+    impl Bits for T {
+        fn bits() -> u32 {
+            T::BITS
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Meter(2.0).describe(), "2 m");
+        assert_eq!(Foot(10.0).describe(), "3.048 m");
+        assert_eq!(u8::bytes(), 1);
+        assert_eq!(u32::bytes(), 4);
+    }
+}
+
+mod roundtrip_test {
+    use conditional_trait_gen::trait_gen;
+    use std::fmt::Debug;
+
+    pub trait Bytes: Sized {
+        fn to_bytes(&self) -> Vec<u8>;
+        fn from_bytes(bytes: &[u8]) -> Self;
+    }
+
+    #[trait_gen(T -> u16, i32 #[cfg(any())], u64, f64; roundtrip_test = roundtrip(checks::check))]
+    impl Bytes for T {
+        fn to_bytes(&self) -> Vec<u8> {
+            self.to_be_bytes().to_vec()
+        }
+
+        fn from_bytes(bytes: &[u8]) -> Self {
+            T::from_be_bytes(bytes.try_into().unwrap())
+        }
+    }
+
+    mod checks {
+        use super::*;
+
+        pub fn check<B: Bytes + From<u8> + PartialEq + Debug>() {
+            let value = B::from(200);
+            assert_eq!(B::from_bytes(&value.to_bytes()), value);
+        }
+    }
+}
+
+mod type_id_map {
+    use conditional_trait_gen::trait_gen;
+    use std::any::{Any, TypeId};
+
+    trait Parse: Sized {
+        fn parse_any(text: &str) -> Option<Box<dyn Any>>;
+    }
+
+    #[trait_gen(T -> u8, f64, bool, char #[cfg(any())]; type_id_map = pub(crate) parsers(parse_any))]
+    impl Parse for T {
+        fn parse_any(text: &str) -> Option<Box<dyn Any>> {
+            let value: T = text.parse().ok()?;
+            Some(Box::new(value))
+        }
+    }
+
+    fn parse<P: 'static>(text: &str) -> Option<P> {
+        let parse = parsers().get(&TypeId::of::<P>())?;
+        parse(text)?.downcast().ok().map(|b| *b)
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(parsers().len(), 3);
+        assert_eq!(parse::<u8>("12"), Some(12));
+        assert_eq!(parse::<u8>("300"), None);
+        assert_eq!(parse::<f64>("2.5"), Some(2.5));
+        assert_eq!(parse::<bool>("true"), Some(true));
+        assert_eq!(parse::<char>("c"), None);
+    }
+}
+
+mod max_size {
+    use conditional_trait_gen::trait_gen;
+
+    const WORD: usize = std::mem::size_of::<usize>();
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    // `[u64; 4]` would fail, but it's removed by its cfg attribute, along with its check
+    #[trait_gen(T -> u8, (u32, u16), [u64; 4] #[cfg(any())]; max_size = 2 * WORD)]
+    impl Zero for T {
+        fn zero() -> T {
+            <T>::default()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::zero(), 0);
+        assert_eq!(<(u32, u16)>::zero(), (0, 0));
+    }
+}
+
+mod deferred_macro {
+    use conditional_trait_gen::trait_gen;
+
+    trait Half {
+        fn half(&self) -> Self;
+    }
+
+    #[trait_gen(T -> u32, f64; deferred_macro = impl_half)]
+    impl Half for T {
+        fn half(&self) -> T {
+            let two: T = 2 as T;
+            *self / two
+        }
+    }
+
+    #[trait_gen(U -> u8, i8; deferred_macro = impl_name, layout_table = pub(super) LAYOUTS)]
+    impl Name for U {
+        fn name() -> &'static str {
+            "${U}"
+        }
+    }
+
+    trait Name {
+        fn name() -> &'static str;
+    }
+
+    mod generated {
+        use super::{Half, Name};
+
+        impl_half!();
+        impl_name!();
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(9_u32.half(), 4);
+        assert_eq!(9.0_f64.half(), 4.5);
+        assert_eq!(<i8 as Name>::name(), "i8");
+        assert_eq!(generated::LAYOUTS, [("u8", 1, 1), ("i8", 1, 1)]);
+    }
+}
+
+mod mod_groups {
+    use conditional_trait_gen::trait_gen;
+
+    pub trait Unit {
+        const SYMBOL: &'static str;
+    }
+
+    #[derive(Clone, Copy)]
+    pub struct Meter;
+    #[derive(Clone, Copy)]
+    pub struct Kilometer;
+    #[derive(Clone, Copy)]
+    pub struct Foot;
+    #[derive(Clone, Copy)]
+    pub struct Mile;
+    #[derive(Clone, Copy)]
+    pub struct Parsec;
+
+    // the unit is the wrapped type, which is found in the parent module
+    #[trait_gen(T -> Meter, Kilometer, Foot, Mile, Parsec; newtype,
+        mod_groups(pub metric = [Meter, Kilometer], pub(crate) imperial = [Foot, Mile]))]
+    #[derive(Clone, Copy)]
+    pub struct Length(pub T);
+
+    #[trait_gen(T -> metric::MeterLength, metric::KilometerLength, imperial::FootLength,
+        imperial::MileLength, ParsecLength)]
+    impl Unit for T {
+        const SYMBOL: &'static str = "${T}";
+    }
+
+    // in legacy format, the original code goes in the group of the generic argument too
+    #[trait_gen(Meter, Foot, Parsec; mod_groups(pub metric_names = [Meter], imperial_names = [Foot]))]
+    pub fn unit() -> Meter {
+        Meter
+    }
+
+    #[test]
+    fn test() {
+        let _: Meter = metric::MeterLength(Meter).0;
+        let _: Meter = metric_names::unit();
+        let _: Foot = imperial_names::unit();
+        let _: Parsec = unit();
+        let _: Foot = imperial::FootLength(Foot).0;
+        let _: Parsec = ParsecLength(Parsec).0;
+        assert_eq!(metric::KilometerLength::SYMBOL, "metric::KilometerLength");
+        assert_eq!(
+            <imperial::MileLength as Unit>::SYMBOL,
+            "imperial::MileLength"
+        );
+    }
+}
+
+mod factory {
+    use conditional_trait_gen::trait_gen;
+
+    trait Shape {
+        fn sides(&self) -> u32;
+    }
+
+    #[derive(Default)]
+    struct Triangle;
+    #[derive(Default)]
+    struct Square;
+    #[derive(Default)]
+    #[allow(dead_code)]
+    struct Hexagon;
+
+    #[trait_gen(T -> Triangle, Square, Hexagon #[cfg(any())]; factory = make_shape)]
+    impl Shape for T {
+        fn sides(&self) -> u32 {
+            match "${T}" {
+                "Triangle" => 3,
+                "Square" => 4,
+                _ => 6,
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(make_shape("Triangle").map(|s| s.sides()), Some(3));
+        assert_eq!(make_shape("Square").map(|s| s.sides()), Some(4));
+        assert!(make_shape("Hexagon").is_none());
+        assert!(make_shape("square").is_none());
+    }
+}
+
+mod from_roundtrip_tests {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Debug, PartialEq, Default)]
+    struct Meter(i64);
+    #[derive(Debug, PartialEq, Default)]
+    struct Centimeter(i64);
+    #[derive(Debug, PartialEq, Default)]
+    struct Millimeter(i64);
+
+    // generates `from_roundtrip_centimeter_meter` and `from_roundtrip_millimeter_meter`
+    #[trait_gen(T -> Centimeter, Millimeter; bidirectional, from_roundtrip_tests)]
+    impl From<T> for Meter {
+        fn from(value: T) -> Self {
+            let factor = if "${T}" == "Centimeter" { 100 } else { 1000 };
+            Meter(value.0 / factor)
+        }
+
+        fn reverse(value: Meter) -> T {
+            let factor = if "${T}" == "Centimeter" { 100 } else { 1000 };
+            T(value.0 * factor)
+        }
+    }
+
+    #[test]
+    fn test() {
+        from_roundtrip_centimeter_meter();
+        from_roundtrip_millimeter_meter();
+        assert_eq!(Millimeter::from(Meter(2)), Millimeter(2000));
+    }
+}
+
+mod doc_table {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    #[trait_gen(T -> u8, i64 #[cfg(any())], f32; doc_table = pub(crate) zero_types)]
+    impl Zero for T {
+        fn zero() -> T {
+            0 as T
+        }
+    }
+
+    #[test]
+    fn test() {
+        // the module is only there to be documented
+        #[allow(unused_imports)]
+        use zero_types as _;
+        assert_eq!(u8::zero(), 0);
+        assert_eq!(f32::zero(), 0.0);
+    }
+}
+
+mod zip {
+    use conditional_trait_gen::trait_gen;
+    use std::sync::atomic::{AtomicI64, AtomicU16, AtomicU32, AtomicU8, Ordering};
+
+    trait AtomicCounter {
+        type Value: Copy;
+        fn new(value: Self::Value) -> Self;
+        fn add(&self, delta: Self::Value) -> Self::Value;
+        fn get(&self) -> Self::Value;
+        fn name(&self) -> String;
+    }
+
+    #[trait_gen(T -> u8, u16, u32, i64; zip)]
+    #[trait_gen(A -> AtomicU8, AtomicU16, AtomicU32, AtomicI64)]
+    impl AtomicCounter for A {
+        type Value = T;
+
+        fn new(value: T) -> Self {
+            A::new(value)
+        }
+
+        fn add(&self, delta: T) -> T {
+            self.fetch_add(delta, Ordering::SeqCst) + delta
+        }
+
+        fn get(&self) -> T {
+            self.load(Ordering::SeqCst)
+        }
+
+        fn name(&self) -> String {
+            "${A}<${T}>".to_string()
+        }
+    }
+
+    fn count<C: AtomicCounter>(counter: &C, delta: C::Value, times: usize) -> C::Value {
+        for _ in 0..times {
+            counter.add(delta);
+        }
+        counter.get()
+    }
+
+    #[test]
+    fn test() {
+        let a = <AtomicU8 as AtomicCounter>::new(1);
+        assert_eq!(count(&a, 2, 3), 7);
+        assert_eq!(a.name(), "AtomicU8<u8>");
+        let a = <AtomicU16 as AtomicCounter>::new(1000);
+        assert_eq!(count(&a, 100, 5), 1500);
+        assert_eq!(a.name(), "AtomicU16<u16>");
+        let a = <AtomicU32 as AtomicCounter>::new(0);
+        assert_eq!(a.add(70_000), 70_000);
+        assert_eq!(a.name(), "AtomicU32<u32>");
+        let a = <AtomicI64 as AtomicCounter>::new(0);
+        assert_eq!(count(&a, -3, 4), -12);
+        assert_eq!(a.name(), "AtomicI64<i64>");
+    }
+}
+
+mod schema_version {
+    use conditional_trait_gen::trait_gen;
+
+    const MAJOR: u32 = 1;
+
+    trait Schema {
+        const SCHEMA_VERSION: u32;
+        fn tag() -> String;
+    }
+
+    struct Point;
+    struct Rect;
+
+    #[trait_gen(T -> Point, Rect; schema_version = MAJOR << 16 | 2)]
+    impl Schema for T {
+        fn tag() -> String {
+            format!("${T}/v{}", <Self as Schema>::SCHEMA_VERSION)
+        }
+    }
+
+    #[trait_gen(T -> Point, Rect; schema_version = 7)]
+    impl T {
+        fn version(&self) -> u32 {
+            Self::SCHEMA_VERSION
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(<Point as Schema>::SCHEMA_VERSION, 0x10002);
+        assert_eq!(<Rect as Schema>::SCHEMA_VERSION, 0x10002);
+        assert_eq!(Point::tag(), "Point/v65538");
+        assert_eq!(Rect::tag(), "Rect/v65538");
+        assert_eq!(Point.version(), 7);
+        assert_eq!(Rect.version(), 7);
+    }
+}
+
+// `informat` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod gate_case {
+    use conditional_trait_gen::trait_gen;
+
+    // `in_format` is a feature of the crate
+    #[allow(non_camel_case_types)]
+    struct IN_FORMAT;
+    struct InFormat;
+    #[allow(non_camel_case_types)]
+    struct in_format;
+
+    trait Gated {
+        fn is_gated(&self) -> bool;
+    }
+
+    // features "in_format" and "informat"
+    #[trait_gen(T -> IN_FORMAT, InFormat; gate_by_feature, gate_case = lower)]
+    impl Gated for T {
+        fn is_gated(&self) -> bool {
+            true
+        }
+    }
+
+    // feature "in_format"
+    #[trait_gen(T -> in_format; gate_by_feature, gate_case = exact)]
+    impl Gated for T {
+        fn is_gated(&self) -> bool {
+            true
+        }
+    }
+
+    #[cfg(not(feature = "in_format"))]
+    impl Gated for IN_FORMAT {
+        fn is_gated(&self) -> bool {
+            false
+        }
+    }
+
+    #[cfg(not(feature = "informat"))]
+    impl Gated for InFormat {
+        fn is_gated(&self) -> bool {
+            false
+        }
+    }
+
+    #[cfg(not(feature = "in_format"))]
+    impl Gated for in_format {
+        fn is_gated(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(IN_FORMAT.is_gated(), cfg!(feature = "in_format"));
+        assert!(!InFormat.is_gated());
+        assert_eq!(in_format.is_gated(), cfg!(feature = "in_format"));
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod bit_const {
+    use conditional_trait_gen::trait_gen;
+
+    pub struct Meter(pub f64);
+    #[allow(dead_code)]
+    pub struct Foot(pub f64);
+    pub struct BigInt<V>(pub V);
+
+    trait Length {
+        fn meters(&self) -> f64;
+    }
+
+    #[trait_gen(T -> Meter, Foot #[cfg(feature = "no_such_feature")], BigInt<u8>; bit_const)]
+    impl Length for T {
+        fn meters(&self) -> f64 {
+            self.0 as f64
+        }
+    }
+
+    trait Bits {
+        fn bits() -> u32;
+    }
+
+    #[trait_gen(T -> u8, u16, u32, u64, u128, i8, i16, i32; bit_const = pub(crate) "${T}_MASK", bit_type = u8)]
+    impl Bits for T {
+        fn bits() -> u32 {
+            T::BITS
+        }
+    }
+
+    #[test]
+    fn test() {
+        let bits: u32 = METER_BIT;
+        assert_eq!(bits, 1);
+        // the position in the list is kept when a type is disabled
+        assert_eq!(BIG_INT_BIT, 4);
+        assert_eq!(BigInt(3_u8).meters() + Meter(1.0).meters(), 4.0);
+        let masks: [u8; 4] = [U8_MASK, U16_MASK, U128_MASK, I32_MASK];
+        assert_eq!(masks, [1, 2, 16, 128]);
+        assert_eq!(u8::bits() + i32::bits(), 40);
+    }
+}
+
+mod coherence_test {
+    use conditional_trait_gen::trait_gen;
+
+    struct Meter(f64);
+    struct Foot(f64);
+
+    trait Scale<U> {
+        fn scale(&self, factor: U) -> f64;
+    }
+
+    // checks `(Meter, Foot): Scale<u8>` and `(Meter, Foot): Scale<f32>`, and that `u8` and `f32`
+    // are different types
+    #[trait_gen(T -> u8, f32; coherence_test = scale_coherence)]
+    impl Scale<T> for (Meter, Foot) {
+        fn scale(&self, factor: T) -> f64 {
+            (self.0 .0 + self.1 .0) * factor as f64
+        }
+    }
+
+    #[trait_gen(T -> Meter, Foot #[cfg(not(test))]; coherence_test = unit_coherence)]
+    impl T {
+        fn value(&self) -> f64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test() {
+        let pair = (Meter(1.0), Foot(2.0));
+        assert_eq!(pair.scale(2_u8), 6.0);
+        assert_eq!(pair.scale(0.5_f32), 1.5);
+        assert_eq!(Meter(4.0).value(), 4.0);
+        assert_eq!(pair.1 .0, 2.0);
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod type_enum {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    #[trait_gen(T -> u8, i64 #[cfg(feature = "no_such_feature")], f32, std::num::Wrapping<u16>; type_enum = pub(crate) Kind)]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    trait One {
+        fn one() -> Self;
+    }
+
+    // the discriminants follow the alphabetical order of the names, not the list
+    #[trait_gen(T -> u16, i8, f64; type_enum = Sorted, tag_order = alphabetical)]
+    impl One for T {
+        fn one() -> Self {
+            1 as T
+        }
+    }
+
+    fn encode(kind: Kind) -> u8 {
+        kind.index_of() as u8
+    }
+
+    fn decode(byte: u8) -> Option<Kind> {
+        Kind::from_index(byte as usize)
+    }
+
+    #[test]
+    fn test() {
+        let kinds = [Kind::U8, Kind::F32, Kind::Wrapping];
+        // the index ordering matches the variant order, and a disabled type keeps its position
+        assert_eq!(kinds.map(|kind| kind.index_of()), [0, 2, 3]);
+        for kind in kinds {
+            assert_eq!(decode(encode(kind)), Some(kind));
+        }
+        assert_eq!(decode(1), None);
+        assert_eq!(decode(4), None);
+        assert_eq!(Kind::name_of_index(0), Some("u8"));
+        assert_eq!(Kind::name_of_index(1), None);
+        assert_eq!(Kind::name_of_index(3), Some("std::num::Wrapping::<u16>"));
+        assert_eq!(u8::zero() as f32 + f32::zero(), 0.0);
+        assert_eq!(
+            [Sorted::F64 as u8, Sorted::I8 as u8, Sorted::U16 as u8],
+            [0, 1, 2]
+        );
+        assert_eq!(Sorted::U16.index_of(), 2);
+        assert_eq!(Sorted::name_of_index(0), Some("f64"));
+        assert_eq!(u16::one() as f64, f64::one());
+    }
+}
+
+mod type_tag {
+    use conditional_trait_gen::trait_gen;
+    use std::num::Wrapping;
+
+    mod units {
+        pub struct Meter(pub f64);
+        pub struct Foot(pub f64);
+    }
+
+    trait Tagged {
+        fn type_tag(&self) -> &'static str;
+        fn is_zero(&self) -> bool;
+    }
+
+    #[trait_gen(T -> u8, units::Meter, Wrapping<u16>; type_tag)]
+    impl Tagged for T {
+        fn is_zero(&self) -> bool {
+            "${T}" == "u8"
+        }
+    }
+
+    trait FullName {
+        fn full_name(&self) -> &'static str;
+    }
+
+    #[trait_gen(T -> units::Meter, units::Foot, Wrapping<u16>; type_tag = full_name, type_tag_path = full)]
+    impl FullName for T {}
+
+    #[test]
+    fn test() {
+        assert_eq!(1_u8.type_tag(), "u8");
+        assert_eq!(units::Meter(1.0).type_tag(), "Meter");
+        assert_eq!(Wrapping(1_u16).type_tag(), "Wrapping");
+        assert_eq!(units::Meter(1.0).full_name(), "units::Meter");
+        assert_eq!(units::Foot(2.0).full_name(), "units::Foot");
+        assert_eq!(Wrapping(1_u16).full_name(), "Wrapping::<u16>");
+        assert!(0_u8.is_zero());
+        assert_eq!(units::Meter(3.0).0 + units::Foot(1.0).0, 4.0);
+    }
+}
+
+mod deprecated_note {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Clone, Copy)]
+    struct Meter(f64);
+    #[derive(Clone, Copy)]
+    struct Foot(f64);
+    #[derive(Clone, Copy)]
+    struct Yard(f64);
+    #[derive(Clone, Copy)]
+    struct Mile(f64);
+
+    // only `Foot` and `Yard` get the note, `Mile` keeps its own
+    #[trait_gen(T -> Meter, Foot #[deprecated], Yard #[deprecated], Mile #[deprecated(note = "too far")];
+        deprecated_note = "use Meter instead of ${T}")]
+    impl T {
+        fn value(&self) -> f64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Meter(1.0).value(), 1.0);
+        #[allow(deprecated)]
+        let values = (Foot(2.0).value(), Yard(3.0).value(), Mile(4.0).value());
+        assert_eq!(values, (2.0, 3.0, 4.0));
+    }
+}
+
+mod ctor_array {
+    use conditional_trait_gen::trait_gen;
+
+    #[derive(Default)]
+    struct Counter(u32);
+
+    trait Describe {
+        fn describe(&self) -> String;
+    }
+
+    #[trait_gen(T -> u8, String, Counter, Vec<i32>; ctor_array = pub(crate) CTORS, tag_const)]
+    impl Describe for T {
+        fn describe(&self) -> String {
+            "${T}".to_string()
+        }
+    }
+
+    impl Counter {
+        fn count(&self) -> u32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test() {
+        let ctors: [fn() -> Box<dyn Describe>; 4] = CTORS;
+        // the array is in the order of the list, like the tags
+        assert_eq!(ctors[U8_TAG as usize]().describe(), "u8");
+        assert_eq!(ctors[COUNTER_TAG as usize]().describe(), "Counter");
+        let names = CTORS
+            .iter()
+            .map(|ctor| ctor().describe())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["u8", "String", "Counter", "Vec::<i32>"]);
+        assert_eq!(Counter::default().count(), 0);
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod try_from_name {
+    use conditional_trait_gen::trait_gen;
+    use std::convert::TryFrom;
+
+    mod units {
+        #[derive(Debug, Default, PartialEq)]
+        pub struct Meter(pub f64);
+        #[derive(Debug, Default, PartialEq)]
+        pub struct Foot(pub f64);
+        #[derive(Debug, Default, PartialEq)]
+        pub struct Mile(pub f64);
+    }
+    use units::*;
+
+    // the generated code doesn't depend on the `Result` in scope
+    #[allow(dead_code)]
+    type Result<T> = std::result::Result<T, String>;
+
+    trait Length {
+        fn meters(&self) -> f64;
+    }
+
+    #[trait_gen(T -> Meter, units::Foot, Mile #[cfg(feature = "no_such_feature")]; try_from_name)]
+    impl Length for T {
+        fn meters(&self) -> f64 {
+            self.0
+        }
+    }
+
+    fn parse(name: &str) -> Option<Box<dyn Length>> {
+        let name = match Meter::try_from(name) {
+            Ok(meter) => return Some(Box::new(meter)),
+            Err(name) => name,
+        };
+        Foot::try_from(name)
+            .ok()
+            .map(|foot| Box::new(foot) as Box<dyn Length>)
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(Meter::try_from("Meter"), Ok(Meter(0.0)));
+        assert_eq!(Meter::try_from("meter"), Err("meter"));
+        // the name is the type as given in the attribute
+        assert_eq!(Foot::try_from("units::Foot"), Ok(Foot(0.0)));
+        assert_eq!(Foot::try_from("Foot"), Err("Foot"));
+        assert_eq!(parse("units::Foot").map(|foot| foot.meters()), Some(0.0));
+        assert!(parse("Mile").is_none());
+        assert_eq!(Mile(1.0), Mile(1.0));
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod tag_trait {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    #[trait_gen(T -> u8, i64 #[cfg(feature = "no_such_feature")], f32, [u16; 2]; tag_trait = pub(crate) HasTag, tag_base = 100)]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    // compile-time dispatch on the tags
+    struct Tagged<U: HasTag>(U);
+
+    impl<U: HasTag> Tagged<U> {
+        const IS_FIRST: bool = U::TAG == 100;
+
+        fn tag(&self) -> u32 {
+            U::TAG
+        }
+    }
+
+    #[allow(dead_code)]
+    trait Other {}
+
+    #[trait_gen(T -> u8, f32; tag_trait = OtherTag, tag_const)]
+    impl Other for T {}
+
+    #[test]
+    fn test() {
+        assert_eq!(<u8 as HasTag>::TAG, 100);
+        // the position in the list is kept when a type is disabled
+        assert_eq!(<f32 as HasTag>::TAG, 102);
+        assert_eq!(Tagged([1_u16, 2]).tag(), 103);
+        assert_eq!(
+            [Tagged::<u8>::IS_FIRST, Tagged::<f32>::IS_FIRST],
+            [true, false]
+        );
+        assert_eq!(Tagged(f32::zero()).0 + u8::zero() as f32, 0.0);
+        // same values as the tag constants
+        assert_eq!(<f32 as OtherTag>::TAG, F32_TAG);
+    }
+}
+
+mod sorted_names {
+    mod units {
+        use conditional_trait_gen::trait_gen;
+
+        pub trait Zero {
+            fn zero() -> Self;
+        }
+
+        #[trait_gen(T -> u8, Vec<u16>, i32, [u8; 2], String, (u8, bool); sorted_names = pub(crate) SORTED_NAMES)]
+        impl Zero for T {
+            fn zero() -> Self {
+                Default::default()
+            }
+        }
+    }
+
+    use units::{Zero, SORTED_NAMES};
+
+    fn index_of(name: &str) -> Option<usize> {
+        SORTED_NAMES
+            .binary_search_by(|(n, _)| n.cmp(&name))
+            .ok()
+            .map(|i| SORTED_NAMES[i].1)
+    }
+
+    // the table can be used in constant expressions
+    const FIRST: &str = SORTED_NAMES[0].0;
+
+    #[test]
+    fn test() {
+        assert_eq!(
+            SORTED_NAMES,
+            [
+                ("(u8,bool)", 5),
+                ("String", 4),
+                ("Vec::<u16>", 1),
+                ("[u8;2]", 3),
+                ("i32", 2),
+                ("u8", 0),
+            ]
+        );
+        assert!(SORTED_NAMES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(FIRST, "(u8,bool)");
+        assert_eq!(index_of("u8"), Some(0));
+        assert_eq!(index_of("Vec::<u16>"), Some(1));
+        assert_eq!(index_of("String"), Some(4));
+        assert_eq!(index_of("u16"), None);
+        assert_eq!(i32::zero(), 0);
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod name_fn {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    #[trait_gen(T -> u8, i64 #[cfg(feature = "no_such_feature")], Vec<f32>, (u8, char); name_fn = name_of)]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    // the function can be evaluated in constant contexts
+    const NAMES: [&str; 5] = [name_of(0), name_of(1), name_of(2), name_of(3), name_of(4)];
+    const _: () = assert!(name_of(0).len() == 2);
+
+    #[test]
+    fn test() {
+        // the position in the list is kept when a type is disabled
+        assert_eq!(NAMES, ["u8", "", "Vec::<f32>", "(u8,char)", ""]);
+        assert_eq!(name_of(usize::MAX), "");
+        assert_eq!(<(u8, char)>::zero(), (0, '\0'));
+    }
+}
+
+mod strategies {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    /// Minimal stand-in for the strategy trait of a property-testing crate.
+    trait Strategy {
+        type Value;
+        fn sample(&self, seed: u8) -> Self::Value;
+    }
+
+    struct Seeded<V>(fn(u8) -> V);
+
+    impl<V> Strategy for Seeded<V> {
+        type Value = V;
+        fn sample(&self, seed: u8) -> V {
+            (self.0)(seed)
+        }
+    }
+
+    #[trait_gen(T -> u16, i64, f32; strategies = arb(impl Strategy<Value = T> => Seeded(T::from)))]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    // only generated when the `in_format` feature is enabled
+    #[trait_gen(T -> u8, Vec<u8>; strategies = gated(T => T::default()), strategy_feature = "in_format")]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    fn check<S: Strategy>(strategy: S) -> bool
+    where
+        S::Value: Zero + PartialOrd,
+    {
+        (1..10).all(|seed| strategy.sample(seed) > S::Value::zero())
+    }
+
+    #[test]
+    fn test() {
+        assert!(check(arb::u16()));
+        assert!(check(arb::i64()));
+        assert!(check(arb::f32()));
+        assert_eq!(arb::f32().sample(3), 3.0);
+    }
+
+    #[cfg(feature = "in_format")]
+    #[test]
+    fn test_gated() {
+        assert_eq!(gated::u8(), 0);
+        assert!(gated::vec().is_empty());
+    }
+}
+
+mod doc_example {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    /// Zero value of the type.
+    #[trait_gen(T -> u8, i64, f32; doc_example = "assert_eq!(${T}::default(), 0 as ${T});")]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::zero(), 0);
+        assert_eq!(i64::zero(), 0);
+        assert_eq!(f32::zero(), 0.0);
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod assert_bounds {
+    use conditional_trait_gen::trait_gen;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    // `Rc<u8>` isn't checked, since it's disabled
+    #[trait_gen(T -> u8, String, Arc<u32>, Rc<u8> #[cfg(feature = "no_such_feature")]; assert_bounds)]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    trait Named {
+        fn name() -> &'static str;
+    }
+
+    #[trait_gen(T -> u8, Rc<u8>, Vec<&'static str>; assert_bounds(Unpin + Clone + 'static))]
+    impl Named for T {
+        fn name() -> &'static str {
+            "${T}"
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(*Arc::<u32>::zero(), 0);
+        assert_eq!(String::zero(), "");
+        assert_eq!(<Rc<u8>>::name(), "Rc::<u8>");
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod as_any {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Meter(f64);
+
+    impl Default for Meter {
+        fn default() -> Self {
+            Meter(0.0)
+        }
+    }
+
+    #[trait_gen(T -> u8, i64 #[cfg(feature = "no_such_feature")], Meter, Vec<&'static str>; as_any = pub(crate) Erased)]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    #[test]
+    fn test() {
+        let values: Vec<Box<dyn Erased>> = vec![
+            Box::new(u8::zero()),
+            Box::new(Meter(2.5)),
+            Box::new(vec!["a", "b"]),
+        ];
+        let names = values.iter().map(|v| v.type_name()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [Some("u8"), Some("Meter"), Some("Vec::<&'static str>")]
+        );
+        assert!(values[1].is_named("Meter"));
+        assert!(!values[1].is_named("u8"));
+        // disabled type
+        assert!(!values[0].is_named("i64"));
+        assert_eq!(values[1].downcast_ref::<Meter>(), Some(&Meter(2.5)));
+        assert_eq!(values[2].downcast_ref::<Vec<&str>>().map(Vec::len), Some(2));
+        assert_eq!(values[0].downcast_ref::<Meter>(), None);
+        assert_eq!(values[0].as_any().downcast_ref::<u8>(), Some(&0));
+    }
+}
+
+mod tag_order {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    #[trait_gen(T -> u8, bool, i64, char; type_enum = Kind, tag_trait = Tagged, tag_base = 10, tag_order = alphabetical)]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    // same types in another order give the same values
+    mod reordered {
+        use conditional_trait_gen::trait_gen;
+
+        pub trait Zero {
+            fn zero() -> Self;
+        }
+
+        #[trait_gen(T -> i64, char, u8, bool; type_enum = pub(crate) Kind, tag_const = pub(crate), tag_order = alphabetical)]
+        impl Zero for T {
+            fn zero() -> Self {
+                Default::default()
+            }
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(
+            [
+                Kind::Bool as u8,
+                Kind::Char as u8,
+                Kind::I64 as u8,
+                Kind::U8 as u8
+            ],
+            [0, 1, 2, 3]
+        );
+        assert_eq!(Kind::from_index(3), Some(Kind::U8));
+        assert_eq!(Kind::name_of_index(0), Some("bool"));
+        assert_eq!(Kind::I64.index_of(), 2);
+        assert_eq!(<u8 as Tagged>::TAG, 13);
+        assert_eq!(<bool as Tagged>::TAG, 10);
+        assert_eq!(reordered::Kind::U8.index_of(), Kind::U8.index_of());
+        assert_eq!(reordered::Kind::name_of_index(1), Some("char"));
+        assert_eq!((reordered::BOOL_TAG, reordered::U8_TAG), (0, 3));
+        assert_eq!(u8::zero(), <u8 as reordered::Zero>::zero());
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod enum_all {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    #[trait_gen(T -> u8, i64 #[cfg(feature = "no_such_feature")], f32, char; type_enum = pub(crate) Kind, enum_all)]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    trait One {
+        fn one() -> Self;
+    }
+
+    #[trait_gen(T -> u8, bool, char; type_enum = Sorted, enum_all, tag_order = alphabetical)]
+    impl One for T {
+        fn one() -> Self {
+            Default::default()
+        }
+    }
+
+    #[test]
+    fn test() {
+        // the disabled type is skipped
+        let kinds = Kind::all().collect::<Vec<_>>();
+        assert_eq!(kinds, [Kind::U8, Kind::F32, Kind::Char]);
+        let names = Kind::all()
+            .map(|kind| Kind::name_of_index(kind.index_of()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["u8", "f32", "char"]);
+        // in the order of the indices
+        let sorted = Sorted::all().collect::<Vec<_>>();
+        assert_eq!(sorted, [Sorted::Bool, Sorted::Char, Sorted::U8]);
+        assert_eq!((u8::zero(), bool::one()), (0, false));
+    }
+}
+
+mod bench_fn {
+    use conditional_trait_gen::trait_gen;
+    use std::collections::{BTreeSet, VecDeque};
+
+    /// Measures the operation on clones of the value, here by adding its results.
+    fn measure<V: Clone>(value: &V, operation: fn(V) -> usize) -> usize {
+        (0..4).map(|_| operation(value.clone())).sum()
+    }
+
+    // the `#[test]` attribute is kept on each generated function
+    #[trait_gen(T -> Vec<u8>, VecDeque<u8>, BTreeSet<u8>; bench_fn)]
+    #[test]
+    fn clone_len() {
+        let value = [1, 2, 3].into_iter().collect::<T>();
+        assert_eq!(measure(&value, |v: T| v.len()), 12);
+    }
+
+    #[test]
+    fn test() {
+        // the functions are named after the types
+        clone_len_vec();
+        clone_len_vec_deque();
+        clone_len_btree_set();
+    }
+}
+
+// `no_such_feature` isn't a feature of the crate, so it's never enabled
+#[allow(unexpected_cfgs)]
+mod enum_try_from {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    #[trait_gen(T -> u8, i64 #[cfg(feature = "no_such_feature")], f32; type_enum = Kind, enum_try_from, enum_all)]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    #[test]
+    fn test() {
+        // roundtrip
+        for kind in Kind::all() {
+            assert_eq!(Kind::try_from(kind.index_of()), Ok(kind));
+        }
+        assert_eq!(Kind::try_from(2), Ok(Kind::F32));
+        // disabled type and out of range
+        assert_eq!(Kind::try_from(1), Err(1));
+        let kind: Result<Kind, _> = usize::MAX.try_into();
+        assert_eq!(kind, Err(usize::MAX));
+        assert_eq!(f32::zero(), 0.0);
+    }
+}
+
+mod doctests {
+    use conditional_trait_gen::trait_gen;
+
+    trait Zero {
+        fn zero() -> Self;
+    }
+
+    // the module is only compiled by rustdoc, which isn't used for the integration tests
+    #[trait_gen(T -> u8, f64; doctests = zero_doctests("assert_eq!(${T}::default(), 0 as ${T});"))]
+    impl Zero for T {
+        fn zero() -> Self {
+            Default::default()
+        }
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(u8::zero(), 0);
+        assert_eq!(f64::zero(), 0.0);
+    }
+}